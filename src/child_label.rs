@@ -0,0 +1,32 @@
+use bevy::prelude::*;
+
+// Keeps the text child marked `marker` of `entity` showing `message`,
+// spawned on first use. Map objects are saved without their children, so
+// labels are made lazily like the conveyor stripes.
+pub fn update<M: Component>(commands: &mut Commands,
+                            label_q: &mut Query<&mut Text, With<M>>,
+                            entity: Entity,
+                            children: Option<&Children>,
+                            message: String,
+                            style: TextStyle,
+                            offset: Vec3,
+                            marker: M) {
+    let label = children.and_then(|c| c.iter().find(|child| label_q.contains(**child)));
+    if let Some(label) = label {
+        let mut text = label_q.get_mut(*label).unwrap();
+        if text.sections[0].value != message {
+            text.sections[0].value = message;
+        }
+    } else {
+        commands.entity(entity).with_children(|children| {
+            children.spawn((
+                marker,
+                Text2dBundle {
+                    text: Text::from_section(message, style).with_alignment(TextAlignment::Center),
+                    transform: Transform::from_translation(offset),
+                    ..default()
+                },
+            ));
+        });
+    }
+}
@@ -0,0 +1,159 @@
+use serde::{Serialize, Deserialize};
+use bevy::prelude::*;
+use rand::prelude::*;
+use std::collections::HashMap;
+
+use crate::cmp_artillery_auto::ArtilleryAuto;
+use crate::cmp_breakable::Breakable;
+use crate::cmp_breakable_sync::BreakableSync;
+use crate::cmp_combat::Status;
+use crate::cmp_combat_unit::Unit;
+use crate::cmp_combat_unit::UnitDef;
+use crate::cmp_combat_unit::UnitRegistry;
+use crate::cmp_economy;
+use crate::cmp_economy::Controller;
+use crate::cmp_economy::EconomyConfig;
+use crate::cmp_economy::Funds;
+use crate::cmp_gate_generic::SpawnBall;
+use crate::cmp_team::Team;
+use crate::cmp_team::TeamConfig;
+
+// enemy units closer than this to one of our walls count as a threat
+const THREAT_RANGE: f32 = 500.0;
+
+#[derive(Reflect, FromReflect, Default, Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    // seconds between decisions
+    fn interval(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 3.0,
+            Difficulty::Normal => 1.5,
+            Difficulty::Hard => 0.5,
+        }
+    }
+
+    // chance of buying something random instead of the best pick
+    fn mistake(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.4,
+            Difficulty::Normal => 0.15,
+            Difficulty::Hard => 0.0,
+        }
+    }
+}
+
+// What one team sees of the board.
+#[derive(Default, Debug)]
+pub struct Board {
+    // hp of enemy units pushing on our walls
+    pub threat: f32,
+    // enemy auto artillery on the map
+    pub coverage: f32,
+    pub own_units: usize,
+    pub enemy_units: usize,
+}
+
+impl Board {
+    // cheap damage answers a push, bulk survives artillery
+    fn score(&self, def: &UnitDef) -> f32 {
+        let attack_weight = 1.0 + self.threat / 100.0;
+        let hp_weight = 0.1 * (1.0 + self.coverage);
+
+        (def.attack * attack_weight + def.hp * hp_weight) / def.cost.max(1.0)
+    }
+}
+
+
+fn evaluate(team: Team,
+            team_config: &TeamConfig,
+            structure_q: &Query<(&Team, &Transform), Or<(With<Breakable>, With<BreakableSync>)>>,
+            unit_q: &Query<(&Team, &Transform, &Status), With<Unit>>,
+            artillery_q: &Query<&Team, With<ArtilleryAuto>>) -> Board {
+    let mut board = Board::default();
+
+    let walls: Vec<Vec2> = structure_q.iter()
+        .filter(|(t, _)| **t == team)
+        .map(|(_, transform)| transform.translation.truncate())
+        .collect();
+
+    for (unit_team, transform, status) in unit_q.iter() {
+        if *unit_team == team {
+            board.own_units += 1;
+            continue;
+        }
+        if ! team_config.hostile(team, *unit_team) { continue; }
+
+        board.enemy_units += 1;
+        let pos = transform.translation.truncate();
+        if walls.iter().any(|wall| wall.distance(pos) < THREAT_RANGE) {
+            board.threat = board.threat + status.hp.max(0.0);
+        }
+    }
+
+    board.coverage = artillery_q.iter()
+        .filter(|t| **t != team && team_config.hostile(team, **t))
+        .count() as f32;
+
+    board
+}
+
+
+pub fn system(
+    time: Res<Time>,
+    config: Res<EconomyConfig>,
+    team_config: Res<TeamConfig>,
+    registry: Res<UnitRegistry>,
+    mut funds: ResMut<Funds>,
+    mut event: EventWriter<SpawnBall>,
+    mut cooldown: Local<HashMap<Team, f32>>,
+    structure_q: Query<(&Team, &Transform), Or<(With<Breakable>, With<BreakableSync>)>>,
+    unit_q: Query<(&Team, &Transform, &Status), With<Unit>>,
+    artillery_q: Query<&Team, With<ArtilleryAuto>>,
+) {
+    if ! config.enabled { return; }
+
+    let mut rng = rand::thread_rng();
+    for player in config.players.iter().filter(|p| p.controller == Controller::Ai) {
+        let difficulty = player.difficulty;
+
+        let wait = cooldown.entry(player.team).or_insert(0.0);
+        *wait = *wait - time.delta_seconds();
+        if *wait > 0.0 { continue; }
+        *wait = difficulty.interval();
+
+        let board = if difficulty == Difficulty::Easy {
+            Board::default()
+        } else {
+            evaluate(player.team, &team_config, &structure_q, &unit_q, &artillery_q)
+        };
+
+        let budget = funds.get(player.team);
+        let roster: Vec<&UnitDef> = config.roster.iter().filter_map(|name| registry.get(name)).collect();
+        let affordable: Vec<&UnitDef> = roster.iter().cloned().filter(|def| def.cost <= budget).collect();
+        if affordable.len() == 0 { continue; }
+
+        let by_score = |a: &&&UnitDef, b: &&&UnitDef| board.score(a).partial_cmp(&board.score(b)).unwrap();
+        let best = *roster.iter().max_by(by_score).unwrap();
+
+        let pick = if rng.gen::<f32>() < difficulty.mistake() {
+            *affordable.choose(&mut rng).unwrap()
+        } else if best.cost <= budget {
+            best
+        } else if difficulty == Difficulty::Hard && board.threat == 0.0 {
+            // nothing is pushing, save up for the best unit
+            continue;
+        } else {
+            *affordable.iter().max_by(by_score).unwrap()
+        };
+
+        let name = pick.name.clone();
+        cmp_economy::buy(&mut funds, &config, &registry, &mut event, player.team, &name);
+    }
+}
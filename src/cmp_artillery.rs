@@ -4,6 +4,7 @@ use bevy_rapier2d::prelude::*;
 use crate::cmp_bbsize::BBSize;
 use crate::cmp_game_asset::GameAsset;
 use crate::cmp_ball::Ball;
+use crate::contacts;
 
 const DEFAULT_RADIUS: f32 = 512.0 / 2.0;
 const DEFAULT_RANGE: f32 = 0.25 * std::f32::consts::PI;
@@ -191,8 +192,8 @@ pub fn system_fire(
     artillery_q: Query<(Entity, &Transform, &BBSize, &Artillery), Without<Ball>>,
 ) {
     for (artillery_e, artillery_transform, bbsize, artillery) in artillery_q.iter() {
-        for (ball_e, mut ball_transform, mut ball_velocity, ball) in ball_q.iter_mut() {
-            if rapier_context.intersection_pair(artillery_e, ball_e) == Some(true) {
+        for other in contacts::intersecting(&rapier_context, artillery_e) {
+            if let Ok((_, mut ball_transform, mut ball_velocity, ball)) = ball_q.get_mut(other) {
                 let dir = Quat::from_rotation_z(artillery.angle).mul_vec3(Vec3::new(1.0, 0.0, 0.0));
                 let dist = bbsize.x / 2.0 * artillery_transform.scale.x + ball.radius + 1.0;
                 ball_transform.translation = artillery_transform.translation + dir * dist;
@@ -1,27 +1,64 @@
 use serde::{Serialize, Deserialize};
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
-use crate::cmp_ball_bomb::BallBombBundle;
+use crate::cmp_ball_bomb;
+use crate::cmp_ball_bomb::Ammo;
+use crate::cmp_ball_bomb::AMMO_LIST;
 use crate::cmp_bbsize::BBSize;
 use crate::cmp_game_asset::GameAsset;
 use crate::cmp_ball::Ball;
 use crate::cmp_explosion::ExplosionBundle;
 use crate::cmp_fuse_time::FuseTime;
 
-use crate::cmp_combat::Player1;
-use crate::cmp_combat::Player2;
+use crate::cmp_combat::Status;
+use crate::cmp_team::Team;
+use crate::cmp_team::TeamConfig;
+use crate::cmp_trajectory;
+use bevy_inspector_egui::bevy_egui::{egui, EguiContexts};
 
 const DEFAULT_RADIUS: f32 = 512.0 / 2.0;
 const DEFAULT_RANGE: f32 = 0.25 * std::f32::consts::PI;
 const DETECTION_RANGE: f32 = 700.0;
+const MUZZLE_SPEED: f32 = 400.0;
 const COOL_TIME: f32 = 5.0;
+// fire once the barrel is this close to the firing solution
+const AIM_TOLERANCE: f32 = 0.02;
+// segments of the flight arc checked for walls
+const LOS_SEGMENTS: usize = 8;
+
+#[derive(Reflect, FromReflect, Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
+pub enum TargetPriority {
+    Closest,
+    // lowest hp left
+    Weakest,
+    // highest attack
+    Strongest,
+    HighestHp,
+}
+
+impl Default for TargetPriority {
+    fn default() -> Self {
+        TargetPriority::Closest
+    }
+}
+
+fn default_detection_range() -> f32 { DETECTION_RANGE }
+fn default_muzzle_speed() -> f32 { MUZZLE_SPEED }
 
-#[derive(Component, Reflect, Clone, Serialize, Deserialize, Debug, Default)]
+#[derive(Component, Reflect, Clone, PartialEq, Serialize, Deserialize, Debug, Default)]
 pub struct ArtilleryAuto {
     pub angvel: f32,
     pub angle: f32,
     pub angle_range: (f32, f32),
     pub cool_time: f32,
+    #[serde(default = "default_detection_range")]
+    pub detection_range: f32,
+    #[serde(default = "default_muzzle_speed")]
+    pub muzzle_speed: f32,
+    #[serde(default)]
+    pub priority: TargetPriority,
+    #[serde(default)]
+    pub ammo: Ammo,
     }
 
 #[derive(Component, Reflect, Clone, Serialize, Deserialize, Debug)]
@@ -56,8 +93,8 @@ impl From<(Quat, &GameAsset)> for ArtilleryAutoBarrelBundle {
 
 
 #[derive(Bundle)]
-pub struct ArtilleryAutoBaseBundle<T: Component + Default> {
-    player: T,
+pub struct ArtilleryAutoBaseBundle {
+    team: Team,
     artillery: ArtilleryAuto,
     fuse_time: FuseTime,
     bbsize: BBSize,
@@ -69,11 +106,11 @@ pub struct ArtilleryAutoBaseBundle<T: Component + Default> {
 }
 
 
-impl<T: Component + Default> From<&GameAsset> for ArtilleryAutoBaseBundle<T> {
+impl From<&GameAsset> for ArtilleryAutoBaseBundle {
     fn from(game_assets: &GameAsset) -> Self {
         let sprite_handle = game_assets.image_handles.get("artillery_frag1").unwrap();
         Self {
-            player: T::default(),
+            team: Team::default(),
             artillery: ArtilleryAuto::default(),
             fuse_time: FuseTime{timer: Timer::from_seconds(0.0, TimerMode::Once)},
             bbsize: BBSize{x: DEFAULT_RADIUS * 2.0, y: DEFAULT_RADIUS * 2.0},
@@ -92,12 +129,13 @@ impl<T: Component + Default> From<&GameAsset> for ArtilleryAutoBaseBundle<T> {
     }
 }
 
-impl<T: Component + Default> From<(Vec3, Vec3, ArtilleryAuto, &GameAsset)> for ArtilleryAutoBaseBundle<T> {
-    fn from(tuple: (Vec3, Vec3, ArtilleryAuto, &GameAsset)) -> Self {
-        let (translation, scale, artillery, game_assets) = tuple;
+impl From<(Vec3, Vec3, ArtilleryAuto, Team, &GameAsset)> for ArtilleryAutoBaseBundle {
+    fn from(tuple: (Vec3, Vec3, ArtilleryAuto, Team, &GameAsset)) -> Self {
+        let (translation, scale, artillery, team, game_assets) = tuple;
 
         let mut bundle = ArtilleryAutoBaseBundle::from(game_assets);
         bundle.artillery = artillery;
+        bundle.team = team;
         bundle.sprite_bundle.transform.translation = translation;
         bundle.sprite_bundle.transform.scale = scale;
 
@@ -134,45 +172,31 @@ pub fn handle_user_input(
                 angle: 0.0,
                 angle_range: (-DEFAULT_RANGE, DEFAULT_RANGE),
                 cool_time: COOL_TIME,
+                detection_range: DETECTION_RANGE,
+                muzzle_speed: MUZZLE_SPEED,
+                priority: TargetPriority::Closest,
+                ammo: Ammo::Bomb,
             };
 
-            if let MapObject::ArtilleryAutoP1 = map_object {
-                if buttons.just_pressed(MouseButton::Left) {
-                    let mut entity = commands.spawn(ArtilleryAutoBaseBundle::<Player1>::from((
-                                Vec3::from((world_position.translation, 2.0)),
-                                Vec3::ONE,
-                                artillery,
-                                game_assets,
-                                )));
-                    entity.with_children(|children| {
-                        children.spawn(ArtilleryAutoBarrelBundle::from((Quat::from_rotation_z(0.0), game_assets)));
-                    });
-                    entity.insert(MapObject::ArtilleryAutoP1);
-                    *edit_context = EditContext::Edit(MapObject::ArtilleryAutoP1, vec![entity.id()], EditTool::Select);
-                }
-            }
-
-            else if let MapObject::ArtilleryAutoP2 = map_object {
-                if buttons.just_pressed(MouseButton::Left) {
-                    let mut entity = commands.spawn(ArtilleryAutoBaseBundle::<Player2>::from((
-                                Vec3::from((world_position.translation, 2.0)),
-                                Vec3::ONE,
-                                artillery,
-                                game_assets,
-                                )));
-                    entity.with_children(|children| {
-                        children.spawn(ArtilleryAutoBarrelBundle::from((Quat::from_rotation_z(0.0), game_assets)));
-                    });
-                    entity.insert(MapObject::ArtilleryAutoP2);
-                    *edit_context = EditContext::Edit(MapObject::ArtilleryAutoP2, vec![entity.id()], EditTool::Select);
-                }
+            if let MapObject::ArtilleryAuto(team) = map_object {
+                let mut entity = commands.spawn(ArtilleryAutoBaseBundle::from((
+                            Vec3::from((world_position.translation, 2.0)),
+                            Vec3::ONE,
+                            artillery,
+                            team,
+                            game_assets,
+                            )));
+                entity.with_children(|children| {
+                    children.spawn(ArtilleryAutoBarrelBundle::from((Quat::from_rotation_z(0.0), game_assets)));
+                });
+                entity.insert(MapObject::ArtilleryAuto(team));
+                *edit_context = EditContext::Edit(MapObject::ArtilleryAuto(team), vec![entity.id()], EditTool::Select);
             }
         }
     }
 
     match edit_context.clone() {
-        EditContext::Edit(MapObject::ArtilleryAutoP1, _, EditTool::Select) |
-        EditContext::Edit(MapObject::ArtilleryAutoP2, _, EditTool::Select) => {
+        EditContext::Edit(MapObject::ArtilleryAuto(_), _, EditTool::Select) => {
             if let EditContext::Edit(map_object, entities, _) = edit_context.clone() {
                 if keys.pressed(KeyCode::Key1) {
                     *edit_context = EditContext::Edit(map_object , entities, EditTool::Custom1);
@@ -183,8 +207,7 @@ pub fn handle_user_input(
     }
 
     match edit_context.clone() {
-        EditContext::Edit(MapObject::ArtilleryAutoP1, entities, EditTool::Custom1) |
-        EditContext::Edit(MapObject::ArtilleryAutoP2, entities, EditTool::Custom1) => {
+        EditContext::Edit(MapObject::ArtilleryAuto(_), entities, EditTool::Custom1) => {
             let entity = entities[0];
             if let Ok((children, base_transform, mut artillery)) = artillery_frag1.get_mut(entity) {
                 let mut barrel_transform = artillery_frag2.get_mut(*children.iter().next().unwrap()).unwrap();
@@ -204,16 +227,107 @@ pub fn handle_user_input(
 
 }
 
-pub fn find_nearest<T: Component>(ball_q: &Query<(Entity, &mut Transform, &mut Velocity, &Ball), (With<T>, Without<Barrel>)>,
-                                  translation: &Vec3) -> Option<(Entity, f32)> {
-    let mut min: Option<(Entity, f32)> = None;
-    for (entity, ball_t, _, _) in ball_q {
-        let distance = translation.truncate().distance(ball_t.translation.truncate());
-        if min.is_none() || min.unwrap().1 >= distance{
-            min = Some((entity, distance));
+pub fn edit_window(
+    mut egui_contexts: EguiContexts,
+    edit_context: Res<EditContext>,
+    mut artillery_q: Query<&mut ArtilleryAuto>,
+    ) {
+    if let EditContext::Edit(MapObject::ArtilleryAuto(_), pick, _) = edit_context.clone() {
+        if pick.len() == 0 { return; }
+
+        if let Ok(mut artillery) = artillery_q.get_mut(pick[0]) {
+            let mut a = artillery.clone();
+
+            egui::Window::new("ArtilleryAuto").show(egui_contexts.ctx_mut(), |ui: &mut egui::Ui| {
+                ui.horizontal(|ui: &mut egui::Ui| {
+                    ui.label("range");
+                    ui.add(egui::DragValue::new(&mut a.detection_range).clamp_range(0.0..=5000.0));
+                    ui.label("speed");
+                    ui.add(egui::DragValue::new(&mut a.muzzle_speed).clamp_range(10.0..=5000.0));
+                    ui.label("cool time");
+                    ui.add(egui::DragValue::new(&mut a.cool_time).speed(0.1).clamp_range(0.0..=60.0));
+                });
+                ui.horizontal(|ui: &mut egui::Ui| {
+                    ui.label("priority");
+                    ui.selectable_value(&mut a.priority, TargetPriority::Closest, "Closest");
+                    ui.selectable_value(&mut a.priority, TargetPriority::Weakest, "Weakest");
+                    ui.selectable_value(&mut a.priority, TargetPriority::Strongest, "Strongest");
+                    ui.selectable_value(&mut a.priority, TargetPriority::HighestHp, "HighestHp");
+                });
+                ui.horizontal(|ui: &mut egui::Ui| {
+                    ui.label("ammo");
+                    for ammo in AMMO_LIST {
+                        ui.selectable_value(&mut a.ammo, ammo, ammo.name());
+                    }
+                });
+            });
+
+            if a != *artillery {
+                *artillery = a;
+            }
+        }
+    }
+}
+
+
+// lower is better
+fn priority_key(priority: TargetPriority, distance: f32, status: Option<&Status>) -> f32 {
+    match (priority, status) {
+        (TargetPriority::Closest, _) | (_, None) => distance,
+        (TargetPriority::Weakest, Some(s)) => s.hp,
+        (TargetPriority::Strongest, Some(s)) => -s.attack,
+        (TargetPriority::HighestHp, Some(s)) => -s.hp,
+    }
+}
+
+// true when no wall is in the way along the flight arc
+fn line_of_sight(rapier_context: &RapierContext, points: &Vec<Vec2>, target: Entity) -> bool {
+    let filter = QueryFilter::new()
+        .exclude_sensors()
+        .groups(CollisionGroups::new(Group::GROUP_13, Group::GROUP_1));
+
+    for segment in points.windows(2) {
+        let delta = segment[1] - segment[0];
+        let length = delta.length();
+        if length <= 0.0 { continue; }
+
+        if let Some((entity, _)) = rapier_context.cast_ray(segment[0], delta / length, length, true, filter) {
+            if entity != target { return false; }
+        }
+    }
+    true
+}
+
+// Picks the best hostile target in range that can be hit, and the angle to hit it at.
+pub fn find_target(ball_q: &Query<(Entity, &Transform, &Velocity, &Ball, &Team, Option<&Status>), Without<Barrel>>,
+                   rapier_context: &RapierContext,
+                   team_config: &TeamConfig,
+                   gravity: Vec2,
+                   team: Team,
+                   origin: Vec2,
+                   artillery: &ArtilleryAuto) -> Option<(Entity, f32)> {
+    let mut candidates: Vec<(f32, Entity, Vec2, Vec2)> = vec![];
+    for (entity, ball_t, velocity, _, ball_team, status) in ball_q.iter() {
+        if ! team_config.hostile(team, *ball_team) { continue; }
+
+        let pos = ball_t.translation.truncate();
+        let distance = origin.distance(pos);
+        if distance > artillery.detection_range { continue; }
+
+        candidates.push((priority_key(artillery.priority, distance, status), entity, pos, velocity.linvel));
+    }
+    candidates.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+    for (_, entity, pos, velocity) in candidates {
+        let Some((angle, time, _)) = cmp_trajectory::lead(origin, pos, velocity, artillery.muzzle_speed, gravity) else { continue; };
+
+        let launch = Vec2::new(angle.cos(), angle.sin()) * artillery.muzzle_speed;
+        let points = cmp_trajectory::arc(origin, launch, gravity, time, time / LOS_SEGMENTS as f32);
+        if line_of_sight(rapier_context, &points, entity) {
+            return Some((entity, angle));
         }
     }
-    min
+    None
 }
 
 fn normalized_angle(angle: f32) -> f32 {
@@ -227,47 +341,34 @@ fn normalized_angle(angle: f32) -> f32 {
     angle
 }
 
-pub fn system<T1: Component + Default, T2: Component>(
+pub fn system(
     mut commands: Commands,
     time: Res<Time>,
     game_assets: Res<GameAsset>,
-    mut ball_q: Query<(Entity, &mut Transform, &mut Velocity, &Ball), (With<T2>, Without<Barrel>)>,
-    mut artillery_frag1: Query<(Entity, &Children, &Transform, &mut FuseTime, &mut ArtilleryAuto), (With<T1>, Without<Barrel>, Without<Ball>)>,
+    team_config: Res<TeamConfig>,
+    rapier_context: Res<RapierContext>,
+    rapier_configuration: Res<RapierConfiguration>,
+    ball_q: Query<(Entity, &Transform, &Velocity, &Ball, &Team, Option<&Status>), Without<Barrel>>,
+    mut artillery_frag1: Query<(Entity, &Children, &Transform, &mut FuseTime, &mut ArtilleryAuto, &Team), (Without<Barrel>, Without<Ball>)>,
     mut artillery_frag2: Query<&mut Transform, (With<Barrel>, Without<Ball>)>,
 ) {
     let game_assets = game_assets.into_inner();
 
-    for (entity, children, transform, mut fuse_time, mut artillery) in artillery_frag1.iter_mut() {
+    for (entity, children, transform, mut fuse_time, mut artillery, team) in artillery_frag1.iter_mut() {
         let child = children.iter().next().unwrap();
         let mut barrel_transform = artillery_frag2.get_mut(child.to_owned()).unwrap();
 
         let mut angle_delta: f32 = artillery.angvel * time.delta_seconds();
         let mut angle_target: Option<f32> = None;
-        let mut distance: f32 = std::f32::MAX;
-        let nearest = find_nearest(&ball_q, &transform.translation);
-
-        if let Some((entity, dist)) = nearest {
-            if dist <= DETECTION_RANGE {
-                let (entity, ball_t, _, _) = ball_q.get(entity).unwrap();
-                let angle = {
-                    let dir = (ball_t.translation.truncate() - transform.translation.truncate()).normalize();
-                    let q1 = Quat::from_rotation_z(artillery.angle);
-                    let q2 = Quat::from_rotation_z(Vec2::new(1.0, 0.0).angle_between(dir));
-                    let q_between = q1.conjugate() * q2;
+        let origin = transform.translation.truncate();
+        let target = find_target(&ball_q, &rapier_context, &team_config, rapier_configuration.gravity, *team, origin, &artillery);
 
-                    let angle = q_between.to_euler(EulerRot::ZYX).0;
-                    angle
-                };
+        if let Some((_, angle)) = target {
+            let angle = normalized_angle(angle - artillery.angle);
+            angle_target = Some(angle);
 
-                angle_target = Some(angle);
-                println!("angle_target{:?}", angle_target);
-
-                angle_delta = normalized_angle(angle);
-                let clamp = artillery.angvel.abs() * time.delta_seconds();
-
-                angle_delta = angle_delta.clamp(-clamp, clamp);
-            }
-            distance = dist;
+            let clamp = artillery.angvel.abs() * time.delta_seconds();
+            angle_delta = angle.clamp(-clamp, clamp);
         }
 
         // set angle
@@ -289,13 +390,11 @@ pub fn system<T1: Component + Default, T2: Component>(
         // fire
         {
             fuse_time.timer.tick(time.delta());
-            if distance <= DETECTION_RANGE && angle_target.is_some() && angle_target.unwrap().abs() < 0.0001 {
-                if fuse_time.timer.finished() { 
-                    let dir = Quat::from_rotation_z(artillery.angle).mul_vec3(Vec3::new(1.0, 0.0, 0.0));
-                    let bundle = BallBombBundle::<T1>::from((transform.translation.truncate(), dir.truncate() * 400.0, game_assets));
-                    commands.spawn(bundle);
-                    fuse_time.timer = Timer::from_seconds(artillery.cool_time, TimerMode::Once);
-                }
+            let on_target = angle_target.map_or(false, |a| (a - angle_delta).abs() < AIM_TOLERANCE);
+            if on_target && fuse_time.timer.finished() {
+                let dir = Vec2::new(artillery.angle.cos(), artillery.angle.sin());
+                cmp_ball_bomb::spawn_shell(&mut commands, artillery.ammo, origin, dir * artillery.muzzle_speed, *team, game_assets);
+                fuse_time.timer = Timer::from_seconds(artillery.cool_time, TimerMode::Once);
             }
         }
     }
@@ -310,25 +409,10 @@ pub fn system_fire(
 }
 
 
-fn file_name_string<T>() -> String {
-    const FILE_PREFIX: &str = "/artillery_auto_";
-    const FILE_EXT: &str = ".map";
-
-    let type_name = std::any::type_name::<T>();
-    let pure_type_name = type_name.split("::").last().unwrap_or(type_name);
-
-    (FILE_PREFIX.to_string() + pure_type_name + FILE_EXT)
-}
-
-use std::any::TypeId;
-fn get_map_object<T: 'static>() -> MapObject {
-    if (TypeId::of::<T>() == TypeId::of::<Player1>()) {
-        MapObject::ArtilleryAutoP1
-    }
-    else {
-        MapObject::ArtilleryAutoP2
-    } 
-}
+const FILE_NAME: &str = "/artillery_auto.map";
+// written before teams existed, one file per player
+const LEGACY_FILE_NAMES: [(&str, Team); 2] = [("/artillery_auto_Player1.map", Team(0)),
+                                              ("/artillery_auto_Player2.map", Team(1))];
 
 use crate::ev_despawn;
 pub fn despawn(
@@ -348,7 +432,7 @@ pub fn despawn(
 }
 
 use crate::ev_save_load_world::LoadWorldEvent;
-pub fn load<T: Component + Default>(
+pub fn load(
     mut load_world_er: EventReader<LoadWorldEvent>,
     mut commands: Commands,
     game_assets: Res<GameAsset>,
@@ -357,41 +441,51 @@ pub fn load<T: Component + Default>(
 
     for e in load_world_er.iter() {
         let dir = e.0.clone();
+        let mut elem_list: Vec<(u32, u32, Vec3, Quat, Vec3, Team, ArtilleryAuto)> = vec![];
+
+        if let Ok(json_str) = std::fs::read_to_string(dir.clone() + FILE_NAME) {
+            elem_list = serde_json::from_str(&json_str).unwrap();
+        } else {
+            for (file_name, team) in LEGACY_FILE_NAMES {
+                if let Ok(json_str) = std::fs::read_to_string(dir.clone() + file_name) {
+                    let legacy_list: Vec<(u32, u32, Vec3, Quat, Vec3, ArtilleryAuto)> = serde_json::from_str(&json_str).unwrap();
+                    for (i, i2, t, r, s, a) in legacy_list {
+                        elem_list.push((i, i2, t, r, s, team, a));
+                    }
+                }
+            }
+        }
 
-        let json_str = std::fs::read_to_string(dir + file_name_string::<T>().as_str());
-        if let Ok(json_str) = json_str {
-            let elem_list: Vec<(u32, u32, Vec3, Quat, Vec3, ArtilleryAuto)> = serde_json::from_str(&json_str).unwrap();
-
-            for (i, i2, t, _, s, a) in elem_list {
-                let rotation = Quat::from_rotation_z(a.angle);
-                let entity2 = commands.get_or_spawn(Entity::from_raw(i2))
-                                    .insert(ArtilleryAutoBarrelBundle::from(( rotation, game_assets ))).id();
-
-                let entity = commands.get_or_spawn(Entity::from_raw(i))
-                                    .insert(ArtilleryAutoBaseBundle::<T>::from((t, s, a, game_assets)))
-                                    .insert(get_map_object::<T>())
-                                    .push_children(&[entity2]);
+        for (i, i2, t, _, s, team, a) in elem_list {
+            let rotation = Quat::from_rotation_z(a.angle);
+            let entity2 = commands.get_or_spawn(Entity::from_raw(i2))
+                                .insert(ArtilleryAutoBarrelBundle::from(( rotation, game_assets ))).id();
 
-            }
+            commands.get_or_spawn(Entity::from_raw(i))
+                    .insert(ArtilleryAutoBaseBundle::from((t, s, a, team, game_assets)))
+                    .insert(MapObject::ArtilleryAuto(team))
+                    .push_children(&[entity2]);
         }
     }
 }
 
 use crate::ev_save_load_world::SaveWorldEvent;
-pub fn save<T: Component + Default>(mut save_world_er: EventReader<SaveWorldEvent>,
-              artillery_q: Query<(Entity, &Children, &Transform, &ArtilleryAuto), With<T>>,
+pub fn save(mut save_world_er: EventReader<SaveWorldEvent>,
+              artillery_q: Query<(Entity, &Children, &Transform, &Team, &ArtilleryAuto)>,
               barrel_q: Query<Entity, With<Barrel>>,
               ) {
     for e in save_world_er.iter() {
         let dir = e.0.clone();
-        let mut artillery_list: Vec<(u32, u32, Vec3, Quat, Vec3, ArtilleryAuto)> = vec![];
+        let mut artillery_list: Vec<(u32, u32, Vec3, Quat, Vec3, Team, ArtilleryAuto)> = vec![];
 
-        for (e, c, t, a) in artillery_q.iter() {
+        for (e, c, t, team, a) in artillery_q.iter() {
             let barrel = barrel_q.get(*c.iter().next().unwrap()).unwrap();
-            artillery_list.push((e.index(), barrel.index(), t.translation, t.rotation, t.scale, a.clone()));
+            artillery_list.push((e.index(), barrel.index(), t.translation, t.rotation, t.scale, *team, a.clone()));
         }
 
-        std::fs::write(dir + file_name_string::<T>().as_str(), serde_json::to_string(&artillery_list).unwrap()).unwrap();
+        std::fs::write(dir.clone() + FILE_NAME, serde_json::to_string(&artillery_list).unwrap()).unwrap();
+        for (file_name, _) in LEGACY_FILE_NAMES {
+            let _ = std::fs::remove_file(dir.clone() + file_name);
+        }
     }
 }
-
@@ -0,0 +1,402 @@
+use serde::{Serialize, Deserialize};
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::cmp_ball_bomb;
+use crate::cmp_ball_bomb::Ammo;
+use crate::cmp_ball_bomb::AMMO_LIST;
+use crate::cmp_bbsize::BBSize;
+use crate::cmp_explosion::ExplosionBundle;
+use crate::cmp_fuse_time::FuseTime;
+use crate::cmp_game_asset::GameAsset;
+use crate::cmp_team::Team;
+use crate::cmp_trajectory;
+
+const DEFAULT_RADIUS: f32 = 512.0 / 2.0;
+const COOL_TIME: f32 = 2.0;
+const CHARGE_TIME: f32 = 1.5;
+const MIN_SPEED: f32 = 200.0;
+const MAX_SPEED: f32 = 1200.0;
+// radians per second when aimed with keys
+const AIM_SPEED: f32 = 1.5;
+const PREVIEW_DURATION: f32 = 1.5;
+const PREVIEW_STEP: f32 = 0.05;
+const METER_SIZE: Vec2 = Vec2::new(200.0, 16.0);
+
+#[derive(Reflect, FromReflect, Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
+pub enum Control {
+    // aim at the cursor, hold left to charge, right to switch ammo
+    Mouse,
+    // A/D aim, hold W to charge, S to switch ammo
+    KeysLeft,
+    // J/L aim, hold I to charge, K to switch ammo
+    KeysRight,
+}
+
+impl Default for Control {
+    fn default() -> Self {
+        Control::KeysLeft
+    }
+}
+
+#[derive(Component, Reflect, Clone, Serialize, Deserialize, Debug, Default)]
+pub struct ArtilleryManual {
+    pub angle: f32,
+    pub control: Control,
+    pub ammo: Ammo,
+    pub cool_time: f32,
+    // charge from 0.0 to 1.0, between MIN_SPEED and MAX_SPEED
+    #[serde(skip)]
+    pub power: f32,
+    #[serde(skip)]
+    pub charging: bool,
+}
+
+impl ArtilleryManual {
+    pub fn speed(&self) -> f32 {
+        MIN_SPEED + (MAX_SPEED - MIN_SPEED) * self.power
+    }
+
+    pub fn direction(&self) -> Vec2 {
+        Vec2::new(self.angle.cos(), self.angle.sin())
+    }
+}
+
+#[derive(Component, Reflect, Clone, Serialize, Deserialize, Debug)]
+pub struct ManualBarrel;
+
+// preview arc and charge meter, created in game
+#[derive(Component, Clone, Debug)]
+pub struct ManualUi {
+    preview: Entity,
+    meter: Entity,
+}
+
+#[derive(Bundle)]
+pub struct ArtilleryManualBarrelBundle {
+    barrel: ManualBarrel,
+    #[bundle]
+    sprite_bundle: SpriteBundle,
+}
+
+
+impl From<(Quat, &GameAsset)> for ArtilleryManualBarrelBundle {
+    fn from(tuple: (Quat, &GameAsset)) -> Self {
+        let (rotation, game_assets) = tuple;
+        let sprite_handle = game_assets.image_handles.get("artillery_frag2").unwrap();
+        Self {
+            barrel: ManualBarrel,
+            sprite_bundle: SpriteBundle {
+                texture: sprite_handle.clone(),
+                transform: Transform {
+                    rotation,
+                    scale: Vec3::ONE,
+                    translation: Vec3::new(0.0, 0.0, 2.0),
+                },
+                ..default()
+            },
+        }
+    }
+}
+
+
+#[derive(Bundle)]
+pub struct ArtilleryManualBaseBundle {
+    team: Team,
+    artillery: ArtilleryManual,
+    fuse_time: FuseTime,
+    bbsize: BBSize,
+    #[bundle]
+    sprite_bundle: SpriteBundle,
+}
+
+
+impl From<&GameAsset> for ArtilleryManualBaseBundle {
+    fn from(game_assets: &GameAsset) -> Self {
+        let sprite_handle = game_assets.image_handles.get("artillery_frag1").unwrap();
+        Self {
+            team: Team::default(),
+            artillery: ArtilleryManual {
+                cool_time: COOL_TIME,
+                ..default()
+            },
+            fuse_time: FuseTime{timer: Timer::from_seconds(0.0, TimerMode::Once)},
+            bbsize: BBSize{x: DEFAULT_RADIUS * 2.0, y: DEFAULT_RADIUS * 2.0},
+            sprite_bundle: SpriteBundle {
+                texture: sprite_handle.clone(),
+                transform: Transform {
+                    scale: Vec3::ONE,
+                    ..Default::default()
+                },
+                ..default()
+            },
+        }
+    }
+}
+
+impl From<(Vec3, Vec3, ArtilleryManual, Team, &GameAsset)> for ArtilleryManualBaseBundle {
+    fn from(tuple: (Vec3, Vec3, ArtilleryManual, Team, &GameAsset)) -> Self {
+        let (translation, scale, artillery, team, game_assets) = tuple;
+
+        let mut bundle = ArtilleryManualBaseBundle::from(game_assets);
+        bundle.artillery = artillery;
+        bundle.team = team;
+        bundle.sprite_bundle.transform.translation = translation;
+        bundle.sprite_bundle.transform.scale = scale;
+
+        bundle
+    }
+}
+
+
+use crate::edit_context::*;
+pub fn handle_user_input(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<MouseButton>>,
+    game_assets: Res<GameAsset>,
+    mut edit_context: ResMut<EditContext>,
+    world_position: ResMut<WorldPosition>,
+    mut artillery_q: Query<(&Children, &Transform, &mut ArtilleryManual)>,
+    mut barrel_q: Query<&mut Transform, (With<ManualBarrel>, Without<ArtilleryManual>)>,
+    ) {
+    let game_assets = game_assets.into_inner();
+
+    if buttons.just_pressed(MouseButton::Left) {
+        if let EditContext::Spawn(MapObject::ArtilleryManual(team, control)) = edit_context.clone() {
+            let artillery = ArtilleryManual {
+                control,
+                cool_time: COOL_TIME,
+                ..default()
+            };
+
+            let mut entity = commands.spawn(ArtilleryManualBaseBundle::from((
+                        Vec3::from((world_position.translation, 2.0)),
+                        Vec3::ONE,
+                        artillery,
+                        team,
+                        game_assets,
+                        )));
+            entity.with_children(|children| {
+                children.spawn(ArtilleryManualBarrelBundle::from((Quat::from_rotation_z(0.0), game_assets)));
+            });
+            entity.insert(MapObject::ArtilleryManual(team, control));
+            *edit_context = EditContext::Edit(MapObject::ArtilleryManual(team, control), vec![entity.id()], EditTool::Select);
+        }
+    }
+
+    match edit_context.clone() {
+        EditContext::Edit(map_object @ MapObject::ArtilleryManual(..), entities, EditTool::Select) => {
+            if keys.pressed(KeyCode::Key1) {
+                *edit_context = EditContext::Edit(map_object, entities, EditTool::Custom1);
+            }
+        }
+        // point the initial aim at the cursor
+        EditContext::Edit(MapObject::ArtilleryManual(..), entities, EditTool::Custom1) => {
+            if let Ok((children, base_transform, mut artillery)) = artillery_q.get_mut(entities[0]) {
+                let pos = base_transform.translation.truncate();
+                let angle = Vec2::new(1.0, 0.0).angle_between(world_position.translation - pos);
+
+                if let Some(barrel) = children.iter().find(|c| barrel_q.contains(**c)) {
+                    barrel_q.get_mut(*barrel).unwrap().rotation = Quat::from_rotation_z(angle);
+                }
+                artillery.angle = angle;
+            }
+        }
+        _ => {}
+    }
+}
+
+
+pub fn system(
+    mut commands: Commands,
+    time: Res<Time>,
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<MouseButton>>,
+    world_position: Res<WorldPosition>,
+    game_assets: Res<GameAsset>,
+    mut artillery_q: Query<(&Children, &Transform, &mut FuseTime, &mut ArtilleryManual, &Team)>,
+    mut barrel_q: Query<&mut Transform, (With<ManualBarrel>, Without<ArtilleryManual>)>,
+) {
+    let game_assets = game_assets.into_inner();
+    let dt = time.delta_seconds();
+
+    for (children, transform, mut fuse_time, mut artillery, team) in artillery_q.iter_mut() {
+        let pos = transform.translation.truncate();
+
+        let (turn, charge, released, switch) = match artillery.control {
+            Control::Mouse => {
+                artillery.angle = Vec2::new(1.0, 0.0).angle_between(world_position.translation - pos);
+                (0.0,
+                 buttons.pressed(MouseButton::Left),
+                 buttons.just_released(MouseButton::Left),
+                 buttons.just_pressed(MouseButton::Right))
+            }
+            Control::KeysLeft => {
+                (keys.pressed(KeyCode::A) as i32 as f32 - keys.pressed(KeyCode::D) as i32 as f32,
+                 keys.pressed(KeyCode::W),
+                 keys.just_released(KeyCode::W),
+                 keys.just_pressed(KeyCode::S))
+            }
+            Control::KeysRight => {
+                (keys.pressed(KeyCode::J) as i32 as f32 - keys.pressed(KeyCode::L) as i32 as f32,
+                 keys.pressed(KeyCode::I),
+                 keys.just_released(KeyCode::I),
+                 keys.just_pressed(KeyCode::K))
+            }
+        };
+
+        artillery.angle = artillery.angle + turn * AIM_SPEED * dt;
+        if let Some(barrel) = children.iter().find(|c| barrel_q.contains(**c)) {
+            barrel_q.get_mut(*barrel).unwrap().rotation = Quat::from_rotation_z(artillery.angle);
+        }
+
+        if switch {
+            let index = AMMO_LIST.iter().position(|a| *a == artillery.ammo).map_or(0, |i| i + 1);
+            artillery.ammo = AMMO_LIST[index % AMMO_LIST.len()];
+        }
+
+        fuse_time.timer.tick(time.delta());
+        if ! fuse_time.timer.finished() { continue; }
+
+        if charge {
+            artillery.charging = true;
+            artillery.power = (artillery.power + dt / CHARGE_TIME).min(1.0);
+        } else if released && artillery.charging {
+            let velocity = artillery.direction() * artillery.speed();
+            cmp_ball_bomb::spawn_shell(&mut commands, artillery.ammo, pos, velocity, *team, game_assets);
+
+            artillery.charging = false;
+            artillery.power = 0.0;
+            fuse_time.timer = Timer::from_seconds(artillery.cool_time, TimerMode::Once);
+        }
+    }
+}
+
+
+pub fn system_ui(
+    mut commands: Commands,
+    rapier_configuration: Res<RapierConfiguration>,
+    artillery_q: Query<(Entity, &Transform, &FuseTime, &ArtilleryManual, &Team, Option<&ManualUi>)>,
+    mut preview_q: Query<&mut Path>,
+    mut meter_q: Query<(&mut Transform, &mut Sprite), Without<ArtilleryManual>>,
+) {
+    for (entity, transform, fuse_time, artillery, team, ui) in artillery_q.iter() {
+        let Some(ui) = ui else {
+            let preview = commands.spawn((
+                ShapeBundle::default(),
+                Stroke::new(team.color().with_a(0.6), 3.0),
+            )).id();
+            let meter = commands.spawn(SpriteBundle::default()).id();
+            commands.entity(entity).insert(ManualUi { preview, meter });
+            continue;
+        };
+
+        let pos = transform.translation.truncate();
+        let velocity = artillery.direction() * artillery.speed();
+
+        if let Ok(mut path) = preview_q.get_mut(ui.preview) {
+            let points = cmp_trajectory::arc(pos, velocity, rapier_configuration.gravity, PREVIEW_DURATION, PREVIEW_STEP);
+            let mut path_builder = PathBuilder::new();
+            path_builder.move_to(points[0]);
+            for point in points.iter().skip(1) {
+                path_builder.line_to(*point);
+            }
+            *path = path_builder.build();
+        }
+
+        // grey while cooling down, team colored while charging
+        if let Ok((mut meter_t, mut sprite)) = meter_q.get_mut(ui.meter) {
+            let size = Vec2::new(METER_SIZE.x * artillery.power.max(0.02), METER_SIZE.y);
+            meter_t.translation = Vec3::new(pos.x - (METER_SIZE.x - size.x) / 2.0, pos.y - DEFAULT_RADIUS * transform.scale.y, 5.0);
+            sprite.custom_size = Some(size);
+            sprite.color = if fuse_time.timer.finished() { team.color() } else { Color::GRAY };
+        }
+    }
+}
+
+
+// the preview and meter are drawn in world space, not as children, so
+// they are removed with the artillery or when the game ends
+pub fn system_cleanup(
+    mut commands: Commands,
+    artillery_q: Query<(Entity, &ManualUi)>,
+) {
+    for (entity, ui) in artillery_q.iter() {
+        commands.entity(ui.preview).despawn();
+        commands.entity(ui.meter).despawn();
+        commands.entity(entity).remove::<ManualUi>();
+    }
+}
+
+
+use crate::ev_despawn;
+pub fn despawn(
+    mut commands: Commands,
+    mut event: EventReader<ev_despawn::Despawn>,
+    game_assets: Res<GameAsset>,
+    query: Query<(&Transform, Option<&ManualUi>), With<ArtilleryManual>>,
+    ) {
+    let game_assets = game_assets.into_inner();
+    for ev_despawn::Despawn(entity) in event.iter() {
+        if let Ok((t, ui)) = query.get(Entity::from_raw(*entity)) {
+            if let Some(ui) = ui {
+                commands.entity(ui.preview).despawn();
+                commands.entity(ui.meter).despawn();
+            }
+            commands.entity(Entity::from_raw(*entity)).despawn_recursive();
+            commands.spawn(ExplosionBundle::from((t.translation, 500.0, game_assets)));
+        }
+    }
+}
+
+
+const FILE_NAME: &str = "/artillery_manual.map";
+use crate::ev_save_load_world::LoadWorldEvent;
+pub fn load(
+    mut load_world_er: EventReader<LoadWorldEvent>,
+    mut commands: Commands,
+    game_assets: Res<GameAsset>,
+    ) {
+    let game_assets = game_assets.into_inner();
+
+    for e in load_world_er.iter() {
+        let dir = e.0.clone();
+
+        let json_str = std::fs::read_to_string(dir + FILE_NAME);
+        if let Ok(json_str) = json_str {
+            let elem_list: Vec<(u32, u32, Vec3, Quat, Vec3, Team, ArtilleryManual)> = serde_json::from_str(&json_str).unwrap();
+
+            for (i, i2, t, _, s, team, a) in elem_list {
+                let rotation = Quat::from_rotation_z(a.angle);
+                let control = a.control;
+                let entity2 = commands.get_or_spawn(Entity::from_raw(i2))
+                                    .insert(ArtilleryManualBarrelBundle::from(( rotation, game_assets ))).id();
+
+                commands.get_or_spawn(Entity::from_raw(i))
+                        .insert(ArtilleryManualBaseBundle::from((t, s, a, team, game_assets)))
+                        .insert(MapObject::ArtilleryManual(team, control))
+                        .push_children(&[entity2]);
+            }
+        }
+    }
+}
+
+use crate::ev_save_load_world::SaveWorldEvent;
+pub fn save(mut save_world_er: EventReader<SaveWorldEvent>,
+              artillery_q: Query<(Entity, &Children, &Transform, &Team, &ArtilleryManual)>,
+              barrel_q: Query<Entity, With<ManualBarrel>>,
+              ) {
+    for e in save_world_er.iter() {
+        let dir = e.0.clone();
+        let mut artillery_list: Vec<(u32, u32, Vec3, Quat, Vec3, Team, ArtilleryManual)> = vec![];
+
+        for (e, c, t, team, a) in artillery_q.iter() {
+            let barrel = c.iter().find(|c| barrel_q.contains(**c)).unwrap();
+            artillery_list.push((e.index(), barrel.index(), t.translation, t.rotation, t.scale, *team, a.clone()));
+        }
+
+        std::fs::write(dir + FILE_NAME, serde_json::to_string(&artillery_list).unwrap()).unwrap();
+    }
+}
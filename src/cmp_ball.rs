@@ -3,9 +3,8 @@ use bevy_rapier2d::prelude::*;
 use rand::prelude::*;
 
 use crate::cmp_game_asset::GameAsset;
-use crate::cmp_trajectory::Trajectory;
-use crate::cmp_trajectory;
-use crate::cmp_blood;
+use crate::cmp_particle;
+use crate::cmp_particle::Preset;
 
 const DEFAULT_BALL_RADIUS: f32 = 19.0 / 2.0;
 //const DEFAULT_RESTITUTION: f32 = 0.1;
@@ -14,10 +13,20 @@ const DEFAULT_BALL_RADIUS: f32 = 19.0 / 2.0;
 const DEFAULT_RESTITUTION: f32 = 0.4;
 const DEFAULT_FRICTION: f32 = 0.3;
 
+const DEFAULT_DEATH_SOUNDS: [&str; 5] = [
+    //"zundamon_die1_handle",
+    //"zundamon_die2_handle",
+    "zundamon_die3_handle",
+    "zundamon_die4_handle",
+    "zundamon_die5_handle",
+    "zundamon_die6_handle",
+    "zundamon_die7_handle",
+];
+
 #[derive(Component)]
 pub struct Ball {
     pub radius: f32,
-    pub previous_position: Option<Vec2>,
+    pub death_sounds: Vec<String>,
 }
 
 #[derive(Bundle)]
@@ -39,7 +48,10 @@ pub struct BallBundle {
 impl Default for BallBundle {
     fn default() -> Self {
         Self {
-            ball: Ball { radius: DEFAULT_BALL_RADIUS, previous_position: None },
+            ball: Ball {
+                radius: DEFAULT_BALL_RADIUS,
+                death_sounds: DEFAULT_DEATH_SOUNDS.iter().map(|s| s.to_string()).collect(),
+            },
             ccd: Ccd::enabled(),
             rigid_body: RigidBody::Dynamic,
             restitution: Restitution::coefficient(DEFAULT_RESTITUTION),
@@ -83,42 +95,19 @@ impl From<(Vec2, f32, Vec2, Handle<Image>)> for BallBundle {
 }
 
 
-pub fn system_trajectory(
-    mut commands: Commands,
-    mut q: Query<(&Transform, &mut Ball)>,
-) {
-    for (t, mut ball) in q.iter_mut() {
-        let curr_pos = t.translation.truncate();
-
-        if ball.previous_position.is_some() {
-            let prev_pos = ball.previous_position.unwrap();
-            let trajectory = Trajectory { line: (prev_pos, curr_pos), life_time: 0.6 };
-            cmp_trajectory::add(&mut commands, trajectory);
-        }
-
-        ball.previous_position = Some(curr_pos);
-    }
-}
-
 pub fn kill(commands: &mut Commands,
             audio: &Res<Audio>,
             game_assets: &GameAsset,
             entity: Entity,
             trans: &Transform,
+            ball: &Ball,
             ) {
         let mut rng = rand::thread_rng();
-        let sv = vec![ 
-                        //"zundamon_die1_handle",
-                        //"zundamon_die2_handle",
-                        "zundamon_die3_handle",
-                        "zundamon_die4_handle",
-                        "zundamon_die5_handle",
-                        "zundamon_die6_handle",
-                        "zundamon_die7_handle",
-                     ];
-        let random_audio = sv[rng.gen_range(0..sv.len())];
-        cmp_blood::add(commands, trans.translation.truncate(), 4);
+        cmp_particle::burst(commands, Preset::Blood, trans.translation.truncate(), 12);
         commands.entity(entity).despawn();
-        audio.play(game_assets.audio_handles.get(random_audio).unwrap().clone());
-}
 
+        if ball.death_sounds.len() > 0 {
+            let random_audio = &ball.death_sounds[rng.gen_range(0..ball.death_sounds.len())];
+            audio.play(game_assets.audio_handles.get(random_audio).unwrap().clone());
+        }
+}
@@ -0,0 +1,198 @@
+use serde::{Serialize, Deserialize};
+use bevy::prelude::*;
+use bevy::ecs::system::EntityCommands;
+use bevy_rapier2d::prelude::*;
+use bevy_rapier2d::rapier::geometry::CollisionEventFlags;
+use rand::prelude::*;
+
+use crate::cmp_ball;
+use crate::cmp_ball::Ball;
+use crate::cmp_ball_species;
+use crate::cmp_ball_species::BallSpecies;
+use crate::cmp_ball_species::BallSpeciesRegistry;
+use crate::cmp_ball_species::Species;
+use crate::cmp_game_asset::GameAsset;
+
+const SPLIT_SPEED: f32 = 150.0;
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub enum BallBehavior {
+    Split { count: u32, into: String },
+    Sticky,
+    Bouncy,
+    Heavy { gravity_scale: f32 },
+    Infection { target: String, into: String, prob: f32 },
+}
+
+#[derive(Component, Clone, Debug)]
+pub struct Splitting {
+    pub count: u32,
+    pub into: String,
+}
+
+#[derive(Component, Clone, Debug)]
+pub struct Sticky;
+
+#[derive(Component, Clone, Debug)]
+pub struct Bouncy;
+
+#[derive(Component, Clone, Debug)]
+pub struct Heavy;
+
+#[derive(Component, Clone, Debug)]
+pub struct Infectious {
+    pub target: String,
+    pub into: String,
+    pub prob: f32,
+}
+
+
+pub fn insert(entity: &mut EntityCommands, behavior: &BallBehavior, species: &BallSpecies) {
+    match behavior {
+        BallBehavior::Split { count, into } => {
+            entity.insert(Splitting { count: *count, into: into.clone() })
+                  .insert(ActiveEvents::COLLISION_EVENTS);
+        }
+
+        BallBehavior::Sticky => {
+            entity.insert(Sticky)
+                  .insert(ActiveEvents::COLLISION_EVENTS);
+        }
+
+        BallBehavior::Bouncy => {
+            entity.insert(Bouncy)
+                  .insert(Restitution {
+                      coefficient: species.restitution,
+                      combine_rule: CoefficientCombineRule::Max,
+                  });
+        }
+
+        BallBehavior::Heavy { gravity_scale } => {
+            entity.insert(Heavy)
+                  .insert(GravityScale(*gravity_scale));
+        }
+
+        BallBehavior::Infection { target, into, prob } => {
+            entity.insert(Infectious { target: target.clone(), into: into.clone(), prob: *prob })
+                  .insert(ActiveEvents::COLLISION_EVENTS);
+        }
+    }
+}
+
+
+pub fn system_split(
+    mut commands: Commands,
+    registry: Res<BallSpeciesRegistry>,
+    game_assets: Res<GameAsset>,
+    mut collision_events: EventReader<CollisionEvent>,
+    split_q: Query<(&Transform, &Velocity, &Ball, &Splitting)>,
+) {
+    let mut split: Vec<Entity> = vec![];
+
+    for collision_event in collision_events.iter() {
+        if let CollisionEvent::Started(e1, e2, flags) = collision_event {
+            if flags.contains(CollisionEventFlags::SENSOR) { continue; }
+
+            for entity in [*e1, *e2] {
+                if split_q.contains(entity) && ! split.contains(&entity) {
+                    split.push(entity);
+                }
+            }
+        }
+    }
+
+    for entity in split {
+        let (t, v, ball, splitting) = split_q.get(entity).unwrap();
+        commands.entity(entity).despawn();
+
+        for i in 0..splitting.count {
+            let angle = 2.0 * std::f32::consts::PI * i as f32 / splitting.count as f32;
+            let dir = Vec2::new(angle.cos(), angle.sin());
+            let pos = t.translation.truncate() + dir * ball.radius * 0.5;
+
+            cmp_ball_species::spawn(&mut commands, &registry, &game_assets,
+                                    &splitting.into, pos, v.linvel + dir * SPLIT_SPEED);
+        }
+    }
+}
+
+
+pub fn system_sticky(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    sticky_q: Query<&Transform, With<Sticky>>,
+    body_q: Query<&GlobalTransform, With<RigidBody>>,
+    parent_q: Query<&Parent>,
+) {
+    let mut stuck: Vec<Entity> = vec![];
+
+    for collision_event in collision_events.iter() {
+        if let CollisionEvent::Started(e1, e2, flags) = collision_event {
+            if flags.contains(CollisionEventFlags::SENSOR) { continue; }
+
+            for (ball_e, other_e) in [(*e1, *e2), (*e2, *e1)] {
+                if stuck.contains(&ball_e) { continue; }
+
+                if let Ok(ball_t) = sticky_q.get(ball_e) {
+                    // colliders of gears are children of the body
+                    let body_e = if body_q.contains(other_e) {
+                        Some(other_e)
+                    } else {
+                        parent_q.get(other_e).ok()
+                            .map(|parent| parent.get())
+                            .filter(|parent| body_q.contains(*parent))
+                    };
+
+                    if let Some(body_e) = body_e {
+                        let body_gt = body_q.get(body_e).unwrap();
+                        let (_, rotation, translation) = body_gt.to_scale_rotation_translation();
+                        let anchor = rotation.inverse().mul_vec3(ball_t.translation - translation).truncate();
+                        let joint = FixedJointBuilder::new()
+                            .local_anchor1(anchor)
+                            .local_anchor2(Vec2::ZERO);
+
+                        commands.entity(ball_e).insert(ImpulseJoint::new(body_e, joint));
+                    } else {
+                        commands.entity(ball_e).insert(RigidBody::Fixed);
+                    }
+
+                    commands.entity(ball_e).remove::<Sticky>();
+                    stuck.push(ball_e);
+                }
+            }
+        }
+    }
+}
+
+
+pub fn system_infection(
+    mut commands: Commands,
+    audio: Res<Audio>,
+    registry: Res<BallSpeciesRegistry>,
+    game_assets: Res<GameAsset>,
+    mut collision_events: EventReader<CollisionEvent>,
+    infectious_q: Query<&Infectious>,
+    ball_q: Query<(&Transform, &Velocity, &Ball, &Species)>,
+) {
+    let mut rng = rand::thread_rng();
+    let mut infected: Vec<Entity> = vec![];
+
+    for collision_event in collision_events.iter() {
+        if let CollisionEvent::Started(e1, e2, flags) = collision_event {
+            if flags.contains(CollisionEventFlags::SENSOR) { continue; }
+
+            for (source_e, target_e) in [(*e1, *e2), (*e2, *e1)] {
+                if infected.contains(&target_e) { continue; }
+
+                if let (Ok(infectious), Ok((t, v, ball, species))) = (infectious_q.get(source_e), ball_q.get(target_e)) {
+                    if species.0 == infectious.target && rng.gen::<f32>() < infectious.prob {
+                        cmp_ball::kill(&mut commands, &audio, &game_assets, target_e, t, ball);
+                        cmp_ball_species::spawn(&mut commands, &registry, &game_assets,
+                                                &infectious.into, t.translation.truncate(), v.linvel);
+                        infected.push(target_e);
+                    }
+                }
+            }
+        }
+    }
+}
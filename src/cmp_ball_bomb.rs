@@ -3,18 +3,21 @@ use bevy_rapier2d::prelude::*;
 use rand::prelude::*;
 
 use crate::cmp_game_asset::GameAsset;
-use crate::cmp_trajectory::Trajectory;
-use crate::cmp_trajectory;
-use crate::cmp_blood;
+use crate::cmp_particle;
+use crate::cmp_particle::Preset;
 use crate::cmp_explosion;
 use crate::cmp_ball;
 use crate::cmp_ball::BallBundle;
 use crate::cmp_ball::Ball;
+use crate::cmp_combat::Effect;
+use crate::cmp_combat::EffectKind;
 use crate::cmp_combat::Status;
-use crate::cmp_combat::Player1;
-use crate::cmp_combat::Player2;
 use crate::cmp_explosion::ExplosionBundle;
 use crate::cmp_rotator::Rotator;
+use crate::cmp_team::Team;
+use crate::cmp_team::TeamConfig;
+use crate::contacts;
+use serde::{Serialize, Deserialize};
 
 const RADIUS: f32 = 20.0;
 const HP: f32 = 1.0;
@@ -22,26 +25,104 @@ const ATTACK: f32 = 1.0;
 const EXPLOSION_ATTACK: f32 = 0.7;
 const ANGVEL: f32 = -5.0;
 const EXPLOSION_RADIUS: f32 = 150.0;
+const CLUSTER_SPEED: f32 = 300.0;
+const PIERCE_ATTACK: f32 = 40.0;
+const HOMING_RANGE: f32 = 800.0;
+
+#[derive(Reflect, FromReflect, Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
+pub enum ZoneKind {
+    Fire,
+    Poison,
+}
+
+#[derive(Reflect, FromReflect, Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
+pub enum Ammo {
+    Bomb,
+    // bursts into `count` bombs on impact
+    Cluster { count: usize },
+    // passes through `count` enemy units before it explodes
+    Piercing { count: usize },
+    // flies without gravity and turns towards the nearest enemy, radians per second
+    Homing { turn_rate: f32 },
+    // leaves a damaging area behind
+    Zone { kind: ZoneKind, radius: f32, duration: f32 },
+}
+
+// what artillery can be loaded with, in selection order
+pub const AMMO_LIST: [Ammo; 6] = [
+    Ammo::Bomb,
+    Ammo::Cluster { count: 5 },
+    Ammo::Piercing { count: 3 },
+    Ammo::Homing { turn_rate: 3.0 },
+    Ammo::Zone { kind: ZoneKind::Fire, radius: 150.0, duration: 5.0 },
+    Ammo::Zone { kind: ZoneKind::Poison, radius: 200.0, duration: 8.0 },
+];
+
+impl Ammo {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Ammo::Bomb => "Bomb",
+            Ammo::Cluster { .. } => "Cluster",
+            Ammo::Piercing { .. } => "Piercing",
+            Ammo::Homing { .. } => "Homing",
+            Ammo::Zone { kind: ZoneKind::Fire, .. } => "Fire",
+            Ammo::Zone { kind: ZoneKind::Poison, .. } => "Poison",
+        }
+    }
+}
+
+impl Default for Ammo {
+    fn default() -> Self {
+        Ammo::Bomb
+    }
+}
+
+#[derive(Component, Clone, Debug)]
+pub struct Cluster {
+    pub count: usize,
+}
+
+#[derive(Component, Clone, Debug)]
+pub struct Homing {
+    pub turn_rate: f32,
+}
+
+#[derive(Component, Clone, Debug)]
+pub struct ZoneShell {
+    pub kind: ZoneKind,
+    pub radius: f32,
+    pub duration: f32,
+}
+
+#[derive(Component, Clone, Debug)]
+pub struct Piercing {
+    pub remaining: usize,
+    pub hit: Vec<Entity>,
+}
+
+// child of a piercing shell, which itself passes through units
+#[derive(Component)]
+pub struct PierceSensor;
 
 #[derive(Component)]
 pub struct BallBomb;
 
 #[derive(Bundle)]
-pub struct BallBombBundle<T: Component + Default> {
-    player: T,
+pub struct BallBombBundle {
+    team: Team,
     ball_type: BallBomb,
     collision_events: ActiveEvents,
     #[bundle]
     ball_bundle: BallBundle,
 }
 
-impl<T: Component + Default> From<(Vec2, Vec2, &GameAsset)> for BallBombBundle<T> {
-    fn from(tuple: (Vec2, Vec2, &GameAsset)) -> Self {
-        let (translation, velocity, game_assets) = tuple;
+impl From<(Vec2, Vec2, Team, &GameAsset)> for BallBombBundle {
+    fn from(tuple: (Vec2, Vec2, Team, &GameAsset)) -> Self {
+        let (translation, velocity, team, game_assets) = tuple;
 
         let handle = game_assets.image_handles.get("bomb_handle").unwrap();
         let mut bundle = Self {
-            player: T::default(),
+            team,
             ball_type: BallBomb,
             collision_events: ActiveEvents::COLLISION_EVENTS,
             ball_bundle: BallBundle::from((translation, RADIUS, velocity, handle.clone())),
@@ -52,21 +133,95 @@ impl<T: Component + Default> From<(Vec2, Vec2, &GameAsset)> for BallBombBundle<T
     }
 }
 
-pub fn system<T: Component + Default>(
-    mut commands: Commands,
-    audio: Res<Audio>,
-    game_assets: Res<GameAsset>,
-    mut p1_q: Query<(Entity, &Status, &Transform, &BallBomb), With<T>>,
-) {
+pub fn spawn_shell(commands: &mut Commands,
+                   ammo: Ammo,
+                   translation: Vec2,
+                   velocity: Vec2,
+                   team: Team,
+                   game_assets: &GameAsset) -> Entity {
+    let mut entity = commands.spawn(BallBombBundle::from((translation, velocity, team, game_assets)));
+
+    match ammo {
+        Ammo::Bomb => {}
+        Ammo::Cluster { count } => {
+            entity.insert(Cluster { count });
+        }
+        Ammo::Piercing { count } => {
+            entity.insert(Piercing { remaining: count, hit: vec![] })
+                  .with_children(|children| {
+                      children.spawn((
+                          PierceSensor,
+                          Collider::ball(RADIUS),
+                          Sensor,
+                          ColliderMassProperties::Density(0.0),
+                          CollisionGroups::new(Group::GROUP_1, Group::ALL),
+                          TransformBundle::default(),
+                      ));
+                  });
+        }
+        Ammo::Homing { turn_rate } => {
+            entity.insert(Homing { turn_rate })
+                  .insert(GravityScale(0.0));
+        }
+        Ammo::Zone { kind, radius, duration } => {
+            entity.insert(ZoneShell { kind, radius, duration });
+        }
+    }
+
+    entity.id()
 }
 
-pub fn system_ignition<T1: Component + Default>(
+fn explode(commands: &mut Commands,
+           game_assets: &GameAsset,
+           entity: Entity,
+           t: &Transform,
+           team: &Team,
+           cluster: Option<&Cluster>,
+           zone: Option<&ZoneShell>) {
+    let mut bundle = ExplosionBundle::from((t.translation, EXPLOSION_RADIUS, game_assets));
+    bundle.status.attack = EXPLOSION_ATTACK;
+
+    commands.entity(entity).despawn();
+    commands.spawn(bundle)
+        .insert(*team);
+
+    // fan the bomblets out upwards, away from whatever was hit
+    if let Some(cluster) = cluster {
+        for i in 0..cluster.count {
+            let angle = std::f32::consts::PI * (i as f32 + 1.0) / (cluster.count as f32 + 1.0);
+            let dir = Vec2::new(angle.cos(), angle.sin());
+            let pos = t.translation.truncate() + dir * RADIUS * 2.0;
+            commands.spawn(BallBombBundle::from((pos, dir * CLUSTER_SPEED, *team, game_assets)));
+        }
+    }
+
+    if let Some(zone) = zone {
+        let (attack, on_hit, color) = match zone.kind {
+            ZoneKind::Fire => (1.0,
+                               vec![Effect { kind: EffectKind::Burn { dps: 15.0 }, duration: 2.0 }],
+                               Color::rgba(1.0, 0.4, 0.1, 0.5)),
+            ZoneKind::Poison => (0.5,
+                                 vec![Effect { kind: EffectKind::Burn { dps: 5.0 }, duration: 6.0 },
+                                      Effect { kind: EffectKind::Slow { factor: 0.5 }, duration: 6.0 }],
+                                 Color::rgba(0.4, 0.9, 0.2, 0.5)),
+        };
+
+        let mut bundle = ExplosionBundle::lingering(t.translation, zone.radius, zone.duration, color, game_assets);
+        bundle.status.attack = attack;
+        bundle.status.on_hit = on_hit;
+        commands.spawn(bundle)
+            .insert(cmp_explosion::Lingering)
+            .insert(*team);
+    }
+}
+
+pub fn system_ignition(
     mut commands: Commands,
     audio: Res<Audio>,
     game_assets: Res<GameAsset>,
     rapier_context: Res<RapierContext>,
     mut collision_events: EventReader<CollisionEvent>,
-    mut query: Query<(Entity, &Transform), (With<BallBomb>, With<T1>)>,
+    mut query: Query<(Entity, &Transform, &Team, Option<&Cluster>, Option<&ZoneShell>), With<BallBomb>>,
 ) {
     let game_assets = game_assets.into_inner();
 
@@ -74,20 +229,10 @@ pub fn system_ignition<T1: Component + Default>(
         if let CollisionEvent::Started(e1, e2, flags) = collision_event {;
 
             if flags.is_empty() && (query.contains(*e1) || query.contains(*e2)) {
-                if let Ok((entity, t)) = query.get(*e1) {
-                    let mut bundle = ExplosionBundle::from((t.translation, EXPLOSION_RADIUS, game_assets));
-                    bundle.status.attack = EXPLOSION_ATTACK;
-
-                    commands.entity(entity).despawn();
-                    commands.spawn(bundle)
-                        .insert(T1::default());
-                } else if let Ok((entity, t)) = query.get(*e2) {
-                    let mut bundle = ExplosionBundle::from((t.translation, EXPLOSION_RADIUS, game_assets));
-                    bundle.status.attack = EXPLOSION_ATTACK;
-
-                    commands.entity(entity).despawn();
-                    commands.spawn(bundle)
-                        .insert(T1::default());
+                if let Ok((entity, t, team, cluster, zone)) = query.get(*e1) {
+                    explode(&mut commands, game_assets, entity, t, team, cluster, zone);
+                } else if let Ok((entity, t, team, cluster, zone)) = query.get(*e2) {
+                    explode(&mut commands, game_assets, entity, t, team, cluster, zone);
                 }
             }
         }
@@ -96,3 +241,74 @@ pub fn system_ignition<T1: Component + Default>(
     }
 }
 
+
+
+pub fn system_piercing(
+    mut commands: Commands,
+    game_assets: Res<GameAsset>,
+    team_config: Res<TeamConfig>,
+    rapier_context: Res<RapierContext>,
+    sensor_q: Query<(Entity, &Parent), With<PierceSensor>>,
+    mut shell_q: Query<(&Transform, &Team, &mut Piercing), With<BallBomb>>,
+    mut unit_q: Query<(&Transform, &Team, &mut Status), (With<Ball>, Without<BallBomb>)>,
+) {
+    let game_assets = game_assets.into_inner();
+
+    for (sensor_e, parent) in sensor_q.iter() {
+        let shell_e = parent.get();
+        // most paths despawn a shell without its children
+        let Ok((shell_t, shell_team, mut piercing)) = shell_q.get_mut(shell_e) else {
+            commands.entity(sensor_e).despawn();
+            continue;
+        };
+
+        for unit_e in contacts::intersecting(&rapier_context, sensor_e) {
+            let Ok((unit_t, unit_team, mut status)) = unit_q.get_mut(unit_e) else { continue; };
+            if ! team_config.harms(*shell_team, *unit_team) { continue; }
+            if piercing.hit.contains(&unit_e) { continue; }
+
+            status.damage(PIERCE_ATTACK);
+            cmp_particle::burst(&mut commands, Preset::Blood, unit_t.translation.truncate(), 3);
+            piercing.hit.push(unit_e);
+            piercing.remaining = piercing.remaining.saturating_sub(1);
+
+            if piercing.remaining == 0 {
+                explode(&mut commands, game_assets, shell_e, shell_t, shell_team, None, None);
+                break;
+            }
+        }
+    }
+}
+
+
+pub fn system_homing(
+    time: Res<Time>,
+    team_config: Res<TeamConfig>,
+    mut shell_q: Query<(&Transform, &Team, &Homing, &mut Velocity), With<BallBomb>>,
+    // balls only, walls have a Status and a Team as well
+    unit_q: Query<(&Transform, &Team), (With<Ball>, With<Status>, Without<BallBomb>)>,
+) {
+    for (shell_t, shell_team, homing, mut velocity) in shell_q.iter_mut() {
+        let pos = shell_t.translation.truncate();
+
+        let nearest = unit_q.iter()
+            .filter(|(_, team)| team_config.hostile(*shell_team, **team))
+            .map(|(t, _)| t.translation.truncate())
+            .filter(|p| p.distance(pos) < HOMING_RANGE)
+            .min_by(|a, b| a.distance(pos).partial_cmp(&b.distance(pos)).unwrap());
+
+        if let Some(target) = nearest {
+            let speed = velocity.linvel.length();
+            let heading = Vec2::new(1.0, 0.0).angle_between(velocity.linvel);
+            let wanted = Vec2::new(1.0, 0.0).angle_between(target - pos);
+
+            let mut turn = (wanted - heading) % (2.0 * std::f32::consts::PI);
+            if turn > std::f32::consts::PI { turn -= 2.0 * std::f32::consts::PI; }
+            if turn < -std::f32::consts::PI { turn += 2.0 * std::f32::consts::PI; }
+
+            let max_turn = homing.turn_rate * time.delta_seconds();
+            let heading = heading + turn.clamp(-max_turn, max_turn);
+            velocity.linvel = Vec2::new(heading.cos(), heading.sin()) * speed;
+        }
+    }
+}
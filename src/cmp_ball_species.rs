@@ -0,0 +1,140 @@
+use serde::{Serialize, Deserialize};
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use rand::prelude::*;
+use std::collections::HashMap;
+
+use crate::cmp_ball::BallBundle;
+use crate::cmp_ball_behavior;
+use crate::cmp_ball_behavior::BallBehavior;
+use crate::cmp_ball_zundamon::Zundamon;
+use crate::cmp_ball_zombie::Zombie;
+use crate::cmp_game_asset;
+use crate::cmp_game_asset::GameAsset;
+
+const FILE_PATH: &str = "assets/ball_species.json";
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
+pub enum SpeciesMarker {
+    Zundamon,
+    Zombie,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct BallSpecies {
+    pub name: String,
+    pub radius: f32,
+    pub sprites: Vec<String>,
+    pub restitution: f32,
+    pub friction: f32,
+    #[serde(default)]
+    pub mass: Option<f32>,
+    #[serde(default)]
+    pub death_sounds: Vec<String>,
+    #[serde(default)]
+    pub marker: Option<SpeciesMarker>,
+    #[serde(default)]
+    pub behaviors: Vec<BallBehavior>,
+    // scored when the ball reaches a goal bucket
+    #[serde(default = "default_points")]
+    pub points: u32,
+    // rgb multiplied onto the sprites, e.g. for gold
+    #[serde(default)]
+    pub tint: Option<[f32; 3]>,
+}
+
+fn default_points() -> u32 { 1 }
+
+#[derive(Resource, Default, Debug)]
+pub struct BallSpeciesRegistry {
+    pub species: HashMap<String, BallSpecies>,
+}
+
+impl BallSpeciesRegistry {
+    pub fn get(&self, name: &str) -> Option<&BallSpecies> {
+        self.species.get(name)
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.species.keys().cloned().collect();
+        names.sort();
+        names
+    }
+}
+
+#[derive(Component, Clone, Debug)]
+pub struct Species(pub String);
+
+// base color of the sprite, cmp_combat::system_effect_color tints on top
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Tint(pub Color);
+
+
+pub fn setup(mut registry: ResMut<BallSpeciesRegistry>, game_assets: Res<GameAsset>) {
+    // read from the working directory, so species can be tuned without rebuilding
+    let species_list: Vec<BallSpecies> = cmp_game_asset::read_list(FILE_PATH, include_str!("../assets/ball_species.json"));
+
+    for mut species in species_list {
+        if species.sprites.is_empty() {
+            warn!("{}: skipping {}, no sprites", FILE_PATH, species.name);
+            continue;
+        }
+        if let Some(sprite) = species.sprites.iter().find(|s| ! game_assets.image_handles.contains_key(*s)) {
+            warn!("{}: skipping {}, unknown sprite {}", FILE_PATH, species.name, sprite);
+            continue;
+        }
+        species.death_sounds.retain(|s| {
+            let known = game_assets.audio_handles.contains_key(s);
+            if ! known { warn!("{}: {} drops unknown death sound {}", FILE_PATH, species.name, s); }
+            known
+        });
+        registry.species.insert(species.name.clone(), species);
+    }
+}
+
+
+pub fn spawn(commands: &mut Commands,
+             registry: &BallSpeciesRegistry,
+             game_assets: &GameAsset,
+             name: &str,
+             translation: Vec2,
+             velocity: Vec2) -> Option<Entity> {
+    let species = registry.get(name)?;
+
+    let mut rng = rand::thread_rng();
+    let sprite = &species.sprites[rng.gen_range(0..species.sprites.len())];
+    let handle = game_assets.image_handles.get(sprite).unwrap();
+
+    let mut bundle = BallBundle::from((translation, species.radius, velocity, handle.clone()));
+    bundle.restitution = Restitution::coefficient(species.restitution);
+    bundle.friction = Friction::coefficient(species.friction);
+    bundle.ball.death_sounds = species.death_sounds.clone();
+
+    let mut entity = commands.spawn(bundle);
+    entity.insert(Species(species.name.clone()));
+
+    if let Some([r, g, b]) = species.tint {
+        entity.insert(Tint(Color::rgb(r, g, b)));
+    }
+
+    if let Some(mass) = species.mass {
+        entity.insert(ColliderMassProperties::Mass(mass));
+    }
+
+    match species.marker {
+        Some(SpeciesMarker::Zundamon) => {
+            entity.insert(Zundamon);
+        }
+        Some(SpeciesMarker::Zombie) => {
+            entity.insert(Zombie)
+                  .insert(CollisionGroups::new(Group::GROUP_2, Group::ALL));
+        }
+        None => {}
+    }
+
+    for behavior in species.behaviors.iter() {
+        cmp_ball_behavior::insert(&mut entity, behavior, species);
+    }
+
+    Some(entity.id())
+}
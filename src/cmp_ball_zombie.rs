@@ -2,12 +2,9 @@ use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 use rand::prelude::*;
 
-use crate::cmp_ball;
-use crate::cmp_ball::Ball;
 use crate::cmp_ball::BallBundle;
-use crate::cmp_ball_zundamon;
-use crate::cmp_ball_zundamon::Zundamon;
 use crate::cmp_game_asset::GameAsset;
+use crate::cmp_ball_species::Species;
 
 #[derive(Component)]
 pub struct Zombie;
@@ -15,6 +12,7 @@ pub struct Zombie;
 #[derive(Bundle)]
 pub struct BallZombieBundle {
     zombie: Zombie,
+    species: Species,
     #[bundle]
     ball_bundle: BallBundle,
 }
@@ -36,6 +34,7 @@ impl From<(Vec2, f32, Vec2, &GameAsset)> for BallZombieBundle {
         let handle = random_sprite_handle(&game_assets);
         let mut bundle = Self {
             zombie: Zombie,
+            species: Species(String::from("zombie")),
             ball_bundle: BallBundle::from((translation, radius, velocity, handle.clone())),
         };
         bundle.ball_bundle.collision_groups = CollisionGroups::new(Group::GROUP_2, Group::ALL);
@@ -43,25 +42,3 @@ impl From<(Vec2, f32, Vec2, &GameAsset)> for BallZombieBundle {
         bundle
     }
 }
-
-pub fn system_infection(
-    mut commands: Commands,
-    audio: Res<Audio>,
-    rapier_context: Res<RapierContext>,
-    game_assets: Res<GameAsset>,
-    zundamon_q: Query<(Entity, &Transform, &Velocity, &Ball), With<Zundamon>>,
-    zombie_q: Query<Entity, (With<Zombie>, Without<Zundamon>)>,
-) {
-    let game_assets = game_assets.into_inner();
-
-    for (zundamon_e, zundamon_t, zundamon_v, zundamon_ball) in zundamon_q.iter() {
-        for zombie_e in zombie_q.iter() {
-            if let Some(_) = rapier_context.contact_pair(zundamon_e, zombie_e) {
-                cmp_ball::kill(&mut commands, &audio, &game_assets, zundamon_e, &zundamon_t);
-                let _ = commands.spawn(
-                    BallZombieBundle::from((zundamon_t.translation.truncate(), zundamon_ball.radius, zundamon_v.linvel, game_assets)));
-            }
-        }
-    }
-}
-
@@ -3,9 +3,7 @@ use bevy_rapier2d::prelude::*;
 use rand::prelude::*;
 
 use crate::cmp_game_asset::GameAsset;
-use crate::cmp_trajectory::Trajectory;
-use crate::cmp_trajectory;
-use crate::cmp_blood;
+use crate::cmp_ball_species::Species;
 use crate::cmp_ball;
 use crate::cmp_ball::BallBundle;
 use crate::cmp_ball::Ball;
@@ -16,6 +14,7 @@ pub struct Zundamon;
 #[derive(Bundle)]
 pub struct BallZundamonBundle {
     zundamon: Zundamon,
+    species: Species,
     #[bundle]
     ball_bundle: BallBundle,
 }
@@ -38,6 +37,7 @@ impl From<(Vec2, f32, Vec2, &GameAsset)> for BallZundamonBundle {
         let handle = random_sprite_handle(&game_assets);
         let bundle = Self {
             zundamon: Zundamon,
+            species: Species(String::from("zundamon")),
             ball_bundle: BallBundle::from((translation, radius, velocity, handle.clone())),
         };
 
@@ -0,0 +1,117 @@
+use bevy::prelude::*;
+use bevy::app::AppExit;
+use rand::prelude::*;
+use std::time::Instant;
+
+use crate::cmp_ball_species::BallSpeciesRegistry;
+use crate::cmp_ball::Ball;
+use crate::cmp_combat_unit::UnitRegistry;
+use crate::cmp_game_asset::GameAsset;
+use crate::cmp_gate_generic;
+use crate::cmp_gate_generic::BallType;
+use crate::cmp_match::MatchPhase;
+use crate::cmp_match::MatchState;
+use crate::cmp_team::Team;
+use crate::cmp_team::TeamConfig;
+
+// a closed box with velocity pads on the floor, see assets/map_bench
+pub const MAP: &str = "assets/map_bench";
+const AREA: Vec2 = Vec2::new(2200.0, 1000.0);
+const REPORT_EVERY: usize = 60;
+
+// Frame times with thousands of balls, headless:
+//   zunda_shower --bench [balls] [frames]
+// Half the balls are units of two hostile teams so combat contacts are
+// measured too, the rest are plain zundamon.
+//
+// ms/frame over 300 frames, release, one core, against the nested loops
+// that tested every ball (average / worst):
+//   balls   pairs         nested loops
+//     500   1.59 / 6.9    2.83 / 7.9
+//    1000   2.95 / 14.9   5.16 / 20.2
+//    2000   6.17 / 77.9   8.48 / 99.0
+//    4000   12.8 / 354    15.2 / 461
+// Units kill each other fast, about half the balls are left by the end.
+#[derive(Resource, Debug)]
+pub struct Bench {
+    pub balls: usize,
+    pub frames: usize,
+    frame: usize,
+    last: Option<Instant>,
+    total: f32,
+    worst: f32,
+}
+
+impl Bench {
+    pub fn from_args() -> Option<Self> {
+        let args: Vec<String> = std::env::args().collect();
+        let index = args.iter().position(|a| a == "--bench")?;
+
+        let balls = args.get(index + 1).and_then(|n| n.parse().ok()).unwrap_or(2000);
+        let frames = args.get(index + 2).and_then(|n| n.parse().ok()).unwrap_or(600);
+
+        Some(Self { balls, frames, frame: 0, last: None, total: 0.0, worst: 0.0 })
+    }
+}
+
+pub fn bench(bench: Option<Res<Bench>>) -> bool {
+    bench.is_some()
+}
+
+
+pub fn system_setup(
+    mut commands: Commands,
+    game_assets: Res<GameAsset>,
+    registry: Res<BallSpeciesRegistry>,
+    unit_registry: Res<UnitRegistry>,
+    team_config: Res<TeamConfig>,
+    mut state: ResMut<MatchState>,
+    mut bench: ResMut<Bench>,
+) {
+    // no countdown, physics is paused during it
+    state.phase = MatchPhase::Playing { elapsed: 0.0 };
+
+    let mut rng = StdRng::seed_from_u64(0);
+
+    for i in 0..bench.balls {
+        let balltype = match i % 4 {
+            0 => BallType::Unit(String::from("type1"), Team(0)),
+            1 => BallType::Unit(String::from("type1"), Team(1)),
+            _ => BallType::Zundamon,
+        };
+        let position = Vec2::new(rng.gen_range(-0.5..0.5), rng.gen_range(-0.5..0.5)) * AREA;
+        let velocity = Vec2::new(rng.gen_range(-200.0..200.0), rng.gen_range(-200.0..200.0));
+        cmp_gate_generic::spawn(&mut commands, &game_assets, &registry, &unit_registry, &team_config,
+                                &balltype, position, 10.0, velocity);
+    }
+
+    bench.frame = 0;
+    bench.last = None;
+    bench.total = 0.0;
+    bench.worst = 0.0;
+}
+
+
+pub fn system(
+    mut bench: ResMut<Bench>,
+    ball_q: Query<(), With<Ball>>,
+    mut exit_ew: EventWriter<AppExit>,
+) {
+    let now = Instant::now();
+    if let Some(last) = bench.last {
+        let ms = (now - last).as_secs_f32() * 1000.0;
+        bench.total += ms;
+        bench.worst = bench.worst.max(ms);
+        bench.frame += 1;
+
+        if bench.frame % REPORT_EVERY == 0 {
+            println!("frame {}: {} balls, {:.2} ms/frame", bench.frame, ball_q.iter().len(), bench.total / bench.frame as f32);
+        }
+    }
+    bench.last = Some(now);
+
+    if bench.frame >= bench.frames {
+        println!("{} frames, {:.2} ms/frame on average, {:.2} ms worst", bench.frame, bench.total / bench.frame.max(1) as f32, bench.worst);
+        exit_ew.send(AppExit);
+    }
+}
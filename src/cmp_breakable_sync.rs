@@ -9,14 +9,25 @@ use crate::cmp_ball::Ball;
 use crate::cmp_ball_zundamon::Zundamon;
 use crate::cmp_ball_zombie::Zombie;
 use crate::cmp_bbsize::BBSize;
+use crate::cmp_debris;
+use crate::cmp_debris::DebrisConfig;
+use crate::cmp_polygonal_shape::PolygonalShape;
 use crate::cmp_game_asset::GameAsset;
+use crate::cmp_combat;
+use crate::cmp_combat::CombatConfig;
+use crate::cmp_combat::ContactTracker;
+use crate::cmp_combat::Effects;
 use crate::cmp_combat::Status;
-use crate::cmp_combat::Player1;
-use crate::cmp_combat::Player2;
+use crate::cmp_team::Team;
+use crate::cmp_team::TeamCollision;
+use crate::cmp_team::TeamConfig;
 use crate::cmp_ball_zundamon;
 use crate::cmp_ball_zombie;
 use crate::cmp_explosion;
 use crate::cmp_explosion::ExplosionBundle;
+use crate::cmp_particle;
+use crate::cmp_particle::Preset;
+use crate::contacts;
 
 use crate::cmp_wall;
 use crate::cmp_wall::WallBundle;
@@ -38,70 +49,39 @@ pub struct BreakableSync {
 }
 
 #[derive(Bundle)]
-pub struct BreakableSyncP1Bundle {
+pub struct BreakableSyncBundle {
     breakable_sync: BreakableSync,
-    player: Player1,
+    team: Team,
+    team_collision: TeamCollision,
     status: Status,
     collision_groups: CollisionGroups,
 }
 
 
-#[derive(Bundle)]
-pub struct BreakableSyncP2Bundle {
-    breakable_sync: BreakableSync,
-    player: Player2,
-    status: Status,
-    collision_groups: CollisionGroups,
-}
-
-
-impl Default for BreakableSyncP1Bundle {
+impl Default for BreakableSyncBundle {
     fn default() -> Self {
         Self {
             breakable_sync: BreakableSync { entities: vec![] },
-            player: Player1,
+            team: Team::default(),
+            team_collision: TeamCollision::Structure,
             status: Status {
                 hp: HP,
                 hp_max: HP,
                 attack: ATTACK,
+                ..default()
             },
-            collision_groups: CollisionGroups::new(Group::GROUP_1, Group::GROUP_11),
+            // replaced from the team config by cmp_team::system_collision_groups
+            collision_groups: CollisionGroups::new(Group::GROUP_1, Group::NONE),
         }
     }
 }
 
-impl From<(Status, BreakableSync)> for BreakableSyncP1Bundle {
-    fn from(tuple: (Status, BreakableSync)) -> Self {
-        let (status, breakable_sync) = tuple;
+impl From<(Team, Status, BreakableSync)> for BreakableSyncBundle {
+    fn from(tuple: (Team, Status, BreakableSync)) -> Self {
+        let (team, status, breakable_sync) = tuple;
 
-        let mut bundle = BreakableSyncP1Bundle::default();
-        bundle.status = status;
-        bundle.breakable_sync = breakable_sync;
-
-        bundle
-    }
-}
-
-impl Default for BreakableSyncP2Bundle {
-    fn default() -> Self {
-        Self {
-            breakable_sync: BreakableSync { entities: vec![] },
-            player: Player2,
-            status: Status {
-                hp: HP,
-                hp_max: HP,
-                attack: ATTACK,
-            },
-            collision_groups: CollisionGroups::new(Group::GROUP_1, Group::GROUP_10),
-        }
-    }
-}
-
-impl From<(Status, BreakableSync)> for BreakableSyncP2Bundle {
-    fn from(tuple: (Status, BreakableSync)) -> Self {
-        let (status, breakable_sync) = tuple;
-
-        let mut bundle = BreakableSyncP2Bundle::default();
+        let mut bundle = BreakableSyncBundle::default();
+        bundle.team = team;
         bundle.status = status;
         bundle.breakable_sync = breakable_sync;
 
@@ -117,23 +97,12 @@ pub fn handle_user_input(
     ) {
 
     if let EditContext::Spawn(map_object) = edit_context.to_owned() {
-        if let MapObject::BreakableSyncP1(ref entities) = map_object {
+        if let MapObject::BreakableSync(ref entities, team) = map_object {
             let mut iter = entities.iter();
             let base_entity = iter.next().unwrap();
 
-            let mut bundle = BreakableSyncP1Bundle::default();
-            bundle.breakable_sync.entities = iter.map(|x| x.index()).collect();
-
-            let _ = commands.entity(*base_entity).insert(bundle);
-
-            *edit_context = EditContext::Edit(MapObject::None, vec![], EditTool::Select);
-        }
-
-        if let MapObject::BreakableSyncP2(ref entities) = map_object {
-            let mut iter = entities.iter();
-            let base_entity = iter.next().unwrap();
-
-            let mut bundle = BreakableSyncP2Bundle::default();
+            let mut bundle = BreakableSyncBundle::default();
+            bundle.team = team;
             bundle.breakable_sync.entities = iter.map(|x| x.index()).collect();
 
             let _ = commands.entity(*base_entity).insert(bundle);
@@ -145,32 +114,59 @@ pub fn handle_user_input(
 }
 
 use crate::ev_despawn;
-pub fn system_damage<T1: Component + Default, T2: Component>(
+pub fn system_damage(
     mut commands: Commands,
+    time: Res<Time>,
+    config: Res<CombatConfig>,
+    team_config: Res<TeamConfig>,
     rapier_context: Res<RapierContext>,
+    mut tracker: Local<ContactTracker>,
+    mut wall_q: Query<(Entity, &Team, &mut Status, Option<&Velocity>), (With<BreakableSync>, Without<Ball>)>,
+    mut ball_q: Query<(Entity, &Team, &mut Status, &Transform, &Velocity, Option<&mut Effects>), With<Ball>>,
+) {
+    let now = time.elapsed_seconds();
+    tracker.begin_frame(now, config.contact_mode);
+
+    for (wall_e, wall_team, mut wall_s, wall_v) in wall_q.iter_mut() {
+        for other in contacts::touching(&rapier_context, wall_e) {
+            let Ok((ball_e, ball_team, mut ball_s, ball_t, ball_v, ball_fx)) = ball_q.get_mut(other) else { continue; };
+            if ! team_config.hostile(*wall_team, *ball_team) { continue; }
+
+            if tracker.hit((wall_e, ball_e), now, config.contact_mode) {
+                // a moving wall hits harder, one moving along with the ball not at all
+                let wall_linvel = wall_v.map_or(Vec2::ZERO, |v| v.linvel);
+                ball_s.damage(wall_s.attack * config.velocity_scale((ball_v.linvel - wall_linvel).length()));
+                cmp_combat::inflict(&mut commands, ball_e, ball_fx, &wall_s.on_hit);
+                wall_s.damage(1.0);
+                cmp_particle::burst(&mut commands, Preset::Spark, ball_t.translation.truncate(), 6);
+            }
+        }
+    }
+}
+
+
+// walls can also be worn down by explosions, see cmp_explosion::system_blast
+pub fn system_break(
+    mut commands: Commands,
     game_assets: Res<GameAsset>,
     audio: Res<Audio>,
+    debris_config: Res<DebrisConfig>,
     mut event: EventWriter<ev_despawn::Despawn>,
-    mut wall_q: Query<(Entity, &mut Status, &Transform, &BreakableSync), (With<T1>, Without<T2>)>,
-    mut ball_q: Query<(Entity, &mut Status, &Transform), (With<Ball>, With<T2>, Without<T1>)>,
+    wall_q: Query<(Entity, &Team, &Status, &Transform, &BreakableSync, Option<&BBSize>, Option<&PolygonalShape>)>,
 ) {
     let game_assets = game_assets.into_inner();
-    for (wall_e, mut wall_s, wall_t, breakable_sync) in wall_q.iter_mut() {
-        for (ball_e, mut ball_s, ball_t) in ball_q.iter_mut() {
-            if rapier_context.contact_pair(wall_e.clone(), ball_e.clone()).is_some() {
-                ball_s.hp = ball_s.hp - wall_s.attack;
-                wall_s.hp = wall_s.hp - 1.0;
-
-                if wall_s.hp <= 0.0 {
-                    for entity in breakable_sync.entities.iter() {
-                        commands.entity(wall_e).despawn();
-                        event.send(ev_despawn::Despawn(*entity));
-                        commands.spawn(cmp_explosion::ExplosionBundle::from((wall_t.translation, EXPLOSION_RADIUS, game_assets)))
-                                .insert(T1::default());
-
-                        audio.play(game_assets.audio_handles.get("explosion_handle").unwrap().clone());
-                    }
-                }
+    for (wall_e, wall_team, wall_s, wall_t, breakable_sync, bbsize, polygon) in wall_q.iter() {
+        if wall_s.hp <= 0.0 && breakable_sync.entities.len() > 0 {
+            cmp_debris::fracture(&mut commands, &debris_config, wall_t, bbsize, polygon, Color::BLACK);
+            cmp_debris::play_break_sound(&audio, game_assets);
+            commands.entity(wall_e).despawn_recursive();
+
+            for entity in breakable_sync.entities.iter() {
+                event.send(ev_despawn::Despawn(*entity));
+                commands.spawn(cmp_explosion::ExplosionBundle::from((wall_t.translation, EXPLOSION_RADIUS, game_assets)))
+                        .insert(*wall_team);
+
+                audio.play(game_assets.audio_handles.get("explosion_handle").unwrap().clone());
             }
         }
     }
@@ -190,80 +186,56 @@ pub fn system_color(
 }
 
 use crate::ev_save_load_world::LoadWorldEvent;
-const FILE_NAME1: &str = "/breakable_sync_p1.map";
-pub fn load_p1(
+const FILE_NAME: &str = "/breakable_sync.map";
+// written before teams existed, one file per player
+const LEGACY_FILE_NAMES: [(&str, Team); 2] = [("/breakable_sync_p1.map", Team(0)),
+                                              ("/breakable_sync_p2.map", Team(1))];
+pub fn load(
     mut load_world_er: EventReader<LoadWorldEvent>,
     mut commands: Commands,
     ) {
 
     for e in load_world_er.iter() {
         let dir = e.0.clone();
-
-        let json_str = std::fs::read_to_string(dir + FILE_NAME1);
-        if let Ok(json_str) = json_str {
-            let elem_list: Vec<(u32, Status, BreakableSync)> = serde_json::from_str(&json_str).unwrap();
-
-            for (i, s, b) in elem_list {
-                let mut entity = commands.get_or_spawn(Entity::from_raw(i));
-                entity.insert(BreakableSyncP1Bundle::from((s, b)));
+        let mut elem_list: Vec<(u32, Team, Status, BreakableSync)> = vec![];
+
+        if let Ok(json_str) = std::fs::read_to_string(dir.clone() + FILE_NAME) {
+            elem_list = serde_json::from_str(&json_str).unwrap();
+        } else {
+            for (file_name, team) in LEGACY_FILE_NAMES {
+                if let Ok(json_str) = std::fs::read_to_string(dir.clone() + file_name) {
+                    let legacy_list: Vec<(u32, Status, BreakableSync)> = serde_json::from_str(&json_str).unwrap();
+                    for (i, s, b) in legacy_list {
+                        elem_list.push((i, team, s, b));
+                    }
+                }
             }
         }
-    }
-}
-
-
-use crate::ev_save_load_world::SaveWorldEvent;
-pub fn save_p1(mut save_world_er: EventReader<SaveWorldEvent>,
-              q: Query<(Entity, &Status, &BreakableSync), With<Player1>>,
-              ) {
-
-    for e in save_world_er.iter() {
-        let dir = e.0.clone();
-        let mut elem_list: Vec<(u32, Status, BreakableSync)> = vec![];
-
-        for (e, s, b) in q.iter() {
-            elem_list.push((e.index(), s.to_owned(), b.to_owned()));
-        }
-
-        std::fs::write(dir + FILE_NAME1, serde_json::to_string(&elem_list).unwrap()).unwrap();
-    }
-}
 
-const FILE_NAME2: &str = "/breakable_sync_p2.map";
-pub fn load_p2(
-    mut load_world_er: EventReader<LoadWorldEvent>,
-    mut commands: Commands,
-    ) {
-
-    for e in load_world_er.iter() {
-        let dir = e.0.clone();
-
-        let json_str = std::fs::read_to_string(dir + FILE_NAME2);
-        if let Ok(json_str) = json_str {
-            let elem_list: Vec<(u32, Status, BreakableSync)> = serde_json::from_str(&json_str).unwrap();
-
-            for (i, s, b) in elem_list {
-                let mut entity = commands.get_or_spawn(Entity::from_raw(i));
-                entity.insert(BreakableSyncP2Bundle::from((s, b)));
-            }
+        for (i, team, s, b) in elem_list {
+            let mut entity = commands.get_or_spawn(Entity::from_raw(i));
+            entity.insert(BreakableSyncBundle::from((team, s, b)));
         }
     }
 }
 
 
-pub fn save_p2(mut save_world_er: EventReader<SaveWorldEvent>,
-              q: Query<(Entity, &Status, &BreakableSync), With<Player2>>,
+use crate::ev_save_load_world::SaveWorldEvent;
+pub fn save(mut save_world_er: EventReader<SaveWorldEvent>,
+              q: Query<(Entity, &Team, &Status, &BreakableSync)>,
               ) {
 
     for e in save_world_er.iter() {
         let dir = e.0.clone();
-        let mut elem_list: Vec<(u32, Status, BreakableSync)> = vec![];
+        let mut elem_list: Vec<(u32, Team, Status, BreakableSync)> = vec![];
 
-        for (e, s, b) in q.iter() {
-            elem_list.push((e.index(), s.to_owned(), b.to_owned()));
+        for (e, team, s, b) in q.iter() {
+            elem_list.push((e.index(), *team, s.to_owned(), b.to_owned()));
         }
 
-        std::fs::write(dir + FILE_NAME2, serde_json::to_string(&elem_list).unwrap()).unwrap();
+        std::fs::write(dir.clone() + FILE_NAME, serde_json::to_string(&elem_list).unwrap()).unwrap();
+        for (file_name, _) in LEGACY_FILE_NAMES {
+            let _ = std::fs::remove_file(dir.clone() + file_name);
+        }
     }
 }
-
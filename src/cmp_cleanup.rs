@@ -0,0 +1,208 @@
+use serde::{Serialize, Deserialize};
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::cmp_ball::Ball;
+use crate::cmp_bbsize::BBSize;
+use crate::contacts;
+use crate::edit_context::*;
+use crate::ev_save_load_world;
+
+pub const KILL_ZONE_SIZE: f32 = 256.0;
+const MAX_BALLS: usize = 3000;
+
+
+#[derive(Reflect, FromReflect, Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
+pub enum Overflow {
+    // the longest living balls make room
+    RemoveOldest,
+    // the lowest balls make room, usually the ones already fallen through
+    RemoveLowest,
+    // new balls are removed right away
+    RejectNew,
+}
+
+#[derive(Resource, Reflect, Clone, Serialize, Deserialize, Debug)]
+pub struct CleanupConfig {
+    // balls outside (min, max) are removed
+    pub bounds: Option<(Vec2, Vec2)>,
+    // seconds a ball lives at most
+    pub life_time: Option<f32>,
+    // safety cap on the number of live balls
+    pub max_balls: Option<usize>,
+    pub overflow: Overflow,
+}
+
+impl Default for CleanupConfig {
+    fn default() -> Self {
+        Self {
+            bounds: None,
+            life_time: None,
+            max_balls: Some(MAX_BALLS),
+            overflow: Overflow::RemoveOldest,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RemoveReason {
+    OutOfBounds,
+    KillZone,
+    LifeTime,
+    Overflow,
+}
+
+// sent when the rules above remove a ball, not when it scores or dies
+pub struct BallRemoved {
+    pub position: Vec2,
+    pub reason: RemoveReason,
+}
+
+// when a ball came into the game, for life time and overflow
+#[derive(Component)]
+pub struct Born(f32);
+
+// quietly removes every ball that touches it, unlike the Shredder
+#[derive(Component, Reflect, Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct KillZone {
+    pub size: Vec2,
+    pub position: Vec2,
+    pub angle: f32,
+}
+
+
+// `index` reuses the entity saved in a map, so a rematch reload replaces it
+pub fn add(commands: &mut Commands, index: Option<u32>, kill_zone: KillZone) -> Entity {
+    let mut entity = ev_save_load_world::get_or_spawn(commands, index);
+    entity
+        .insert(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(1.0, 0.1, 0.1, 0.2),
+                custom_size: Some(kill_zone.size),
+                ..default()
+            },
+            transform: Transform {
+                translation: Vec3::from((kill_zone.position, -1.0)),
+                rotation: Quat::from_rotation_z(kill_zone.angle),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(Collider::cuboid(kill_zone.size.x / 2.0, kill_zone.size.y / 2.0))
+        .insert(Sensor)
+        .insert(BBSize{x: kill_zone.size.x, y: kill_zone.size.y})
+        .insert(MapObject::KillZone)
+        .insert(kill_zone)
+        .id()
+}
+
+
+fn remove(commands: &mut Commands, removed_ew: &mut EventWriter<BallRemoved>, entity: Entity, transform: &Transform, reason: RemoveReason) {
+    commands.entity(entity).despawn_recursive();
+    removed_ew.send(BallRemoved { position: transform.translation.truncate(), reason });
+}
+
+pub fn system(
+    mut commands: Commands,
+    time: Res<Time>,
+    config: Res<CleanupConfig>,
+    rapier_context: Res<RapierContext>,
+    mut removed_ew: EventWriter<BallRemoved>,
+    newborn_q: Query<Entity, (With<Ball>, Without<Born>)>,
+    zone_q: Query<Entity, With<KillZone>>,
+    ball_q: Query<(Entity, &Transform, &Born), With<Ball>>,
+) {
+    let now = time.elapsed_seconds();
+    for entity in newborn_q.iter() {
+        commands.entity(entity).insert(Born(now));
+    }
+
+    let mut alive = vec![];
+    for (entity, transform, born) in ball_q.iter() {
+        let position = transform.translation.truncate();
+
+        let out = config.bounds.map_or(false, |(min, max)| position.cmplt(min).any() || position.cmpgt(max).any());
+        let in_zone = contacts::intersecting(&rapier_context, entity).any(|other| zone_q.contains(other));
+        let expired = config.life_time.map_or(false, |life_time| now - born.0 > life_time);
+
+        if out {
+            remove(&mut commands, &mut removed_ew, entity, transform, RemoveReason::OutOfBounds);
+        } else if in_zone {
+            remove(&mut commands, &mut removed_ew, entity, transform, RemoveReason::KillZone);
+        } else if expired {
+            remove(&mut commands, &mut removed_ew, entity, transform, RemoveReason::LifeTime);
+        } else {
+            alive.push((entity, transform, born.0));
+        }
+    }
+
+    // balls born this frame count from the next one, so the cap can lag a frame
+    let Some(max_balls) = config.max_balls else { return; };
+    if alive.len() <= max_balls { return; }
+
+    match config.overflow {
+        Overflow::RemoveOldest => alive.sort_by(|a, b| a.2.partial_cmp(&b.2).unwrap()),
+        Overflow::RemoveLowest => alive.sort_by(|a, b| a.1.translation.y.partial_cmp(&b.1.translation.y).unwrap()),
+        Overflow::RejectNew => alive.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap()),
+    }
+    let excess = alive.len() - max_balls;
+    for (entity, transform, _) in alive.into_iter().take(excess) {
+        remove(&mut commands, &mut removed_ew, entity, transform, RemoveReason::Overflow);
+    }
+}
+
+
+const FILE_NAME: &str = "/cleanup.map";
+const KILL_ZONE_FILE_NAME: &str = "/kill_zone.map";
+use crate::ev_save_load_world::LoadWorldEvent;
+pub fn load(
+    mut load_world_er: EventReader<LoadWorldEvent>,
+    mut commands: Commands,
+    mut config: ResMut<CleanupConfig>,
+    ) {
+
+    for e in load_world_er.iter() {
+        let dir = e.0.clone();
+
+        let json_str = std::fs::read_to_string(dir.clone() + FILE_NAME);
+        if let Ok(json_str) = json_str {
+            *config = serde_json::from_str(&json_str).unwrap();
+        } else {
+            *config = CleanupConfig::default();
+        }
+
+        let json_str = std::fs::read_to_string(dir + KILL_ZONE_FILE_NAME);
+        if let Ok(json_str) = json_str {
+            let elem_list: Vec<(u32, KillZone)> = serde_json::from_str(&json_str).unwrap();
+
+            for (i, kill_zone) in elem_list {
+                add(&mut commands, Some(i), kill_zone);
+            }
+        }
+    }
+}
+
+
+use crate::ev_save_load_world::SaveWorldEvent;
+pub fn save(mut save_world_er: EventReader<SaveWorldEvent>,
+              config: Res<CleanupConfig>,
+              q: Query<(Entity, &Transform, &KillZone)>,
+              ) {
+
+    for e in save_world_er.iter() {
+        let dir = e.0.clone();
+
+        std::fs::write(dir.clone() + FILE_NAME, serde_json::to_string(&*config).unwrap()).unwrap();
+
+        let mut elem_list: Vec<(u32, KillZone)> = vec![];
+        for (entity, t, kill_zone) in q.iter() {
+            let mut kill_zone = kill_zone.clone();
+            kill_zone.size = kill_zone.size * t.scale.truncate();
+            kill_zone.position = t.translation.truncate();
+            kill_zone.angle = t.rotation.to_euler(EulerRot::XYZ).2;
+            elem_list.push((entity.index(), kill_zone));
+        }
+
+        std::fs::write(dir + KILL_ZONE_FILE_NAME, serde_json::to_string(&elem_list).unwrap()).unwrap();
+    }
+}
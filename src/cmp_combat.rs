@@ -2,77 +2,354 @@ use serde::{Serialize, Deserialize};
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 use rand::prelude::*;
-use crate::cmp_blood;
+use std::collections::{HashMap, HashSet};
+use crate::cmp_particle;
+use crate::cmp_particle::Preset;
 use crate::cmp_ball;
 use crate::cmp_game_asset::GameAsset;
 use crate::cmp_ball::Ball;
+use crate::cmp_ball_species::Tint;
+use crate::cmp_team::Team;
+use crate::cmp_team::TeamConfig;
+use crate::contacts;
 
-#[derive(Component, Reflect, Clone, Serialize, Deserialize, Debug)]
+const MAX_VELOCITY_SCALE: f32 = 3.0;
+const EXPLOSION_STRUCTURE_DAMAGE: f32 = 0.1;
+// about a ball fired by the auto artillery
+const REFERENCE_SPEED: f32 = 400.0;
+
+#[derive(Component, Reflect, Default, Clone, Serialize, Deserialize, Debug)]
 pub struct Status {
     pub hp: f32,
     pub hp_max: f32,
     pub attack: f32,
+    // flat reduction applied before resistance
+    #[serde(default)]
+    pub armor: f32,
+    // fraction of the remaining damage ignored, 0.0 ..= 1.0
+    #[serde(default)]
+    pub resistance: f32,
+    // effects inflicted on whatever this deals damage to
+    #[serde(default)]
+    pub on_hit: Vec<Effect>,
+    // set by damage(), cleared by whoever reacts to hits. Effect ticks
+    // don't count
+    #[serde(skip)]
+    pub hit: bool,
 }
 
+impl Status {
+    // returns the damage actually taken
+    pub fn damage(&mut self, amount: f32) -> f32 {
+        let taken = (amount - self.armor).max(0.0) * (1.0 - self.resistance.clamp(0.0, 1.0));
+        self.hp = self.hp - taken;
+        if taken > 0.0 {
+            self.hit = true;
+        }
+        taken
+    }
+}
 
-#[derive(Component, Reflect, Default, Clone, Serialize, Deserialize, Debug)]
-pub struct Player1;
-#[derive(Component, Reflect, Default, Clone, Serialize, Deserialize, Debug)]
-pub struct Player2;
-
-fn display_contact_info(entity1: Entity, entity2: Entity, rapier_context: &Res<RapierContext>) {
-    /* Find the contact pair, if it exists, between two colliders. */
-    if let Some(contact_pair) = rapier_context.contact_pair(entity1, entity2) {
-        // The contact pair exists meaning that the broad-phase identified a potential contact.
-        if contact_pair.has_any_active_contacts() {
-            // The contact pair has active contacts, meaning that it
-            // contains contacts for which contact forces were computed.
-        }
-
-        // We may also read the contact manifolds to access the contact geometry.
-        for manifold in contact_pair.manifolds() {
-            println!("Local-space contact normal: {}", manifold.local_n1());
-            println!("Local-space contact normal: {}", manifold.local_n2());
-            println!("World-space contact normal: {}", manifold.normal());
-
-            // Read the geometric contacts.
-            for contact_point in manifold.points() {
-                // Keep in mind that all the geometric contact data are expressed in the local-space of the colliders.
-                println!("Found local contact point 1: {:?}", contact_point.local_p1());
-                println!("Found contact distance: {:?}", contact_point.dist()); // Negative if there is a penetration.
-            }
+
+#[derive(Reflect, FromReflect, Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
+pub enum EffectKind {
+    Burn { dps: f32 },
+    // fraction of the velocity kept per second
+    Slow { factor: f32 },
+    Regen { hps: f32 },
+}
+
+impl EffectKind {
+    // which effect shows when several run at once, harm before help
+    fn priority(&self) -> u8 {
+        match self {
+            EffectKind::Burn { .. } => 0,
+            EffectKind::Slow { .. } => 1,
+            EffectKind::Regen { .. } => 2,
+        }
+    }
+}
+
+impl Default for EffectKind {
+    fn default() -> Self {
+        EffectKind::Burn { dps: 0.0 }
+    }
+}
+
+#[derive(Reflect, FromReflect, Default, Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
+pub struct Effect {
+    pub kind: EffectKind,
+    // f32::INFINITY for unit abilities, which never wear off
+    pub duration: f32,
+}
+
+#[derive(Component, Default, Clone, Debug)]
+pub struct Effects {
+    pub list: Vec<Effect>,
+}
+
+impl Effects {
+    // refresh an effect of the same kind instead of stacking it, a
+    // permanent one runs alongside
+    pub fn add(&mut self, effect: Effect) {
+        let same = self.list.iter_mut()
+            .find(|e| e.duration.is_finite() && std::mem::discriminant(&e.kind) == std::mem::discriminant(&effect.kind));
+
+        if let Some(e) = same {
+            *e = effect;
+        } else {
+            self.list.push(effect);
+        }
+    }
+}
+
+
+#[derive(Reflect, FromReflect, Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
+pub enum ContactMode {
+    // one hit when a contact pair starts touching
+    OnStart,
+    // repeated hits while touching, at most once per `seconds`
+    Cooldown { seconds: f32 },
+}
+
+fn default_explosion_structure_damage() -> f32 { EXPLOSION_STRUCTURE_DAMAGE }
+
+#[derive(Resource, Reflect, Clone, Serialize, Deserialize, Debug)]
+pub struct CombatConfig {
+    pub contact_mode: ContactMode,
+    // relative speed that deals exactly `attack`; 0.0 disables velocity scaling
+    pub reference_speed: f32,
+    pub knockback: f32,
+    // impulse at the center of a blast, falls off to zero at its edge
+    pub explosion_knockback: f32,
+    // fraction of an explosion's attack dealt to breakable walls
+    #[serde(default = "default_explosion_structure_damage")]
+    pub explosion_structure_damage: f32,
+    // explosions cut craters into PolygonalShape terrain
+    #[serde(default)]
+    pub carve_terrain: bool,
+}
+
+impl Default for CombatConfig {
+    fn default() -> Self {
+        Self {
+            contact_mode: ContactMode::Cooldown { seconds: 0.2 },
+            reference_speed: REFERENCE_SPEED,
+            knockback: 6.0,
+            explosion_knockback: 20.0,
+            explosion_structure_damage: EXPLOSION_STRUCTURE_DAMAGE,
+            carve_terrain: false,
+        }
+    }
+}
+
+impl CombatConfig {
+    pub fn velocity_scale(&self, relative_speed: f32) -> f32 {
+        if self.reference_speed > 0.0 {
+            (relative_speed / self.reference_speed).clamp(0.0, MAX_VELOCITY_SCALE)
+        } else {
+            1.0
+        }
+    }
+}
+
+
+// Remembers which pairs touched last frame and when they last hit,
+// so contact damage no longer depends on frame rate.
+#[derive(Default)]
+pub struct ContactTracker {
+    touching: HashSet<(Entity, Entity)>,
+    touching_prev: HashSet<(Entity, Entity)>,
+    last_hit: HashMap<(Entity, Entity), f32>,
+}
+
+impl ContactTracker {
+    pub fn begin_frame(&mut self, now: f32, mode: ContactMode) {
+        self.touching_prev = std::mem::take(&mut self.touching);
+
+        if let ContactMode::Cooldown { seconds } = mode {
+            self.last_hit.retain(|_, t| now - *t < seconds);
+        } else {
+            self.last_hit.clear();
+        }
+    }
+
+    // call for every pair that is touching this frame; true if it should deal damage
+    pub fn hit(&mut self, pair: (Entity, Entity), now: f32, mode: ContactMode) -> bool {
+        self.touching.insert(pair);
+
+        let ready = match mode {
+            ContactMode::OnStart => ! self.touching_prev.contains(&pair),
+            ContactMode::Cooldown { .. } => ! self.last_hit.contains_key(&pair),
+        };
+
+        if ready {
+            self.last_hit.insert(pair, now);
+        }
+        ready
+    }
+}
+
+
+pub fn inflict(commands: &mut Commands, entity: Entity, effects: Option<Mut<Effects>>, on_hit: &Vec<Effect>) {
+    if on_hit.len() == 0 { return; }
+
+    if let Some(mut effects) = effects {
+        for effect in on_hit.iter() {
+            effects.add(*effect);
+        }
+    } else {
+        let mut effects = Effects::default();
+        for effect in on_hit.iter() {
+            effects.add(*effect);
         }
+        commands.entity(entity).insert(effects);
     }
 }
 
+
 pub fn system(
     mut commands: Commands,
-    audio: Res<Audio>,
-    game_assets: Res<GameAsset>,
+    time: Res<Time>,
+    config: Res<CombatConfig>,
+    team_config: Res<TeamConfig>,
     rapier_context: Res<RapierContext>,
-    mut p1_q: Query<(Entity, &mut Status, &Transform, &mut ExternalImpulse), (With<Ball>, With<Player1>, Without<Player2>)>,
-    mut p2_q: Query<(Entity, &mut Status, &Transform, &mut ExternalImpulse), (With<Ball>, With<Player2>, Without<Player1>)>,
+    mut tracker: Local<ContactTracker>,
+    mut unit_q: Query<(Entity, &Team, &mut Status, &Transform, &Velocity, &mut ExternalImpulse, Option<&mut Effects>), With<Ball>>,
+) {
+    let now = time.elapsed_seconds();
+    tracker.begin_frame(now, config.contact_mode);
+
+    // only the pairs Rapier found touching, not every pair of units
+    for contact_pair in rapier_context.contact_pairs() {
+        if ! contact_pair.has_any_active_contacts() { continue; }
+
+        let (e1, e2) = (contact_pair.collider1(), contact_pair.collider2());
+        let Ok([(e1, team1, mut s1, t1, v1, mut ei1, fx1), (e2, team2, mut s2, t2, v2, mut ei2, fx2)]) = unit_q.get_many_mut([e1, e2]) else { continue; };
+        if ! team_config.hostile(*team1, *team2) { continue; }
+
+        if tracker.hit((e1, e2), now, config.contact_mode) {
+            let scale = config.velocity_scale((v1.linvel - v2.linvel).length());
+            let attack1 = s1.attack;
+            s1.damage(s2.attack * scale);
+            s2.damage(attack1 * scale);
+
+            inflict(&mut commands, e1, fx1, &s2.on_hit);
+            inflict(&mut commands, e2, fx2, &s1.on_hit);
+
+            cmp_particle::burst(&mut commands, Preset::Blood, t1.translation.truncate(), 3);
+            cmp_particle::burst(&mut commands, Preset::Blood, t2.translation.truncate(), 3);
+
+            if let Some(manifold) = contact_pair.manifolds().next() {
+                ei1.impulse = manifold.local_n1() * config.knockback;
+                ei2.impulse = -manifold.local_n1() * config.knockback;
+            }
+        }
+    }
+}
+
+
+pub fn system_effects(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Effects, &mut Status, Option<&mut Velocity>)>,
 ) {
-    for (p1_e, mut p1_c, p1_t, mut p1_ei) in p1_q.iter_mut() {
-        for (p2_e, mut p2_c, p2_t, mut p2_ei) in p2_q.iter_mut() {
-            if let Some(contact_pair) = rapier_context.contact_pair(p1_e.clone(), p2_e.clone()) {
-                if contact_pair.has_any_active_contacts() {
-                    p1_c.hp = p1_c.hp - p2_c.attack;
-                    p2_c.hp = p2_c.hp - p1_c.attack;
-
-                    cmp_blood::add(&mut commands, p1_t.translation.truncate(), 1);
-                    cmp_blood::add(&mut commands, p2_t.translation.truncate(), 1);
-
-                    let manifold = contact_pair.manifolds().next().unwrap();
-                    println!("Local-space contact normal: {}", manifold.local_n1());
-                    println!("Local-space contact normal: {}", manifold.local_n2());
-                    p1_ei.impulse = manifold.local_n1() * 6.0;
-                    p2_ei.impulse = -manifold.local_n1() * 6.0;
-                    //p1_ei.impulse.y += 2.5;
-                    //p2_ei.impulse.y += 2.5;
+    let dt = time.delta_seconds();
+
+    for (entity, mut effects, mut status, velocity) in query.iter_mut() {
+        let mut slow = 1.0;
+
+        for effect in effects.list.iter_mut() {
+            match effect.kind {
+                EffectKind::Burn { dps } => {
+                    status.hp = status.hp - dps * dt;
+                }
+                EffectKind::Slow { factor } => {
+                    slow = slow * factor.clamp(0.0, 1.0).powf(dt);
+                }
+                EffectKind::Regen { hps } => {
+                    if status.hp > 0.0 {
+                        status.hp = (status.hp + hps * dt).min(status.hp_max);
+                    }
                 }
             }
+            effect.duration = effect.duration - dt;
+        }
+
+        if let Some(mut velocity) = velocity {
+            velocity.linvel = velocity.linvel * slow;
+        }
+
+        effects.list.retain(|e| e.duration > 0.0);
+        if effects.list.len() == 0 {
+            commands.entity(entity).remove::<Effects>();
+        }
+    }
+}
+
+
+// tint balls by the running effect with the highest priority
+pub fn system_effect_color(
+    mut query: Query<(Option<&mut Sprite>, Option<&mut TextureAtlasSprite>, Option<&Effects>, Option<&Tint>), With<Ball>>,
+) {
+    for (sprite, atlas_sprite, effects, tint) in query.iter_mut() {
+        // abilities are part of the unit, not something that happened to it
+        let shown = effects.and_then(|fx| fx.list.iter()
+            .filter(|e| e.duration.is_finite())
+            .map(|e| e.kind)
+            .min_by_key(|k| k.priority()));
+        let effect = match shown {
+            Some(EffectKind::Burn { .. }) => Color::rgb(1.0, 0.5, 0.3),
+            Some(EffectKind::Slow { .. }) => Color::rgb(0.5, 0.7, 1.0),
+            Some(EffectKind::Regen { .. }) => Color::rgb(0.6, 1.0, 0.6),
+            None => Color::WHITE,
+        };
+        let base = tint.map_or(Color::WHITE, |t| t.0);
+        let color = Color::rgb(base.r() * effect.r(), base.g() * effect.g(), base.b() * effect.b());
+
+        // animated units have an atlas sprite instead
+        if let Some(mut sprite) = sprite {
+            if sprite.color != color {
+                sprite.color = color;
+            }
+        }
+        if let Some(mut sprite) = atlas_sprite {
+            if sprite.color != color {
+                sprite.color = color;
+            }
         }
     }
 }
 
+
+const FILE_NAME: &str = "/combat_config.map";
+use crate::ev_save_load_world::LoadWorldEvent;
+pub fn load(
+    mut load_world_er: EventReader<LoadWorldEvent>,
+    mut config: ResMut<CombatConfig>,
+    ) {
+
+    for e in load_world_er.iter() {
+        let dir = e.0.clone();
+
+        let json_str = std::fs::read_to_string(dir + FILE_NAME);
+        if let Ok(json_str) = json_str {
+            *config = serde_json::from_str(&json_str).unwrap();
+        } else {
+            *config = CombatConfig::default();
+        }
+    }
+}
+
+
+use crate::ev_save_load_world::SaveWorldEvent;
+pub fn save(mut save_world_er: EventReader<SaveWorldEvent>,
+              config: Res<CombatConfig>,
+              ) {
+
+    for e in save_world_er.iter() {
+        let dir = e.0.clone();
+
+        std::fs::write(dir + FILE_NAME, serde_json::to_string(&*config).unwrap()).unwrap();
+    }
+}
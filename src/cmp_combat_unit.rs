@@ -0,0 +1,239 @@
+use serde::{Serialize, Deserialize};
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use std::collections::HashMap;
+
+use crate::cmp_ball;
+use crate::cmp_ball::Ball;
+use crate::cmp_ball::BallBundle;
+use crate::cmp_combat::Effect;
+use crate::cmp_combat::EffectKind;
+use crate::cmp_combat::Effects;
+use crate::cmp_combat::Status;
+use crate::cmp_explosion;
+use crate::cmp_game_asset;
+use crate::cmp_game_asset::GameAsset;
+use crate::cmp_rotator::Rotator;
+use crate::cmp_sprite_animation;
+use crate::cmp_sprite_animation::Character;
+use crate::cmp_sprite_animation::Clips;
+use crate::cmp_sprite_animation::SpriteAnimation;
+use crate::cmp_team::Team;
+use crate::cmp_team::TeamCollision;
+use crate::cmp_team::TeamConfig;
+
+const FILE_PATH: &str = "assets/combat_units.json";
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub enum OnDeath {
+    Blood,
+    Explosion { radius: f32, attack: Option<f32> },
+}
+
+impl Default for OnDeath {
+    fn default() -> Self {
+        OnDeath::Blood
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub enum UnitAbility {
+    Regen { hp_per_sec: f32 },
+    Heavy { gravity_scale: f32 },
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct UnitDef {
+    pub name: String,
+    pub radius: f32,
+    pub hp: f32,
+    pub attack: f32,
+    // units of odd teams spin the other way
+    pub angvel: f32,
+    // one sprite per team, the last one is reused for higher teams
+    pub sprites: Vec<String>,
+    // animated characters picked the same way, replacing `sprites`,
+    // see cmp_sprite_animation
+    #[serde(default)]
+    pub animations: Vec<String>,
+    // price when bought through the economy
+    #[serde(default)]
+    pub cost: f32,
+    #[serde(default)]
+    pub armor: f32,
+    #[serde(default)]
+    pub resistance: f32,
+    #[serde(default)]
+    pub on_hit: Vec<Effect>,
+    #[serde(default)]
+    pub restitution: Option<f32>,
+    #[serde(default)]
+    pub friction: Option<f32>,
+    #[serde(default)]
+    pub mass: Option<f32>,
+    #[serde(default)]
+    pub on_death: OnDeath,
+    #[serde(default)]
+    pub abilities: Vec<UnitAbility>,
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct UnitRegistry {
+    pub units: HashMap<String, UnitDef>,
+}
+
+impl UnitRegistry {
+    pub fn get(&self, name: &str) -> Option<&UnitDef> {
+        self.units.get(name)
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.units.keys().cloned().collect();
+        names.sort();
+        names
+    }
+}
+
+#[derive(Component, Clone, Debug)]
+pub struct Unit {
+    pub name: String,
+    pub on_death: OnDeath,
+}
+
+pub struct UnitDied {
+    pub name: String,
+    pub team: Team,
+}
+
+
+pub fn setup(mut registry: ResMut<UnitRegistry>, game_assets: Res<GameAsset>) {
+    // read from the working directory, so units can be balanced without rebuilding
+    let unit_list: Vec<UnitDef> = cmp_game_asset::read_list(FILE_PATH, include_str!("../assets/combat_units.json"));
+
+    for unit in unit_list {
+        if unit.sprites.is_empty() {
+            warn!("{}: skipping {}, no sprites", FILE_PATH, unit.name);
+            continue;
+        }
+        if let Some(sprite) = unit.sprites.iter().find(|s| ! game_assets.image_handles.contains_key(*s)) {
+            warn!("{}: skipping {}, unknown sprite {}", FILE_PATH, unit.name, sprite);
+            continue;
+        }
+        registry.units.insert(unit.name.clone(), unit);
+    }
+}
+
+
+pub fn spawn(commands: &mut Commands,
+             registry: &UnitRegistry,
+             team_config: &TeamConfig,
+             game_assets: &GameAsset,
+             name: &str,
+             team: Team,
+             translation: Vec2,
+             velocity: Vec2) -> Option<Entity> {
+    let def = registry.get(name)?;
+
+    let sprite = &def.sprites[(team.0 as usize).min(def.sprites.len() - 1)];
+    let angvel = if team.0 % 2 == 0 { def.angvel } else { -def.angvel };
+    let handle = game_assets.image_handles.get(sprite).unwrap();
+
+    let mut bundle = BallBundle::from((translation, def.radius, velocity, handle.clone()));
+    bundle.collision_groups = team_config.collision_groups(team, TeamCollision::Unit);
+    if let Some(restitution) = def.restitution {
+        bundle.restitution = Restitution::coefficient(restitution);
+    }
+    if let Some(friction) = def.friction {
+        bundle.friction = Friction::coefficient(friction);
+    }
+
+    let mut entity = commands.spawn(bundle);
+    entity
+        .insert(Unit { name: def.name.clone(), on_death: def.on_death.clone() })
+        .insert(Status {
+            hp: def.hp,
+            hp_max: def.hp,
+            attack: def.attack,
+            armor: def.armor,
+            resistance: def.resistance,
+            on_hit: def.on_hit.clone(),
+            ..default()
+        })
+        .insert(Rotator { angvel })
+        .insert(team)
+        .insert(TeamCollision::Unit);
+
+    if let Some(mass) = def.mass {
+        entity.insert(ColliderMassProperties::Mass(mass));
+    }
+
+    if ! def.animations.is_empty() {
+        let animation = &def.animations[(team.0 as usize).min(def.animations.len() - 1)];
+        let character = Character::new(animation);
+        entity
+            .remove::<(Sprite, Handle<Image>)>()
+            .insert(TextureAtlasSprite {
+                custom_size: Some(Vec2::ONE * (def.radius * 2.0)),
+                ..default()
+            })
+            .insert(game_assets.atlas_handles.get(animation).cloned().unwrap_or_default())
+            .insert(SpriteAnimation::new(&character.clip()))
+            .insert(character);
+    }
+
+    let mut effects = Effects::default();
+    for ability in def.abilities.iter() {
+        match ability {
+            UnitAbility::Regen { hp_per_sec } => {
+                effects.add(Effect { kind: EffectKind::Regen { hps: *hp_per_sec }, duration: f32::INFINITY });
+            }
+            UnitAbility::Heavy { gravity_scale } => {
+                entity.insert(GravityScale(*gravity_scale));
+            }
+        }
+    }
+    if ! effects.list.is_empty() {
+        entity.insert(effects);
+    }
+
+    Some(entity.id())
+}
+
+
+pub fn system_death(
+    mut commands: Commands,
+    audio: Res<Audio>,
+    game_assets: Res<GameAsset>,
+    clips: Res<Clips>,
+    mut died_ew: EventWriter<UnitDied>,
+    query: Query<(Entity, &Status, &Transform, &Ball, &Unit, &Team, Option<&Character>)>,
+) {
+    let game_assets = game_assets.into_inner();
+
+    for (e, s, t, ball, unit, team, character) in query.iter() {
+        if s.hp > 0.0 { continue; }
+        died_ew.send(UnitDied { name: unit.name.clone(), team: *team });
+
+        match unit.on_death {
+            OnDeath::Blood => {
+                cmp_ball::kill(&mut commands, &audio, game_assets, e, t, ball);
+                if let Some(character) = character {
+                    cmp_sprite_animation::add_corpse(&mut commands, &clips, &character.name, t, Vec2::ONE * (ball.radius * 2.0));
+                }
+            }
+
+            OnDeath::Explosion { radius, attack } => {
+                commands.entity(e).despawn();
+
+                let mut bundle = cmp_explosion::ExplosionBundle::from((t.translation, radius, game_assets));
+                if let Some(attack) = attack {
+                    bundle.status.attack = attack;
+                }
+
+                commands.spawn(bundle).insert(*team);
+                audio.play(game_assets.audio_handles.get("explosion_handle").unwrap().clone());
+            }
+        }
+    }
+}
+
@@ -6,6 +6,7 @@ use crate::cmp_game_asset::GameAsset;
 use crate::cmp_ball_zundamon::Zundamon;
 use crate::cmp_ball::Ball;
 use crate::cmp_zundamon_fullbody;
+use crate::contacts;
 
 #[derive(Component, Reflect, Clone, Serialize, Deserialize, Debug)]
 pub struct ConverterBody {
@@ -44,12 +45,11 @@ pub fn system(
     ball_q: Query<(Entity, &Transform, &Ball), With<Zundamon>>,
     cb_q: Query<Entity, With<ConverterBody>>,
 ) {
-    for (ball_e, ball_t, ball) in ball_q.iter() {
-        for cb_e in cb_q.iter() {
-            if rapier_context.intersection_pair(ball_e, cb_e) == Some(true) {
-                commands.get_entity(ball_e).unwrap().despawn();
-                cmp_zundamon_fullbody::add(&mut commands, &game_assets, ball_t.translation.truncate(), ball.radius * 2.0, Vec2::ZERO);
-            }
+    for cb_e in cb_q.iter() {
+        for other in contacts::intersecting(&rapier_context, cb_e) {
+            let Ok((ball_e, ball_t, ball)) = ball_q.get(other) else { continue; };
+            commands.get_entity(ball_e).unwrap().despawn();
+            cmp_zundamon_fullbody::add(&mut commands, &game_assets, ball_t.translation.truncate(), ball.radius * 2.0, Vec2::ZERO);
         }
     }
 }
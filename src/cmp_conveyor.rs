@@ -0,0 +1,241 @@
+use serde::{Serialize, Deserialize};
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use bevy_inspector_egui::bevy_egui::{egui, EguiContexts};
+
+use crate::constants;
+use crate::cmp_ball::Ball;
+use crate::cmp_bbsize::BBSize;
+use crate::cmp_gate_generic::SpawnBall;
+use crate::contacts;
+use crate::edit_context::*;
+use crate::ev_save_load_world;
+
+pub const DEFAULT_SPEED: f32 = 200.0;
+pub const DEFAULT_WIDTH: f32 = 16.0;
+// share of the missing belt speed a touching ball picks up per 1/60 s
+const GRIP: f32 = 0.3;
+const STRIPE_LENGTH: f32 = 12.0;
+const STRIPE_SPACING: f32 = 32.0;
+
+// A belt along a polyline in local space. Positive speed carries balls
+// from the first point towards the last.
+#[derive(Component, Reflect, Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct Conveyor {
+    pub polyline: Vec<Vec2>,
+    pub speed: f32,
+    pub width: f32,
+}
+
+// moving stripes drawn on top of the belt
+#[derive(Component)]
+pub struct ConveyorStripes;
+
+
+fn bounding_box(polyline: &Vec<Vec2>) -> Vec2 {
+    let min = polyline.iter().fold(Vec2::splat(f32::MAX), |m, p| m.min(*p));
+    let max = polyline.iter().fold(Vec2::splat(f32::MIN), |m, p| m.max(*p));
+    max - min
+}
+
+// dashes along the belt, shifted by `phase`
+fn stripes(polyline: &Vec<Vec2>, phase: f32) -> Path {
+    let mut path_builder = PathBuilder::new();
+    let mut travelled = 0.0;
+
+    for segment in polyline.windows(2) {
+        let (a, b) = (segment[0], segment[1]);
+        let length = a.distance(b);
+        let dir = (b - a).normalize_or_zero();
+
+        let mut start = (phase - travelled).rem_euclid(STRIPE_SPACING);
+        while start < length {
+            let end = (start + STRIPE_LENGTH).min(length);
+            path_builder.move_to(a + dir * start);
+            path_builder.line_to(a + dir * end);
+            start = start + STRIPE_SPACING;
+        }
+        travelled = travelled + length;
+    }
+    path_builder.build()
+}
+
+// `index` reuses the entity saved in a map, so signals still reach it
+pub fn add(commands: &mut Commands, index: Option<u32>, translation: Vec3, rotation: Quat, scale: Vec3, conveyor: Conveyor) -> Entity {
+    let bbsize = bounding_box(&conveyor.polyline);
+    let belt = shapes::Polygon { points: conveyor.polyline.clone(), closed: false };
+    let width = conveyor.width;
+
+    let mut entity = ev_save_load_world::get_or_spawn(commands, index);
+    entity
+        .insert((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&belt),
+                transform: Transform { translation, rotation, scale },
+                ..default()
+            },
+            Stroke::new(Color::DARK_GRAY, width),
+        ))
+        .insert(Collider::polyline(conveyor.polyline.clone(), None))
+        .insert(RigidBody::KinematicVelocityBased)
+        .insert(Friction::coefficient(constants::C_MAP_FRICTION))
+        .insert(BBSize{x: bbsize.x.max(width), y: bbsize.y.max(width)})
+        .insert(MapObject::Conveyor(vec![], vec![]))
+        .insert(conveyor)
+        .id()
+}
+
+
+pub fn system(
+    time: Res<Time>,
+    rapier_context: Res<RapierContext>,
+    conveyor_q: Query<(Entity, &Transform, &Conveyor)>,
+    mut ball_q: Query<(&Transform, &mut Velocity), With<Ball>>,
+) {
+    let grip = 1.0 - (1.0 - GRIP).powf(time.delta_seconds() * 60.0);
+
+    for (conveyor_e, conveyor_t, conveyor) in conveyor_q.iter() {
+        for other in contacts::touching(&rapier_context, conveyor_e) {
+            let Ok((ball_t, mut velocity)) = ball_q.get_mut(other) else { continue; };
+
+            // the segment the ball rests on decides the belt direction
+            let local = (conveyor_t.rotation.inverse() * (ball_t.translation - conveyor_t.translation)).truncate()
+                / conveyor_t.scale.truncate();
+            let nearest = conveyor.polyline.windows(2)
+                .map(|s| {
+                    let t = ((local - s[0]).dot(s[1] - s[0]) / (s[1] - s[0]).length_squared().max(1e-6)).clamp(0.0, 1.0);
+                    (local.distance(s[0] + (s[1] - s[0]) * t), s[1] - s[0])
+                })
+                .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+            let Some((_, along)) = nearest else { continue; };
+
+            let tangent = (conveyor_t.rotation * Vec3::from((along * conveyor_t.scale.truncate(), 0.0))).truncate().normalize_or_zero();
+            let missing = conveyor.speed - velocity.linvel.dot(tangent);
+            velocity.linvel += tangent * missing * grip;
+        }
+    }
+}
+
+
+// created lazily so reloading a map over a live conveyor keeps one set
+pub fn system_stripes(
+    mut commands: Commands,
+    time: Res<Time>,
+    conveyor_q: Query<(Entity, &Conveyor, Option<&Children>)>,
+    mut stripes_q: Query<&mut Path, With<ConveyorStripes>>,
+) {
+    for (entity, conveyor, children) in conveyor_q.iter() {
+        let phase = (time.elapsed_seconds() * conveyor.speed).rem_euclid(STRIPE_SPACING);
+        let path = stripes(&conveyor.polyline, phase);
+
+        let stripe = children.and_then(|c| c.iter().find(|child| stripes_q.contains(**child)));
+        if let Some(stripe) = stripe {
+            *stripes_q.get_mut(*stripe).unwrap() = path;
+        } else {
+            commands.entity(entity).with_children(|children| {
+                children.spawn((
+                    ConveyorStripes,
+                    ShapeBundle {
+                        path,
+                        transform: Transform::from_xyz(0.0, 0.0, 0.1),
+                        ..default()
+                    },
+                    Stroke::new(Color::GRAY, conveyor.width * 0.5),
+                ));
+            });
+        }
+    }
+}
+
+
+// any signal addressed to a conveyor, e.g. from a SpawnTimer or a
+// GateSplitter, reverses it
+pub fn system_signal(
+    mut event: EventReader<SpawnBall>,
+    mut conveyor_q: Query<&mut Conveyor>,
+) {
+    for e in event.iter() {
+        if let Ok(mut conveyor) = conveyor_q.get_mut(Entity::from_raw(e.0)) {
+            conveyor.speed = -conveyor.speed;
+        }
+    }
+}
+
+
+pub fn edit_window(
+    mut egui_contexts: EguiContexts,
+    edit_context: Res<EditContext>,
+    mut conveyor_q: Query<(&mut Conveyor, &mut Stroke, Option<&Children>)>,
+    mut stripes_q: Query<&mut Stroke, (With<ConveyorStripes>, Without<Conveyor>)>,
+    ) {
+    if let EditContext::Edit(MapObject::Conveyor(..), pick, _) = edit_context.clone() {
+        if pick.len() == 0 { return; }
+
+        if let Ok((mut conveyor, mut stroke, children)) = conveyor_q.get_mut(pick[0]) {
+            let mut c = conveyor.clone();
+
+            egui::Window::new("Conveyor").show(egui_contexts.ctx_mut(), |ui: &mut egui::Ui| {
+                ui.horizontal(|ui: &mut egui::Ui| {
+                    ui.label("speed");
+                    ui.add(egui::DragValue::new(&mut c.speed).clamp_range(-2000.0..=2000.0));
+                    if ui.button("reverse").clicked() {
+                        c.speed = -c.speed;
+                    }
+                    ui.label("width");
+                    ui.add(egui::DragValue::new(&mut c.width).clamp_range(2.0..=128.0));
+                });
+            });
+
+            if c != *conveyor {
+                stroke.options.line_width = c.width;
+                for child in children.into_iter().flatten() {
+                    if let Ok(mut stripe_stroke) = stripes_q.get_mut(*child) {
+                        stripe_stroke.options.line_width = c.width * 0.5;
+                    }
+                }
+                *conveyor = c;
+            }
+        }
+    }
+}
+
+
+const FILE_NAME: &str = "/conveyor.map";
+use crate::ev_save_load_world::LoadWorldEvent;
+pub fn load(
+    mut load_world_er: EventReader<LoadWorldEvent>,
+    mut commands: Commands,
+    ) {
+
+    for e in load_world_er.iter() {
+        let dir = e.0.clone();
+
+        let json_str = std::fs::read_to_string(dir + FILE_NAME);
+        if let Ok(json_str) = json_str {
+            let elem_list: Vec<(u32, Vec3, Quat, Vec3, Conveyor)> = serde_json::from_str(&json_str).unwrap();
+
+            for (i, t, r, s, c) in elem_list {
+                add(&mut commands, Some(i), t, r, s, c);
+            }
+        }
+    }
+}
+
+
+use crate::ev_save_load_world::SaveWorldEvent;
+pub fn save(mut save_world_er: EventReader<SaveWorldEvent>,
+              q: Query<(Entity, &Transform, &Conveyor)>,
+              ) {
+
+    for e in save_world_er.iter() {
+        let dir = e.0.clone();
+        let mut elem_list: Vec<(u32, Vec3, Quat, Vec3, Conveyor)> = vec![];
+
+        for (e, t, c) in q.iter() {
+            elem_list.push((e.index(), t.translation, t.rotation, t.scale, c.clone()));
+        }
+
+        std::fs::write(dir + FILE_NAME, serde_json::to_string(&elem_list).unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,330 @@
+use serde::{Serialize, Deserialize};
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use rand::prelude::*;
+
+use crate::cmp_bbsize::BBSize;
+use crate::cmp_breakable::Breakable;
+use crate::cmp_breakable_sync::BreakableSync;
+use crate::cmp_combat::Status;
+use crate::cmp_fuse_time::FuseTime;
+use crate::cmp_game_asset::GameAsset;
+use crate::cmp_polygonal_shape::PolygonalShape;
+
+const LIFE_TIME: f32 = 3.0;
+const PIECES: usize = 8;
+const DEFAULT_RESTITUTION: f32 = 0.1;
+const DEFAULT_FRICTION: f32 = 0.9;
+// polyline terrain breaks into slabs this thick and at most this long
+const SLAB_THICKNESS: f32 = 8.0;
+const SLAB_LENGTH: f32 = 60.0;
+const MAX_SPEED: f32 = 200.0;
+// hp ratios at which another crack appears
+const CRACK_THRESHOLDS: [f32; 3] = [0.75, 0.5, 0.25];
+const CRACK_STEPS: usize = 5;
+
+
+fn default_enabled() -> bool { true }
+
+#[derive(Resource, Reflect, Clone, Serialize, Deserialize, Debug)]
+pub struct DebrisConfig {
+    // off: broken walls just vanish, as before
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    pub pieces: usize,
+    pub life_time: f32,
+}
+
+impl Default for DebrisConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            pieces: PIECES,
+            life_time: LIFE_TIME,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Debris;
+
+// number of cracks drawn on a wall so far
+#[derive(Component)]
+pub struct Cracked {
+    level: usize,
+}
+
+#[derive(Component)]
+pub struct Crack;
+
+#[derive(Bundle)]
+pub struct DebrisBundle {
+    pub debris: Debris,
+    pub rigid_body: RigidBody,
+    pub restitution: Restitution,
+    pub friction: Friction,
+    pub collider: Collider,
+    pub collision_groups: CollisionGroups,
+    pub velocity: Velocity,
+    pub fuse_time: FuseTime,
+    pub fill: Fill,
+    #[bundle]
+    pub shape_bundle: ShapeBundle,
+}
+
+impl Default for DebrisBundle {
+    fn default() -> Self {
+        Self {
+            debris: Debris,
+            rigid_body: RigidBody::Dynamic,
+            restitution: Restitution::coefficient(DEFAULT_RESTITUTION),
+            friction: Friction::coefficient(DEFAULT_FRICTION),
+            collider: Collider::ball(1.0),
+            collision_groups: CollisionGroups::new(Group::GROUP_1, Group::GROUP_1),
+            velocity: Velocity::default(),
+            fuse_time: FuseTime{timer: Timer::from_seconds(LIFE_TIME, TimerMode::Once)},
+            fill: Fill::color(Color::BLACK),
+            shape_bundle: ShapeBundle::default(),
+        }
+    }
+}
+
+impl From<(Vec<Vec2>, Transform, Vec2, Color, f32)> for DebrisBundle {
+    fn from(tuple: (Vec<Vec2>, Transform, Vec2, Color, f32)) -> Self {
+        let (points, transform, velocity, color, life_time) = tuple;
+
+        let mut bundle = DebrisBundle::default();
+        if let Some(collider) = Collider::convex_hull(&points) {
+            bundle.collider = collider;
+        }
+        bundle.velocity.linvel = velocity;
+        bundle.fuse_time = FuseTime{timer: Timer::from_seconds(life_time, TimerMode::Once)};
+        bundle.fill = Fill::color(color);
+        bundle.shape_bundle = ShapeBundle {
+            path: GeometryBuilder::build_as(&shapes::Polygon {points, closed: true}),
+            transform,
+            ..default()
+        };
+
+        bundle
+    }
+}
+
+
+// box of `size` around the origin, cut into triangles along random diagonals
+fn box_pieces(size: Vec2, pieces: usize) -> Vec<Vec<Vec2>> {
+    let mut rng = rand::thread_rng();
+
+    let cells = (pieces / 2).max(1) as f32;
+    let cols = (cells * size.x / size.y.max(1.0)).sqrt().round().max(1.0);
+    let rows = (cells / cols).ceil().max(1.0);
+    let cell = size / Vec2::new(cols, rows);
+    let origin = -size / 2.0;
+
+    let mut result = vec![];
+    for x in 0..cols as usize {
+        for y in 0..rows as usize {
+            let p0 = origin + cell * Vec2::new(x as f32, y as f32);
+            let p1 = p0 + Vec2::new(cell.x, 0.0);
+            let p2 = p0 + cell;
+            let p3 = p0 + Vec2::new(0.0, cell.y);
+
+            if rng.gen::<bool>() {
+                result.push(vec![p0, p1, p2]);
+                result.push(vec![p0, p2, p3]);
+            } else {
+                result.push(vec![p0, p1, p3]);
+                result.push(vec![p1, p2, p3]);
+            }
+        }
+    }
+    result
+}
+
+// thick slabs along the polyline, long segments cut into several
+fn polyline_pieces(polyline: &Vec<Vec2>) -> Vec<Vec<Vec2>> {
+    let mut result = vec![];
+    for segment in polyline.windows(2) {
+        let (a, b) = (segment[0], segment[1]);
+        let normal = (b - a).perp().normalize_or_zero() * SLAB_THICKNESS;
+        let count = ((b - a).length() / SLAB_LENGTH).ceil().max(1.0) as usize;
+
+        for i in 0..count {
+            let s = a + (b - a) * i as f32 / count as f32;
+            let e = a + (b - a) * (i + 1) as f32 / count as f32;
+            result.push(vec![s, e, e - normal, s - normal]);
+        }
+    }
+    result
+}
+
+// Spawn the pieces a wall breaks into. Use either the wall's box or,
+// for terrain, its polygon; both are in the wall's local space.
+pub fn fracture(commands: &mut Commands,
+                config: &DebrisConfig,
+                transform: &Transform,
+                bbsize: Option<&BBSize>,
+                polygon: Option<&PolygonalShape>,
+                color: Color) {
+    if ! config.enabled { return; }
+
+    let pieces = if let Some(polygon) = polygon {
+        polyline_pieces(&polygon.polygon)
+    } else if let Some(bbsize) = bbsize {
+        box_pieces(Vec2::new(bbsize.x, bbsize.y), config.pieces)
+    } else {
+        return;
+    };
+
+    let mut rng = rand::thread_rng();
+    let scale = transform.scale.truncate();
+    for piece in pieces {
+        let center = piece.iter().fold(Vec2::ZERO, |sum, p| sum + *p) / piece.len() as f32;
+        let points: Vec<Vec2> = piece.iter().map(|p| (*p - center) * scale).collect();
+
+        let offset = transform.rotation * Vec3::from((center * scale, 0.0));
+        let piece_t = Transform {
+            translation: transform.translation + offset,
+            rotation: transform.rotation,
+            ..default()
+        };
+        let velocity = offset.truncate().normalize_or_zero() * rng.gen_range(0.0..MAX_SPEED);
+
+        commands.spawn(DebrisBundle::from((points, piece_t, velocity, color, config.life_time)));
+    }
+}
+
+pub fn play_break_sound(audio: &Audio, game_assets: &GameAsset) {
+    let handle = game_assets.audio_handles.get("explosion_handle").unwrap();
+    audio.play_with_settings(handle.clone(), PlaybackSettings::ONCE.with_volume(0.3).with_speed(1.8));
+}
+
+
+pub fn system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut debris_q: Query<(Entity, &mut FuseTime, &mut Fill), With<Debris>>,
+) {
+    for (entity, mut fuse_time, mut fill) in debris_q.iter_mut() {
+        fuse_time.timer.tick(time.delta());
+        if fuse_time.timer.finished() {
+            commands.entity(entity).despawn();
+        } else {
+            // fade out over the last third
+            fill.color.set_a((fuse_time.timer.percent_left() * 3.0).min(1.0));
+        }
+    }
+}
+
+
+// zigzag from a random edge of `rect` towards its middle
+fn crack_path(min: Vec2, max: Vec2) -> Path {
+    let mut rng = rand::thread_rng();
+    let size = max - min;
+
+    let start = match rng.gen_range(0..4) {
+        0 => Vec2::new(min.x, rng.gen_range(min.y..=max.y)),
+        1 => Vec2::new(max.x, rng.gen_range(min.y..=max.y)),
+        2 => Vec2::new(rng.gen_range(min.x..=max.x), min.y),
+        _ => Vec2::new(rng.gen_range(min.x..=max.x), max.y),
+    };
+    let target = (min + max) / 2.0 + size * Vec2::new(rng.gen_range(-0.25..0.25), rng.gen_range(-0.25..0.25));
+
+    let mut path_builder = PathBuilder::new();
+    path_builder.move_to(start);
+    for i in 1..=CRACK_STEPS {
+        let along = start + (target - start) * i as f32 / CRACK_STEPS as f32;
+        let jitter = size * 0.1 * Vec2::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0));
+        path_builder.line_to((along + jitter).clamp(min, max));
+    }
+    path_builder.build()
+}
+
+pub fn system_cracks(
+    mut commands: Commands,
+    wall_q: Query<(Entity, &Status, Option<&BBSize>, Option<&PolygonalShape>, Option<&Cracked>, Option<&Children>),
+                  Or<(With<Breakable>, With<BreakableSync>)>>,
+    crack_q: Query<Entity, With<Crack>>,
+) {
+    for (entity, status, bbsize, polygon, cracked, children) in wall_q.iter() {
+        if status.hp_max <= 0.0 { continue; }
+        let ratio = status.hp / status.hp_max;
+        let level = CRACK_THRESHOLDS.iter().filter(|t| ratio < **t).count();
+        let shown = cracked.map_or(0, |c| c.level);
+
+        // healed, or reloaded for a rematch
+        if level < shown {
+            for child in children.into_iter().flatten() {
+                if crack_q.contains(*child) {
+                    commands.entity(*child).despawn();
+                }
+            }
+            commands.entity(entity).remove::<Cracked>();
+            continue;
+        }
+        if level == shown { continue; }
+
+        // cracks live in the wall's local space, like its collider
+        let (min, max) = if let Some(polygon) = polygon {
+            let min = polygon.polygon.iter().fold(Vec2::splat(f32::MAX), |m, p| m.min(*p));
+            let max = polygon.polygon.iter().fold(Vec2::splat(f32::MIN), |m, p| m.max(*p));
+            (min, max)
+        } else if let Some(bbsize) = bbsize {
+            let half = Vec2::new(bbsize.x, bbsize.y) / 2.0;
+            (-half, half)
+        } else {
+            continue;
+        };
+        if min.x >= max.x || min.y >= max.y { continue; }
+
+        commands.entity(entity)
+            .insert(Cracked { level })
+            .with_children(|children| {
+                for _ in shown..level {
+                    children.spawn((
+                        Crack,
+                        ShapeBundle {
+                            path: crack_path(min, max),
+                            transform: Transform::from_xyz(0.0, 0.0, 0.1),
+                            ..default()
+                        },
+                        Stroke::new(Color::rgba(0.8, 0.8, 0.8, 0.8), 2.0),
+                    ));
+                }
+            });
+    }
+}
+
+
+const FILE_NAME: &str = "/debris.map";
+use crate::ev_save_load_world::LoadWorldEvent;
+pub fn load(
+    mut load_world_er: EventReader<LoadWorldEvent>,
+    mut config: ResMut<DebrisConfig>,
+    ) {
+
+    for e in load_world_er.iter() {
+        let dir = e.0.clone();
+
+        let json_str = std::fs::read_to_string(dir + FILE_NAME);
+        if let Ok(json_str) = json_str {
+            *config = serde_json::from_str(&json_str).unwrap();
+        } else {
+            *config = DebrisConfig::default();
+        }
+    }
+}
+
+
+use crate::ev_save_load_world::SaveWorldEvent;
+pub fn save(mut save_world_er: EventReader<SaveWorldEvent>,
+              config: Res<DebrisConfig>,
+              ) {
+
+    for e in save_world_er.iter() {
+        let dir = e.0.clone();
+
+        std::fs::write(dir + FILE_NAME, serde_json::to_string(&*config).unwrap()).unwrap();
+    }
+}
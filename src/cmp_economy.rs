@@ -0,0 +1,281 @@
+use serde::{Serialize, Deserialize};
+use bevy::prelude::*;
+use rand::prelude::*;
+use std::collections::HashMap;
+
+use crate::cmp_ai::Difficulty;
+use crate::cmp_combat_unit::UnitDied;
+use crate::cmp_combat_unit::UnitRegistry;
+use crate::cmp_game_asset::GameAsset;
+use crate::cmp_gate_generic::BallType;
+use crate::cmp_gate_generic::SpawnBall;
+use crate::cmp_team::Team;
+use crate::cmp_team::TeamConfig;
+
+// one row per keyboard player, indexed by roster slot
+const HOTKEYS: [[KeyCode; 4]; 2] = [
+    [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4],
+    [KeyCode::Key7, KeyCode::Key8, KeyCode::Key9, KeyCode::Key0],
+];
+
+#[derive(Reflect, FromReflect, Default, Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
+pub enum Controller {
+    #[default]
+    Keyboard,
+    // buys a random affordable roster unit, for playing alone
+    Script,
+    // reads the board, see cmp_ai
+    Ai,
+}
+
+#[derive(Reflect, FromReflect, Default, Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct Player {
+    pub team: Team,
+    // GateGeneric the team's units are sent into
+    pub gate: Option<u32>,
+    pub controller: Controller,
+    #[serde(default)]
+    pub difficulty: Difficulty,
+}
+
+#[derive(Resource, Reflect, Clone, Serialize, Deserialize, Debug)]
+pub struct EconomyConfig {
+    pub enabled: bool,
+    pub starting_funds: f32,
+    pub income_per_sec: f32,
+    // paid to every hostile team per dead unit, as a fraction of its cost
+    pub kill_reward: f32,
+    // units that can be bought, in hotkey order
+    pub roster: Vec<String>,
+    pub players: Vec<Player>,
+}
+
+impl Default for EconomyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            starting_funds: 50.0,
+            income_per_sec: 10.0,
+            kill_reward: 0.5,
+            roster: vec![String::from("type1"), String::from("type2"), String::from("type3"), String::from("type4")],
+            players: vec![],
+        }
+    }
+}
+
+impl EconomyConfig {
+    pub fn player(&self, team: Team) -> Option<&Player> {
+        self.players.iter().find(|p| p.team == team)
+    }
+
+    pub fn player_mut(&mut self, team: Team) -> &mut Player {
+        if self.player(team).is_none() {
+            self.players.push(Player { team, ..default() });
+        }
+        self.players.iter_mut().find(|p| p.team == team).unwrap()
+    }
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct Funds {
+    pub funds: HashMap<Team, f32>,
+}
+
+impl Funds {
+    pub fn get(&self, team: Team) -> f32 {
+        *self.funds.get(&team).unwrap_or(&0.0)
+    }
+
+    // spend `cost` if the team can afford it
+    pub fn spend(&mut self, team: Team, cost: f32) -> bool {
+        let funds = self.funds.entry(team).or_insert(0.0);
+        if *funds < cost { return false; }
+
+        *funds = *funds - cost;
+        true
+    }
+}
+
+#[derive(Component)]
+pub struct FundsText;
+
+
+pub fn buy(funds: &mut Funds,
+           config: &EconomyConfig,
+           registry: &UnitRegistry,
+           event: &mut EventWriter<SpawnBall>,
+           team: Team,
+           name: &str) -> bool {
+    let Some(gate) = config.player(team).and_then(|p| p.gate) else { return false; };
+    let Some(def) = registry.get(name) else { return false; };
+
+    if ! funds.spend(team, def.cost) { return false; }
+
+    event.send(SpawnBall(gate, BallType::Unit(name.to_string(), team)));
+    true
+}
+
+
+pub fn add(
+    mut command: Commands,
+    game_assets: Res<GameAsset>,
+    text_q: Query<Entity, With<FundsText>>,
+){
+    if ! text_q.is_empty() { return; }
+
+    let font = game_assets.font_handles.get("font1_handle").unwrap();
+    let message = "";
+
+    command.spawn(TextBundle::from_section(
+        message,
+        TextStyle {
+            font_size: 40.0,
+            color: Color::rgb(0.9, 0.9, 0.9),
+            font: font.clone(),
+            ..default()
+        })
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Percent(5.0),
+                left: Val::Percent(5.0),
+                ..default()
+            },
+            ..default()
+        }),
+    ).insert(FundsText);
+}
+
+
+pub fn system_setup(
+    config: Res<EconomyConfig>,
+    team_config: Res<TeamConfig>,
+    mut funds: ResMut<Funds>,
+) {
+    funds.funds.clear();
+    for team in team_config.teams() {
+        funds.funds.insert(team, config.starting_funds);
+    }
+}
+
+
+pub fn system_income(
+    time: Res<Time>,
+    config: Res<EconomyConfig>,
+    team_config: Res<TeamConfig>,
+    registry: Res<UnitRegistry>,
+    mut funds: ResMut<Funds>,
+    mut died_er: EventReader<UnitDied>,
+) {
+    if ! config.enabled { return; }
+
+    for team in team_config.teams() {
+        *funds.funds.entry(team).or_insert(0.0) += config.income_per_sec * time.delta_seconds();
+    }
+
+    for died in died_er.iter() {
+        let cost = registry.get(&died.name).map_or(0.0, |def| def.cost);
+        for team in team_config.teams() {
+            if team != died.team && team_config.hostile(team, died.team) {
+                *funds.funds.entry(team).or_insert(0.0) += cost * config.kill_reward;
+            }
+        }
+    }
+}
+
+
+pub fn system_keyboard(
+    keys: Res<Input<KeyCode>>,
+    config: Res<EconomyConfig>,
+    registry: Res<UnitRegistry>,
+    mut funds: ResMut<Funds>,
+    mut event: EventWriter<SpawnBall>,
+) {
+    if ! config.enabled { return; }
+
+    let keyboard_players = config.players.iter().filter(|p| p.controller == Controller::Keyboard);
+    for (player, hotkeys) in keyboard_players.zip(HOTKEYS.iter()) {
+        for (name, key) in config.roster.iter().zip(hotkeys.iter()) {
+            if keys.just_pressed(*key) {
+                buy(&mut funds, &config, &registry, &mut event, player.team, name);
+            }
+        }
+    }
+}
+
+
+// Saves up for a randomly picked roster unit and sends it as soon as it is affordable.
+pub fn system_script(
+    config: Res<EconomyConfig>,
+    registry: Res<UnitRegistry>,
+    mut funds: ResMut<Funds>,
+    mut event: EventWriter<SpawnBall>,
+    mut next_pick: Local<HashMap<Team, String>>,
+) {
+    if ! config.enabled || config.roster.len() == 0 { return; }
+
+    let mut rng = rand::thread_rng();
+    for player in config.players.iter().filter(|p| p.controller == Controller::Script) {
+        let name = next_pick.entry(player.team)
+            .or_insert_with(|| config.roster.choose(&mut rng).unwrap().clone())
+            .clone();
+
+        if buy(&mut funds, &config, &registry, &mut event, player.team, &name) {
+            next_pick.remove(&player.team);
+        }
+    }
+}
+
+
+pub fn system_text(
+    config: Res<EconomyConfig>,
+    team_config: Res<TeamConfig>,
+    funds: Res<Funds>,
+    mut text_q: Query<&mut Text, With<FundsText>>,
+) {
+    let Ok(mut text) = text_q.get_single_mut() else { return; };
+
+    let mut message = String::new();
+    if config.enabled {
+        for team in team_config.teams() {
+            message = message + &format!("Team {}: {}\n", team.0 + 1, funds.get(team).floor() as i32);
+        }
+    }
+
+    if text.sections[0].value != message {
+        text.sections[0].value = message;
+    }
+}
+
+
+const FILE_NAME: &str = "/economy.map";
+use crate::ev_save_load_world::LoadWorldEvent;
+pub fn load(
+    mut load_world_er: EventReader<LoadWorldEvent>,
+    mut config: ResMut<EconomyConfig>,
+    ) {
+
+    for e in load_world_er.iter() {
+        let dir = e.0.clone();
+
+        let json_str = std::fs::read_to_string(dir + FILE_NAME);
+        if let Ok(json_str) = json_str {
+            *config = serde_json::from_str(&json_str).unwrap();
+        } else {
+            *config = EconomyConfig::default();
+        }
+    }
+}
+
+
+use crate::ev_save_load_world::SaveWorldEvent;
+pub fn save(mut save_world_er: EventReader<SaveWorldEvent>,
+              config: Res<EconomyConfig>,
+              ) {
+
+    for e in save_world_er.iter() {
+        let dir = e.0.clone();
+
+        std::fs::write(dir + FILE_NAME, serde_json::to_string(&*config).unwrap()).unwrap();
+    }
+}
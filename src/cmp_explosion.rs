@@ -4,11 +4,20 @@ use bevy_rapier2d::prelude::*;
 use rand::prelude::*;
 
 use crate::cmp_ball::Ball;
+use crate::cmp_bbsize::BBSize;
+use crate::cmp_breakable::Breakable;
+use crate::cmp_breakable_sync::BreakableSync;
 use crate::cmp_game_asset::GameAsset;
 use crate::cmp_fuse_time::FuseTime;
+use crate::cmp_sprite_animation::SpriteAnimation;
+use crate::cmp_combat;
+use crate::cmp_combat::CombatConfig;
+use crate::cmp_combat::ContactTracker;
+use crate::cmp_combat::Effects;
 use crate::cmp_combat::Status;
-use crate::cmp_combat::Player1;
-use crate::cmp_combat::Player2;
+use crate::cmp_team::Team;
+use crate::cmp_team::TeamConfig;
+use crate::contacts;
 
 const LIFE_TIME: f32 = 1.0;
 const DEFAULT_RESTITUTION: f32 = 0.0;
@@ -17,14 +26,20 @@ const DEFAULT_FRICTION: f32 = 0.9;
 const RADIUS: f32 = 80.0;
 const HP: f32 = 0.0;
 const ATTACK: f32 = 50.0;
+// share of damage and impulse left at the very edge of a blast
+const EDGE_FALLOFF: f32 = 0.2;
 //const ANGVEL: f32 = -0.5;
 
 
 #[derive(Component)]
 pub struct Explosion{
-    radius: f32
+    pub radius: f32
 }
 
+// keeps its full size and fades out instead of growing, no knockback
+#[derive(Component)]
+pub struct Lingering;
+
 #[derive(Bundle)]
 pub struct ExplosionBundle {
     pub explosion: Explosion,
@@ -33,8 +48,9 @@ pub struct ExplosionBundle {
     pub sensor: Sensor,
     pub collision_groups: CollisionGroups,
     pub fuse_time: FuseTime,
+    pub animation: SpriteAnimation,
     #[bundle]
-    sprite_bundle: SpriteBundle,
+    sprite_sheet_bundle: SpriteSheetBundle,
 }
 
 impl Default for ExplosionBundle {
@@ -45,12 +61,15 @@ impl Default for ExplosionBundle {
                 hp: HP,
                 hp_max: HP,
                 attack: ATTACK,
+                ..default()
             },
             collider: Collider::ball(1.0),
             sensor: Sensor,
             collision_groups: CollisionGroups::new(Group::GROUP_1, Group::ALL),
             fuse_time: FuseTime{timer: Timer::from_seconds(LIFE_TIME, TimerMode::Once)},
-            sprite_bundle: SpriteBundle {
+            // nine frames over LIFE_TIME
+            animation: SpriteAnimation::new("explosion"),
+            sprite_sheet_bundle: SpriteSheetBundle {
                 ..default()
             },
         }
@@ -63,14 +82,14 @@ impl From<(Vec3, f32, &GameAsset)> for ExplosionBundle {
 
         let mut bundle = ExplosionBundle::default();
 
-        let handle = game_assets.image_handles.get("explosion_handle").unwrap();
+        let handle = game_assets.atlas_handles.get("explosion").unwrap();
         bundle.explosion.radius = radius;
-        bundle.sprite_bundle = SpriteBundle {
-            sprite: Sprite {
+        bundle.sprite_sheet_bundle = SpriteSheetBundle {
+            sprite: TextureAtlasSprite {
                 custom_size: Some(Vec2::ONE * (1.0 * 2.0)),
                 ..default()
             },
-            texture: handle.clone(),
+            texture_atlas: handle.clone(),
             transform: Transform {
                 translation,
                 ..default()
@@ -83,15 +102,47 @@ impl From<(Vec3, f32, &GameAsset)> for ExplosionBundle {
     }
 }
 
+impl ExplosionBundle {
+    // spawn together with Lingering
+    pub fn lingering(translation: Vec3, radius: f32, duration: f32, color: Color, game_assets: &GameAsset) -> Self {
+        let mut bundle = ExplosionBundle::from((translation, radius, game_assets));
+        bundle.fuse_time = FuseTime{timer: Timer::from_seconds(duration, TimerMode::Once)};
+        bundle.animation = SpriteAnimation::new("explosion_loop");
+        bundle.sprite_sheet_bundle.sprite.color = color;
+        bundle.sprite_sheet_bundle.transform.scale = Vec3::ONE * radius;
+
+        bundle
+    }
+}
+
+// 1.0 at the center down to EDGE_FALLOFF at `radius`, 0.0 beyond
+pub fn falloff(distance: f32, radius: f32) -> f32 {
+    if distance > radius { return 0.0; }
+    1.0 - (1.0 - EDGE_FALLOFF) * distance / radius.max(1.0)
+}
+
+// distance from `point` to a box of `bbsize` centered on `transform`
+fn box_distance(point: Vec2, transform: &Transform, bbsize: Option<&BBSize>) -> f32 {
+    let local = transform.rotation.inverse() * (Vec3::from((point, 0.0)) - transform.translation);
+    let Some(bbsize) = bbsize else { return local.truncate().length(); };
+
+    let half = Vec2::new(bbsize.x, bbsize.y) * transform.scale.truncate() / 2.0;
+    let nearest = local.truncate().clamp(-half, half);
+    local.truncate().distance(nearest)
+}
+
 pub fn system(
     mut commands: Commands,
     time: Res<Time>,
-    mut explosion_q: Query<(Entity, &mut Transform, &mut FuseTime, &Explosion)>,
+    mut explosion_q: Query<(Entity, &mut Transform, &mut FuseTime, &Explosion, &mut TextureAtlasSprite, Option<&Lingering>)>,
 ) {
-    for (entity, mut transform, mut fuse_time, explosion) in explosion_q.iter_mut() {
+    for (entity, mut transform, mut fuse_time, explosion, mut sprite, lingering) in explosion_q.iter_mut() {
         fuse_time.timer.tick(time.delta());
         if fuse_time.timer.finished() { 
             commands.entity(entity).despawn();
+        } else if lingering.is_some() {
+            let alpha = sprite.color.a();
+            sprite.color.set_a(alpha.min(fuse_time.timer.percent_left() * 2.0));
         } else {
             transform.scale = Vec3::ONE * explosion.radius * fuse_time.timer.percent();
         }
@@ -99,33 +150,76 @@ pub fn system(
 }
 
 
-pub fn system_damage1(
+pub fn system_damage(
     mut commands: Commands,
+    time: Res<Time>,
+    config: Res<CombatConfig>,
+    team_config: Res<TeamConfig>,
     rapier_context: Res<RapierContext>,
-    mut explosion_q: Query<(Entity, &Status, &Transform), (With<Explosion>, With<Player1>, Without<Player2>)>,
-    mut ball_q: Query<(Entity, &mut Status, &Transform), (With<Ball>, With<Player2>, Without<Player1>)>,
+    mut tracker: Local<ContactTracker>,
+    explosion_q: Query<(Entity, &Explosion, &Team, &Status, &Transform, Option<&Lingering>)>,
+    mut ball_q: Query<(Entity, &Team, &mut Status, &Transform, Option<&mut Effects>), (With<Ball>, Without<Explosion>)>,
 ) {
-    for (sensor_e, sensor_s, sensor_t) in explosion_q.iter_mut() {
-        for (ball_e, mut ball_s, ball_t) in ball_q.iter_mut() {
-            if rapier_context.intersection_pair(sensor_e.clone(), ball_e.clone()).is_some() {
-                ball_s.hp = ball_s.hp - sensor_s.attack;
+    let now = time.elapsed_seconds();
+    tracker.begin_frame(now, config.contact_mode);
+
+    for (sensor_e, explosion, sensor_team, sensor_s, sensor_t, lingering) in explosion_q.iter() {
+        for other in contacts::intersecting(&rapier_context, sensor_e) {
+            let Ok((ball_e, ball_team, mut ball_s, ball_t, ball_fx)) = ball_q.get_mut(other) else { continue; };
+            if ! team_config.harms(*sensor_team, *ball_team) { continue; }
+
+            if tracker.hit((sensor_e, ball_e), now, config.contact_mode) {
+                // a zone hurts the same everywhere inside it
+                let scale = if lingering.is_some() {
+                    1.0
+                } else {
+                    let distance = ball_t.translation.truncate().distance(sensor_t.translation.truncate());
+                    falloff(distance, explosion.radius)
+                };
+
+                ball_s.damage(sensor_s.attack * scale);
+                cmp_combat::inflict(&mut commands, ball_e, ball_fx, &sensor_s.on_hit);
             }
         }
     }
 }
 
 
-pub fn system_damage2(
+// The moment a blast goes off, push every dynamic body in range away from
+// it and damage hostile walls. Walls break in their own system_break.
+pub fn system_blast(
     mut commands: Commands,
-    rapier_context: Res<RapierContext>,
-    mut explosion_q: Query<(Entity, &Status, &Transform), (With<Explosion>, With<Player2>, Without<Player1>)>,
-    mut ball_q: Query<(Entity, &mut Status, &Transform), (With<Ball>, With<Player1>, Without<Player2>)>,
+    config: Res<CombatConfig>,
+    team_config: Res<TeamConfig>,
+    explosion_q: Query<(&Explosion, &Team, &Status, &Transform), (Added<Explosion>, Without<Lingering>)>,
+    mut body_q: Query<(Entity, &RigidBody, &Transform, Option<&mut ExternalImpulse>), Without<Explosion>>,
+    mut wall_q: Query<(&Team, &mut Status, &Transform, Option<&BBSize>), (Or<(With<Breakable>, With<BreakableSync>)>, Without<Explosion>)>,
 ) {
-    for (sensor_e, sensor_s, sensor_t) in explosion_q.iter_mut() {
-        for (ball_e, mut ball_s, ball_t) in ball_q.iter_mut() {
-            if rapier_context.intersection_pair(sensor_e.clone(), ball_e.clone()).is_some() {
-                ball_s.hp = ball_s.hp - sensor_s.attack;
+    for (explosion, team, status, transform) in explosion_q.iter() {
+        let center = transform.translation.truncate();
+
+        for (entity, rigid_body, body_t, impulse) in body_q.iter_mut() {
+            if *rigid_body != RigidBody::Dynamic { continue; }
+
+            let offset = body_t.translation.truncate() - center;
+            let scale = falloff(offset.length(), explosion.radius);
+            if scale == 0.0 { continue; }
+
+            let push = offset.normalize_or_zero() * config.explosion_knockback * scale;
+            if let Some(mut impulse) = impulse {
+                impulse.impulse = impulse.impulse + push;
+            } else {
+                commands.entity(entity).insert(ExternalImpulse { impulse: push, ..default() });
             }
         }
+
+        for (wall_team, mut wall_s, wall_t, bbsize) in wall_q.iter_mut() {
+            if ! team_config.harms(*team, *wall_team) { continue; }
+
+            let scale = falloff(box_distance(center, wall_t, bbsize), explosion.radius);
+            if scale == 0.0 { continue; }
+
+            wall_s.damage(status.attack * scale * config.explosion_structure_damage);
+        }
     }
 }
@@ -0,0 +1,283 @@
+use serde::{Serialize, Deserialize};
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use bevy_inspector_egui::bevy_egui::{egui, EguiContexts};
+
+use crate::cmp_ball::Ball;
+use crate::cmp_bbsize::BBSize;
+use crate::contacts;
+use crate::edit_context::*;
+use crate::ev_save_load_world;
+
+pub const DEFAULT_SIZE: f32 = 256.0;
+
+#[derive(Reflect, FromReflect, Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
+pub enum ForceKind {
+    // pulls towards the center, pushes away when negative; strongest at the center
+    Radial { strength: f32 },
+    // blows along the zone's rotation, turbulence 0.0 ..= 1.0 makes it gusty
+    Wind { strength: f32, turbulence: f32 },
+    // buoyancy as a multiple of gravity, drag as fraction of velocity lost per second
+    Water { buoyancy: f32, drag: f32 },
+    // replaces the friction and restitution of balls inside, e.g. ice
+    Surface { friction: f32, restitution: f32 },
+}
+
+impl ForceKind {
+    pub const LIST: [ForceKind; 4] = [
+        ForceKind::Radial { strength: 600.0 },
+        ForceKind::Wind { strength: 300.0, turbulence: 0.3 },
+        ForceKind::Water { buoyancy: 1.3, drag: 0.7 },
+        ForceKind::Surface { friction: 0.0, restitution: 0.0 },
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ForceKind::Radial { .. } => "Radial",
+            ForceKind::Wind { .. } => "Wind",
+            ForceKind::Water { .. } => "Water",
+            ForceKind::Surface { .. } => "Surface",
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            ForceKind::Radial { strength } if *strength < 0.0 => Color::rgba(1.0, 0.6, 0.2, 0.25),
+            ForceKind::Radial { .. } => Color::rgba(0.6, 0.3, 1.0, 0.25),
+            ForceKind::Wind { .. } => Color::rgba(0.9, 0.9, 0.9, 0.2),
+            ForceKind::Water { .. } => Color::rgba(0.2, 0.5, 1.0, 0.35),
+            ForceKind::Surface { .. } => Color::rgba(0.7, 0.95, 1.0, 0.35),
+        }
+    }
+}
+
+#[derive(Component, Reflect, Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct ForceZone {
+    pub size: Vec2,
+    pub position: Vec2,
+    pub angle: f32,
+    pub kind: ForceKind,
+}
+
+// a ball's own friction and restitution while a Surface zone overrides them
+#[derive(Component)]
+pub struct SurfaceOverride {
+    friction: Friction,
+    restitution: Restitution,
+}
+
+
+// radial zones are circles of diameter size.x, everything else a box
+fn collider_and_path(fz: &ForceZone) -> (Collider, Path) {
+    if let ForceKind::Radial { .. } = fz.kind {
+        let circle = shapes::Circle { radius: fz.size.x / 2.0, center: Vec2::ZERO };
+        (Collider::ball(fz.size.x / 2.0), GeometryBuilder::build_as(&circle))
+    } else {
+        let rectangle = shapes::Rectangle { extents: fz.size, origin: RectangleOrigin::Center };
+        (Collider::cuboid(fz.size.x / 2.0, fz.size.y / 2.0), GeometryBuilder::build_as(&rectangle))
+    }
+}
+
+// `index` reuses the entity saved in a map, so a rematch reload replaces it
+pub fn add(commands: &mut Commands, index: Option<u32>, fz: ForceZone) -> Entity {
+    let (collider, path) = collider_and_path(&fz);
+    let color = fz.kind.color();
+
+    let mut entity = ev_save_load_world::get_or_spawn(commands, index);
+    entity
+        .insert((
+            ShapeBundle {
+                path,
+                transform: Transform {
+                    translation: Vec3::new(fz.position.x, fz.position.y, -1.0),
+                    rotation: Quat::from_rotation_z(fz.angle),
+                    ..default()
+                },
+                ..default()
+            },
+            Fill::color(color),
+            Stroke::new(color.with_a(0.8), 2.0),
+        ))
+        .insert(collider)
+        .insert(Sensor)
+        .insert(BBSize{x: fz.size.x, y: fz.size.y})
+        .insert(MapObject::ForceZone(fz.kind))
+        .insert(fz)
+        .id()
+}
+
+
+pub fn system(
+    mut commands: Commands,
+    time: Res<Time>,
+    rapier_context: Res<RapierContext>,
+    rapier_configuration: Res<RapierConfiguration>,
+    zone_q: Query<(&Transform, &ForceZone)>,
+    mut ball_q: Query<(Entity, &Transform, &mut Velocity, &mut Friction, &mut Restitution, Option<&SurfaceOverride>), With<Ball>>,
+) {
+    let dt = time.delta_seconds();
+    let now = time.elapsed_seconds();
+
+    for (ball_e, ball_t, mut velocity, mut friction, mut restitution, surface) in ball_q.iter_mut() {
+        let pos = ball_t.translation.truncate();
+        let mut on_surface = None;
+
+        for other in contacts::intersecting(&rapier_context, ball_e) {
+            let Ok((zone_t, zone)) = zone_q.get(other) else { continue; };
+
+            let center = zone_t.translation.truncate();
+            match zone.kind {
+                ForceKind::Radial { strength } => {
+                    let radius = zone.size.x / 2.0 * zone_t.scale.x;
+                    let offset = center - pos;
+                    let falloff = (1.0 - offset.length() / radius.max(1.0)).max(0.0);
+                    velocity.linvel += offset.normalize_or_zero() * strength * falloff * dt;
+                }
+                ForceKind::Wind { strength, turbulence } => {
+                    let direction = (zone_t.rotation * Vec3::Y).truncate();
+                    // cheap gusts that vary over time and across the zone
+                    let gust = (now * 2.0 + pos.x * 0.01).sin();
+                    let sway = (now * 3.0 + pos.y * 0.01).cos();
+                    let wind = direction * (1.0 + turbulence * gust) + direction.perp() * turbulence * sway;
+                    velocity.linvel += wind * strength * dt;
+                }
+                ForceKind::Water { buoyancy, drag } => {
+                    velocity.linvel += -rapier_configuration.gravity * buoyancy * dt;
+                    velocity.linvel *= (1.0 - drag.clamp(0.0, 1.0)).powf(dt);
+                    velocity.angvel *= (1.0 - drag.clamp(0.0, 1.0)).powf(dt);
+                }
+                ForceKind::Surface { friction, restitution } => {
+                    on_surface = Some((friction, restitution));
+                }
+            }
+        }
+
+        match (on_surface, surface) {
+            (Some((f, r)), None) => {
+                commands.entity(ball_e).insert(SurfaceOverride { friction: *friction, restitution: *restitution });
+                *friction = Friction::coefficient(f);
+                *restitution = Restitution::coefficient(r);
+            }
+            (Some((f, r)), Some(_)) => {
+                friction.coefficient = f;
+                restitution.coefficient = r;
+            }
+            (None, Some(original)) => {
+                *friction = original.friction;
+                *restitution = original.restitution;
+                commands.entity(ball_e).remove::<SurfaceOverride>();
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+
+pub fn edit_window(
+    mut commands: Commands,
+    mut egui_contexts: EguiContexts,
+    edit_context: Res<EditContext>,
+    mut zone_q: Query<&mut ForceZone>,
+    ) {
+    if let EditContext::Edit(MapObject::ForceZone(_), pick, _) = edit_context.clone() {
+        if pick.len() == 0 { return; }
+
+        if let Ok(mut zone) = zone_q.get_mut(pick[0]) {
+            let mut z = zone.clone();
+
+            egui::Window::new("ForceZone").show(egui_contexts.ctx_mut(), |ui: &mut egui::Ui| {
+                ui.horizontal(|ui: &mut egui::Ui| {
+                    for kind in ForceKind::LIST {
+                        if ui.selectable_label(kind.name() == z.kind.name(), kind.name()).clicked() && kind.name() != z.kind.name() {
+                            z.kind = kind;
+                        }
+                    }
+                });
+                ui.horizontal(|ui: &mut egui::Ui| {
+                    match &mut z.kind {
+                        ForceKind::Radial { strength } => {
+                            ui.label("strength");
+                            ui.add(egui::DragValue::new(strength).clamp_range(-5000.0..=5000.0));
+                        }
+                        ForceKind::Wind { strength, turbulence } => {
+                            ui.label("strength");
+                            ui.add(egui::DragValue::new(strength).clamp_range(0.0..=5000.0));
+                            ui.label("turbulence");
+                            ui.add(egui::DragValue::new(turbulence).speed(0.01).clamp_range(0.0..=1.0));
+                        }
+                        ForceKind::Water { buoyancy, drag } => {
+                            ui.label("buoyancy");
+                            ui.add(egui::DragValue::new(buoyancy).speed(0.01).clamp_range(0.0..=5.0));
+                            ui.label("drag");
+                            ui.add(egui::DragValue::new(drag).speed(0.01).clamp_range(0.0..=1.0));
+                        }
+                        ForceKind::Surface { friction, restitution } => {
+                            ui.label("friction");
+                            ui.add(egui::DragValue::new(friction).speed(0.01).clamp_range(0.0..=2.0));
+                            ui.label("restitution");
+                            ui.add(egui::DragValue::new(restitution).speed(0.01).clamp_range(0.0..=2.0));
+                        }
+                    }
+                });
+            });
+
+            if z != *zone {
+                // the shape and color follow the kind
+                let (collider, path) = collider_and_path(&z);
+                let color = z.kind.color();
+                commands.entity(pick[0])
+                    .insert(collider)
+                    .insert(path)
+                    .insert(Fill::color(color))
+                    .insert(Stroke::new(color.with_a(0.8), 2.0))
+                    .insert(MapObject::ForceZone(z.kind));
+                *zone = z;
+            }
+        }
+    }
+}
+
+
+const FILE_NAME: &str = "/force_zone.map";
+use crate::ev_save_load_world::LoadWorldEvent;
+pub fn load(
+    mut load_world_er: EventReader<LoadWorldEvent>,
+    mut commands: Commands,
+    ) {
+
+    for e in load_world_er.iter() {
+        let dir = e.0.clone();
+
+        let json_str = std::fs::read_to_string(dir + FILE_NAME);
+        if let Ok(json_str) = json_str {
+            let elem_list: Vec<(u32, ForceZone)> = serde_json::from_str(&json_str).unwrap();
+
+            for (i, e) in elem_list {
+                add(&mut commands, Some(i), e);
+            }
+        }
+    }
+}
+
+
+use crate::ev_save_load_world::SaveWorldEvent;
+pub fn save(mut save_world_er: EventReader<SaveWorldEvent>,
+              q: Query<(Entity, &Transform, &ForceZone)>,
+              ) {
+
+    for e in save_world_er.iter() {
+        let dir = e.0.clone();
+        let mut elem_list: Vec<(u32, ForceZone)> = vec![];
+
+        for (entity, t, e) in q.iter() {
+            let mut e = e.clone();
+            e.size = e.size * t.scale.truncate();
+            e.position = t.translation.truncate();
+            e.angle = t.rotation.to_euler(EulerRot::XYZ).2;
+            elem_list.push((entity.index(), e));
+        }
+
+        std::fs::write(dir + FILE_NAME, serde_json::to_string(&elem_list).unwrap()).unwrap();
+    }
+}
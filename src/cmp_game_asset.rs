@@ -1,11 +1,39 @@
 use bevy::prelude::*;
 use bevy::audio::AudioSource;
+use serde::de::DeserializeOwned;
 use std::collections::HashMap;
 
 #[derive(Component, Resource, Default, Debug)]
 pub struct GameAsset {
     pub image_handles: HashMap<String, Handle<Image>>,
+    // built from image_handles by cmp_sprite_animation::setup
+    pub atlas_handles: HashMap<String, Handle<TextureAtlas>>,
     pub audio_handles: HashMap<String, Handle<AudioSource>>,
     pub font_handles: HashMap<String, Handle<Font>>,
 }
 
+
+
+// Entries of a json list, read from `path` relative to the working
+// directory or from the `bundled` copy when the file is missing or not a
+// list. Entries that don't parse are logged and skipped.
+pub fn read_list<T: DeserializeOwned>(path: &str, bundled: &str) -> Vec<T> {
+    let json_str = std::fs::read_to_string(path).unwrap_or_else(|_| bundled.to_string());
+    let values: Vec<serde_json::Value> = match serde_json::from_str(&json_str) {
+        Ok(values) => values,
+        Err(e) => {
+            warn!("{}: {}, using the bundled copy", path, e);
+            serde_json::from_str(bundled).unwrap()
+        }
+    };
+
+    values.into_iter()
+        .filter_map(|value| match serde_json::from_value(value) {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                warn!("{}: skipping an entry, {}", path, e);
+                None
+            }
+        })
+        .collect()
+}
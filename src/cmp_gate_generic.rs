@@ -9,11 +9,13 @@ use crate::cmp_bbsize::BBSize;
 use crate::cmp_game_asset::GameAsset;
 use crate::cmp_ball_zundamon;
 use crate::cmp_ball_zombie;
+use crate::cmp_ball_species;
+use crate::cmp_ball_species::BallSpeciesRegistry;
+use crate::cmp_combat_unit;
+use crate::cmp_combat_unit::UnitRegistry;
+use crate::cmp_team::Team;
+use crate::cmp_team::TeamConfig;
 
-use crate::cmp_ball_type1;
-use crate::cmp_ball_type2;
-use crate::cmp_ball_type3;
-use crate::cmp_ball_type4;
 
 use crate::cmp_rotator::Rotator;
 use crate::edit_context::*;
@@ -23,10 +25,11 @@ pub const DEFAULT_SIZE_X: f32 = 10.0;
 pub const DEFAULT_SIZE_Y: f32 = 10.0;
 
 
-#[derive(Component, Reflect, FromReflect, Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Component, Reflect, FromReflect, Clone, PartialEq, Serialize, Deserialize, Debug)]
 pub enum BallType {
     Zundamon,
     Zombie,
+    // fixed combat units, kept so older maps still load
     Type1P1,
     Type1P2,
     Type2P1,
@@ -35,6 +38,27 @@ pub enum BallType {
     Type3P2,
     Type4P1,
     Type4P2,
+    // any species registered in assets/ball_species.json
+    Species(String),
+    // any unit defined in assets/combat_units.json
+    Unit(String, Team),
+}
+
+impl BallType {
+    pub fn unit(&self) -> Option<(String, Team)> {
+        match self {
+            BallType::Type1P1 => Some((String::from("type1"), Team(0))),
+            BallType::Type1P2 => Some((String::from("type1"), Team(1))),
+            BallType::Type2P1 => Some((String::from("type2"), Team(0))),
+            BallType::Type2P2 => Some((String::from("type2"), Team(1))),
+            BallType::Type3P1 => Some((String::from("type3"), Team(0))),
+            BallType::Type3P2 => Some((String::from("type3"), Team(1))),
+            BallType::Type4P1 => Some((String::from("type4"), Team(0))),
+            BallType::Type4P2 => Some((String::from("type4"), Team(1))),
+            BallType::Unit(name, team) => Some((name.clone(), *team)),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Reflect, FromReflect, Clone, PartialEq, Serialize, Deserialize,Debug)]
@@ -147,6 +171,9 @@ pub fn system_setup(
 pub fn system(
     mut commands: Commands,
     game_assets: Res<GameAsset>,
+    registry: Res<BallSpeciesRegistry>,
+    unit_registry: Res<UnitRegistry>,
+    team_config: Res<TeamConfig>,
     mut event: EventReader<SpawnBall>,
     mut query: Query<(Entity, &Transform, &BBSize, &mut GateGeneric)>,
 ) {
@@ -154,10 +181,12 @@ pub fn system(
 
     for e in event.iter() {
         let entity = Entity::from_raw(e.0);
-        let balltype = e.1;
+        let balltype = e.1.clone();
 
-        let (_, _, _, mut gate_generic) = query.get_mut(entity).unwrap();
-        gate_generic.remain.push(balltype);
+        // signals can also be addressed to other elements, e.g. conveyors
+        if let Ok((_, _, _, mut gate_generic)) = query.get_mut(entity) {
+            gate_generic.remain.push(balltype);
+        }
     }
 
     let mut rng = rand::thread_rng();
@@ -174,63 +203,47 @@ pub fn system(
                 let rad = gate_generic.ball_radius;
                 let balltype = gate_generic.remain.pop().unwrap();
 
-                match(balltype) {
-                    BallType::Zundamon => {
-                        let _ = commands.spawn(
-                            cmp_ball_zundamon::BallZundamonBundle::from((Vec2::new(x, y), rad, Vec2::ZERO, game_assets)));
-                    },
-
-                    BallType::Zombie => {
-                        let _ = commands.spawn(
-                            cmp_ball_zombie::BallZombieBundle::from((Vec2::new(x, y), rad, Vec2::ZERO, game_assets)));
-                    }
-
-                    BallType::Type1P1 => {
-                        let _ = commands.spawn(
-                            cmp_ball_type1::BallP1Bundle::from((Vec2::new(x, y), Vec2::ZERO, game_assets)));
-                    },
-
-                    BallType::Type1P2 => {
-                        let _ = commands.spawn(
-                            cmp_ball_type1::BallP2Bundle::from((Vec2::new(x, y), Vec2::ZERO, game_assets)));
-                    }
-
-                    BallType::Type2P1 => {
-                        let _ = commands.spawn(
-                            cmp_ball_type2::BallType2P1Bundle::from((Vec2::new(x, y), Vec2::ZERO, game_assets)));
-                    },
-
-                    BallType::Type2P2 => {
-                        let _ = commands.spawn(
-                            cmp_ball_type2::BallType2P2Bundle::from((Vec2::new(x, y), Vec2::ZERO, game_assets)));
-                    }
-
-                    BallType::Type3P1 => {
-                        let _ = commands.spawn(
-                            cmp_ball_type3::BallType3P1Bundle::from((Vec2::new(x, y), Vec2::ZERO, game_assets)));
-                    },
-
-                    BallType::Type3P2 => {
-                        let _ = commands.spawn(
-                            cmp_ball_type3::BallType3P2Bundle::from((Vec2::new(x, y), Vec2::ZERO, game_assets)));
-                    }
-
-                    BallType::Type4P1 => {
-                        let _ = commands.spawn(
-                            cmp_ball_type4::BallType4P1Bundle::from((Vec2::new(x, y), Vec2::ZERO, game_assets)));
-                    },
-
-                    BallType::Type4P2 => {
-                        let _ = commands.spawn(
-                            cmp_ball_type4::BallType4P2Bundle::from((Vec2::new(x, y), Vec2::ZERO, game_assets)));
-                    }
-                }
+                let _ = spawn(&mut commands, game_assets, &registry, &unit_registry, &team_config,
+                              &balltype, Vec2::new(x, y), rad, Vec2::ZERO);
             }
         }
     }
 }
 
 
+// Spawn any kind of ball. Units bring their own radius.
+pub fn spawn(commands: &mut Commands,
+             game_assets: &GameAsset,
+             registry: &BallSpeciesRegistry,
+             unit_registry: &UnitRegistry,
+             team_config: &TeamConfig,
+             balltype: &BallType,
+             position: Vec2,
+             radius: f32,
+             velocity: Vec2) -> Option<Entity> {
+    match balltype {
+        BallType::Zundamon => {
+            Some(commands.spawn(
+                cmp_ball_zundamon::BallZundamonBundle::from((position, radius, velocity, game_assets))).id())
+        },
+
+        BallType::Zombie => {
+            Some(commands.spawn(
+                cmp_ball_zombie::BallZombieBundle::from((position, radius, velocity, game_assets))).id())
+        }
+
+        BallType::Species(name) => {
+            cmp_ball_species::spawn(commands, registry, game_assets, name, position, velocity)
+        }
+
+        _ => {
+            let (name, team) = balltype.unit()?;
+            cmp_combat_unit::spawn(commands, unit_registry, team_config, game_assets, &name, team, position, velocity)
+        }
+    }
+}
+
+
 const FILE_NAME: &str = "/gate_generic.map";
 use crate::ev_save_load_world::LoadWorldEvent;
 pub fn load(
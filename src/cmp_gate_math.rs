@@ -0,0 +1,359 @@
+use serde::{Serialize, Deserialize};
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use bevy_inspector_egui::bevy_egui::{egui, EguiContexts};
+use rand::prelude::*;
+use std::collections::HashMap;
+
+use crate::cmp_ball::Ball;
+use crate::cmp_ball_zundamon::Zundamon;
+use crate::cmp_ball_zombie::Zombie;
+use crate::cmp_ball_species::Species;
+use crate::cmp_ball_species::BallSpeciesRegistry;
+use crate::cmp_bbsize::BBSize;
+use crate::cmp_combat_unit::Unit;
+use crate::cmp_combat_unit::UnitRegistry;
+use crate::cmp_game_asset::GameAsset;
+use crate::cmp_gate_generic;
+use crate::cmp_gate_generic::BallType;
+use crate::cmp_team::Team;
+use crate::cmp_team::TeamConfig;
+use crate::child_label;
+use crate::contacts;
+use crate::edit_context::*;
+use crate::ev_save_load_world;
+
+pub const DEFAULT_SIZE_X: f32 = 120.0;
+pub const DEFAULT_SIZE_Y: f32 = 16.0;
+// more copies than this per frame are dropped, so ×100 chains stay playable
+const MAX_SPAWN_PER_FRAME: usize = 200;
+
+
+#[derive(Reflect, FromReflect, Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
+pub enum Operator {
+    // every ball becomes n
+    Multiply(u32),
+    // the first ball brings n more
+    Add(u32),
+    // the first n balls are removed
+    Subtract(u32),
+    // one of every n balls is kept
+    Divide(u32),
+}
+
+impl Operator {
+    pub const LIST: [Operator; 4] = [
+        Operator::Multiply(2),
+        Operator::Add(10),
+        Operator::Subtract(10),
+        Operator::Divide(2),
+    ];
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Operator::Multiply(_) => "×",
+            Operator::Add(_) => "+",
+            Operator::Subtract(_) => "-",
+            Operator::Divide(_) => "÷",
+        }
+    }
+
+    fn n(&self) -> u32 {
+        match self {
+            Operator::Multiply(n) | Operator::Add(n) | Operator::Subtract(n) | Operator::Divide(n) => *n,
+        }
+    }
+
+    fn n_mut(&mut self) -> &mut u32 {
+        match self {
+            Operator::Multiply(n) | Operator::Add(n) | Operator::Subtract(n) | Operator::Divide(n) => n,
+        }
+    }
+
+    fn label(&self) -> String {
+        format!("{}{}", self.symbol(), self.n())
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            Operator::Multiply(_) | Operator::Add(_) => Color::rgba(0.2, 0.6, 1.0, 0.5),
+            Operator::Subtract(_) | Operator::Divide(_) => Color::rgba(1.0, 0.3, 0.3, 0.5),
+        }
+    }
+}
+
+#[derive(Component, Reflect, Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct GateMath {
+    pub operator: Operator,
+    // balls the gate acts on before it goes inactive, None for no limit
+    pub capacity: Option<u32>,
+    // the other gate of a left/right pair, a ball passing one has passed both
+    #[serde(default)]
+    pub sibling: Option<u32>,
+    #[serde(skip)]
+    pub used: u32,
+}
+
+// gates a ball, or the ball it was copied from, already went through
+#[derive(Component, Clone, Default)]
+pub struct GateMathPassed(Vec<Entity>);
+
+#[derive(Component)]
+pub struct GateMathLabel;
+
+
+// `index` reuses the entity saved in a map, so a rematch reload replaces it
+pub fn add(commands: &mut Commands, index: Option<u32>, translation: Vec3, rotation: Quat, scale: Vec3, gate: GateMath) -> Entity {
+    let mut entity = ev_save_load_world::get_or_spawn(commands, index);
+    entity
+        .insert(SpriteBundle {
+            sprite: Sprite {
+                color: gate.operator.color(),
+                custom_size: Some(Vec2::new(DEFAULT_SIZE_X, DEFAULT_SIZE_Y)),
+                ..default()
+            },
+            transform: Transform { translation, rotation, scale },
+            ..default()
+        })
+        .insert(Collider::cuboid(DEFAULT_SIZE_X / 2.0, DEFAULT_SIZE_Y / 2.0))
+        .insert(Sensor)
+        .insert(BBSize{x: DEFAULT_SIZE_X, y: DEFAULT_SIZE_Y})
+        .insert(MapObject::GateMath(false))
+        .insert(GateMath { used: 0, ..gate })
+        .id()
+}
+
+
+fn ball_type(zundamon: bool,
+             zombie: bool,
+             species: Option<&Species>,
+             unit: Option<(&Unit, &Team)>) -> Option<BallType> {
+    if let Some(species) = species {
+        Some(BallType::Species(species.0.clone()))
+    } else if let Some((unit, team)) = unit {
+        Some(BallType::Unit(unit.name.clone(), *team))
+    } else if zombie {
+        Some(BallType::Zombie)
+    } else if zundamon {
+        Some(BallType::Zundamon)
+    } else {
+        None
+    }
+}
+
+pub fn system(
+    mut commands: Commands,
+    game_assets: Res<GameAsset>,
+    registry: Res<BallSpeciesRegistry>,
+    unit_registry: Res<UnitRegistry>,
+    team_config: Res<TeamConfig>,
+    rapier_context: Res<RapierContext>,
+    mut gate_q: Query<(Entity, &mut Sprite, &mut GateMath)>,
+    ball_q: Query<(&Ball, &Transform, &Velocity, Option<&GateMathPassed>,
+                   Option<&Zundamon>, Option<&Zombie>, Option<&Species>, Option<(&Unit, &Team)>)>,
+) {
+    let mut rng = rand::thread_rng();
+    let mut spawned = 0;
+    // GateMathPassed inserted this frame, the query still has the old one
+    let mut passed_now: HashMap<Entity, GateMathPassed> = HashMap::new();
+
+    for (gate_e, mut gate_sprite, mut gate) in gate_q.iter_mut() {
+        for ball_e in contacts::intersecting(&rapier_context, gate_e) {
+            let Ok((ball, ball_t, ball_v, passed, zundamon, zombie, species, unit)) = ball_q.get(ball_e) else { continue; };
+            if gate.capacity.map_or(false, |c| gate.used >= c) { break; }
+            let mut passed = passed_now.get(&ball_e).cloned().or(passed.cloned()).unwrap_or_default();
+            // by index, the sibling is only known by the index saved in the map
+            if passed.0.iter().any(|e| e.index() == gate_e.index()) { continue; }
+
+            passed.0.push(gate_e);
+            if let Some(sibling) = gate.sibling {
+                passed.0.push(Entity::from_raw(sibling));
+            }
+            commands.entity(ball_e).insert(passed.clone());
+            passed_now.insert(ball_e, passed.clone());
+
+            let index = gate.used;
+            gate.used += 1;
+
+            let copies = match gate.operator {
+                Operator::Multiply(n) => n.saturating_sub(1),
+                Operator::Add(n) => if index == 0 { n } else { 0 },
+                Operator::Subtract(n) => {
+                    if index < n {
+                        commands.entity(ball_e).despawn_recursive();
+                    }
+                    0
+                }
+                Operator::Divide(n) => {
+                    if index % n.max(1) != 0 {
+                        commands.entity(ball_e).despawn_recursive();
+                    }
+                    0
+                }
+            };
+
+            let Some(balltype) = ball_type(zundamon.is_some(), zombie.is_some(), species, unit) else { continue; };
+            for _ in 0..copies {
+                if spawned >= MAX_SPAWN_PER_FRAME { break; }
+                spawned += 1;
+
+                // nearby, so the copies don't start inside each other
+                let offset = Vec2::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0)) * ball.radius * 2.0;
+                let position = ball_t.translation.truncate() + offset;
+                let copy = cmp_gate_generic::spawn(&mut commands, &game_assets, &registry, &unit_registry, &team_config,
+                                                   &balltype, position, ball.radius, ball_v.linvel);
+                if let Some(copy) = copy {
+                    commands.entity(copy).insert(passed.clone());
+                }
+            }
+        }
+
+        // spent gates fade out
+        if gate.capacity.map_or(false, |c| gate.used >= c) {
+            gate_sprite.color.set_a(0.15);
+        }
+    }
+}
+
+
+// restores the spent gates when a game starts
+pub fn system_setup(
+    mut gate_q: Query<(&mut Sprite, &mut GateMath)>,
+) {
+    for (mut sprite, mut gate) in gate_q.iter_mut() {
+        gate.used = 0;
+        sprite.color = gate.operator.color();
+    }
+}
+
+
+// the operator label
+pub fn system_label(
+    mut commands: Commands,
+    game_assets: Res<GameAsset>,
+    gate_q: Query<(Entity, &GateMath, Option<&Children>)>,
+    mut label_q: Query<&mut Text, With<GateMathLabel>>,
+) {
+    let font = game_assets.font_handles.get("font1_handle").unwrap();
+    for (entity, gate, children) in gate_q.iter() {
+        let style = TextStyle { font: font.clone(), font_size: 28.0, color: Color::WHITE };
+        child_label::update(&mut commands, &mut label_q, entity, children, gate.operator.label(),
+                            style, Vec3::new(0.0, DEFAULT_SIZE_Y * 1.5, 0.1), GateMathLabel);
+    }
+}
+
+
+// a left/right pair spawns side by side, ×2 on the left and +10 on the right
+pub fn handle_user_input(
+    mut commands: Commands,
+    buttons: Res<Input<MouseButton>>,
+    mut edit_context: ResMut<EditContext>,
+    world_position: ResMut<WorldPosition>,
+    ) {
+    if ! buttons.just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    if let EditContext::Spawn(MapObject::GateMath(pair)) = edit_context.clone() {
+        let position = Vec3::from((world_position.translation, 0.0));
+
+        let entities = if pair {
+            let offset = Vec3::new(DEFAULT_SIZE_X / 2.0, 0.0, 0.0);
+            let left_gate = GateMath { operator: Operator::Multiply(2), capacity: None, sibling: None, used: 0 };
+            let right_gate = GateMath { operator: Operator::Add(10), capacity: None, sibling: None, used: 0 };
+            let left = add(&mut commands, None, position - offset, Quat::IDENTITY, Vec3::ONE, left_gate.clone());
+            let right = add(&mut commands, None, position + offset, Quat::IDENTITY, Vec3::ONE, right_gate.clone());
+            // each knows the other, so a ball on the seam takes only one
+            commands.entity(left).insert(GateMath { sibling: Some(right.index()), ..left_gate });
+            commands.entity(right).insert(GateMath { sibling: Some(left.index()), ..right_gate });
+            vec![left, right]
+        } else {
+            vec![add(&mut commands, None, position, Quat::IDENTITY, Vec3::ONE,
+                     GateMath { operator: Operator::Multiply(2), capacity: None, sibling: None, used: 0 })]
+        };
+
+        *edit_context = EditContext::Edit(MapObject::GateMath(false), entities, EditTool::Select);
+    }
+}
+
+
+pub fn edit_window(
+    mut egui_contexts: EguiContexts,
+    edit_context: Res<EditContext>,
+    mut gate_q: Query<(&mut GateMath, &mut Sprite)>,
+    ) {
+    if let EditContext::Edit(MapObject::GateMath(_), pick, _) = edit_context.clone() {
+        if pick.len() == 0 { return; }
+
+        if let Ok((mut gate, mut sprite)) = gate_q.get_mut(pick[0]) {
+            let mut g = gate.clone();
+
+            egui::Window::new("GateMath").show(egui_contexts.ctx_mut(), |ui: &mut egui::Ui| {
+                ui.horizontal(|ui: &mut egui::Ui| {
+                    for operator in Operator::LIST {
+                        let selected = operator.symbol() == g.operator.symbol();
+                        if ui.selectable_label(selected, operator.symbol()).clicked() && ! selected {
+                            g.operator = operator;
+                        }
+                    }
+                    ui.add(egui::DragValue::new(g.operator.n_mut()).clamp_range(1..=100));
+                });
+                ui.horizontal(|ui: &mut egui::Ui| {
+                    let mut limited = g.capacity.is_some();
+                    ui.checkbox(&mut limited, "capacity");
+                    if limited != g.capacity.is_some() {
+                        g.capacity = if limited { Some(10) } else { None };
+                    }
+                    if let Some(capacity) = g.capacity.as_mut() {
+                        ui.add(egui::DragValue::new(capacity).clamp_range(1..=1000));
+                    }
+                });
+            });
+
+            if g != *gate {
+                sprite.color = g.operator.color();
+                *gate = g;
+            }
+        }
+    }
+}
+
+
+const FILE_NAME: &str = "/gate_math.map";
+use crate::ev_save_load_world::LoadWorldEvent;
+pub fn load(
+    mut load_world_er: EventReader<LoadWorldEvent>,
+    mut commands: Commands,
+    ) {
+
+    for e in load_world_er.iter() {
+        let dir = e.0.clone();
+
+        let json_str = std::fs::read_to_string(dir + FILE_NAME);
+        if let Ok(json_str) = json_str {
+            let elem_list: Vec<(u32, Vec3, Quat, Vec3, GateMath)> = serde_json::from_str(&json_str).unwrap();
+
+            for (i, t, r, s, gate) in elem_list {
+                add(&mut commands, Some(i), t, r, s, gate);
+            }
+        }
+    }
+}
+
+
+use crate::ev_save_load_world::SaveWorldEvent;
+pub fn save(mut save_world_er: EventReader<SaveWorldEvent>,
+              q: Query<(Entity, &Transform, &GateMath)>,
+              ) {
+
+    for e in save_world_er.iter() {
+        let dir = e.0.clone();
+        let mut elem_list: Vec<(u32, Vec3, Quat, Vec3, GateMath)> = vec![];
+
+        for (e, t, gate) in q.iter() {
+            elem_list.push((e.index(), t.translation, t.rotation, t.scale, gate.clone()));
+        }
+
+        std::fs::write(dir + FILE_NAME, serde_json::to_string(&elem_list).unwrap()).unwrap();
+    }
+}
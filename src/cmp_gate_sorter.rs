@@ -0,0 +1,366 @@
+use serde::{Serialize, Deserialize};
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use bevy_inspector_egui::bevy_egui::{egui, EguiContexts};
+
+use crate::cmp_ball::Ball;
+use crate::cmp_ball_zundamon::Zundamon;
+use crate::cmp_ball_zombie::Zombie;
+use crate::cmp_ball_species::Species;
+use crate::cmp_bbsize::BBSize;
+use crate::cmp_combat_unit::Unit;
+use crate::cmp_gate_generic::BallType;
+use crate::cmp_team;
+use crate::cmp_team::Team;
+
+use crate::contacts;
+use crate::edit_context::*;
+
+pub const DEFAULT_SIZE_X: f32 = 80.0;
+pub const DEFAULT_SIZE_Y: f32 = 10.0;
+pub const DEFAULT_SPEED: f32 = 400.0;
+
+
+// what a ball needs to pass the gate untouched
+#[derive(Reflect, FromReflect, Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub enum SortFilter {
+    // Zundamon, Zombie, a species or a unit; units also match on their team
+    BallType(BallType),
+    Team(Team),
+    Radius { min: f32, max: f32 },
+}
+
+impl SortFilter {
+    pub const NAMES: [&'static str; 3] = ["Type", "Team", "Radius"];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SortFilter::BallType(_) => "Type",
+            SortFilter::Team(_) => "Team",
+            SortFilter::Radius { .. } => "Radius",
+        }
+    }
+
+    fn from_name(name: &str) -> Self {
+        match name {
+            "Team" => SortFilter::Team(Team(0)),
+            "Radius" => SortFilter::Radius { min: 0.0, max: 20.0 },
+            _ => SortFilter::BallType(BallType::Zundamon),
+        }
+    }
+}
+
+// what happens to the balls that don't pass
+#[derive(Reflect, FromReflect, Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub enum Redirect {
+    // moved onto the entity with this index, e.g. a teleport exit
+    Teleport { target: u32 },
+    // launched like from a velocity pad, direction in the gate's local space
+    Velocity { direction: Vec2, speed: f32 },
+}
+
+#[derive(Component, Reflect, Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct GateSorter {
+    pub filter: SortFilter,
+    // redirect the matching balls instead
+    pub invert: bool,
+    pub redirect: Redirect,
+}
+
+impl Default for GateSorter {
+    fn default() -> Self {
+        Self {
+            filter: SortFilter::BallType(BallType::Zundamon),
+            invert: false,
+            redirect: Redirect::Velocity { direction: Vec2::Y, speed: DEFAULT_SPEED },
+        }
+    }
+}
+
+
+#[derive(Bundle)]
+pub struct GateSorterBundle {
+    bbsize: BBSize,
+    gate_sorter: GateSorter,
+    collider: Collider,
+    sensor: Sensor,
+    map_object: MapObject,
+    #[bundle]
+    sprite_bundle: SpriteBundle,
+}
+
+
+impl Default for GateSorterBundle {
+    fn default() -> Self {
+        Self {
+            bbsize: BBSize {x: DEFAULT_SIZE_X, y: DEFAULT_SIZE_Y},
+            gate_sorter: GateSorter::default(),
+            collider: Collider::cuboid(DEFAULT_SIZE_X / 2.0, DEFAULT_SIZE_Y / 2.0),
+            sensor: Sensor,
+            sprite_bundle: SpriteBundle {
+                sprite: Sprite {
+                    color: Color::ORANGE,
+                    custom_size: Some(Vec2::new(DEFAULT_SIZE_X, DEFAULT_SIZE_Y)),
+                    ..Default::default()
+                },
+                transform: Transform::from_scale(Vec3::ONE),
+                ..Default::default()
+            },
+            map_object: MapObject::GateSorter(None),
+        }
+    }
+}
+
+impl From<(Vec3, Quat, Vec3, GateSorter)> for GateSorterBundle {
+    fn from(tuple: (Vec3, Quat, Vec3, GateSorter)) -> Self {
+        let (translation, rotation, scale, gate_sorter) = tuple;
+
+        let mut bundle = GateSorterBundle::default();
+
+        bundle.sprite_bundle.transform = Transform {
+                    translation,
+                    rotation,
+                    scale,
+                };
+        bundle.gate_sorter = gate_sorter;
+
+        bundle
+    }
+}
+
+
+// spawns with the previously selected entity as teleport target, if any
+pub fn handle_user_input(
+    mut commands: Commands,
+    buttons: Res<Input<MouseButton>>,
+    mut edit_context: ResMut<EditContext>,
+    world_position: ResMut<WorldPosition>,
+    ) {
+    if ! buttons.just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    if let EditContext::Spawn(MapObject::GateSorter(target)) = edit_context.clone() {
+        let mut gate_sorter = GateSorter::default();
+        if let Some(target) = target {
+            gate_sorter.redirect = Redirect::Teleport { target };
+        }
+
+        let entity = commands.spawn(
+            GateSorterBundle::from(
+                (Vec3::from((world_position.translation, 0.0)),
+                Quat::from_rotation_z(0.0),
+                Vec3::ONE,
+                gate_sorter,
+                ))
+            ).id();
+
+        *edit_context = EditContext::Edit(MapObject::GateSorter(None), vec![entity], EditTool::Select);
+    }
+}
+
+
+fn passes(filter: &SortFilter,
+           ball: &Ball,
+           zundamon: bool,
+           zombie: bool,
+           species: Option<&Species>,
+           unit: Option<(&Unit, &Team)>) -> bool {
+    match filter {
+        SortFilter::BallType(BallType::Zundamon) => zundamon,
+        SortFilter::BallType(BallType::Zombie) => zombie,
+        SortFilter::BallType(BallType::Species(name)) => species.map_or(false, |s| s.0 == *name),
+        SortFilter::BallType(ball_type) => {
+            match (ball_type.unit(), unit) {
+                (Some((name, team)), Some((unit, unit_team))) => unit.name == name && team == *unit_team,
+                _ => false,
+            }
+        }
+        SortFilter::Team(team) => unit.map_or(false, |(_, unit_team)| unit_team == team),
+        SortFilter::Radius { min, max } => *min <= ball.radius && ball.radius <= *max,
+    }
+}
+
+pub fn system(
+    rapier_context: Res<RapierContext>,
+    sorter_q: Query<(Entity, &Transform, &GateSorter)>,
+    target_q: Query<&Transform, Without<Ball>>,
+    mut ball_q: Query<(&Ball, &mut Transform, &mut Velocity,
+                       Option<&Zundamon>, Option<&Zombie>, Option<&Species>, Option<(&Unit, &Team)>),
+                      Without<GateSorter>>,
+) {
+    for (sorter_e, sorter_t, sorter) in sorter_q.iter() {
+        for other in contacts::intersecting(&rapier_context, sorter_e) {
+            let Ok((ball, mut ball_t, mut ball_v, zundamon, zombie, species, unit)) = ball_q.get_mut(other) else { continue; };
+
+            let pass = passes(&sorter.filter, ball, zundamon.is_some(), zombie.is_some(), species, unit);
+            if pass != sorter.invert { continue; }
+
+            match sorter.redirect {
+                Redirect::Teleport { target } => {
+                    // a deleted target leaves the gate letting everything through
+                    if target == sorter_e.index() { continue; }
+                    if let Ok(target_t) = target_q.get(Entity::from_raw(target)) {
+                        ball_t.translation.x = target_t.translation.x;
+                        ball_t.translation.y = target_t.translation.y;
+                    }
+                }
+                Redirect::Velocity { direction, speed } => {
+                    let direction = (sorter_t.rotation * Vec3::from((direction, 0.0))).truncate();
+                    ball_v.linvel = direction.normalize_or_zero() * speed;
+                }
+            }
+        }
+    }
+}
+
+
+pub fn edit_window(
+    mut egui_contexts: EguiContexts,
+    edit_context: Res<EditContext>,
+    mut sorter_q: Query<&mut GateSorter>,
+    ) {
+    if let EditContext::Edit(MapObject::GateSorter(_), pick, _) = edit_context.clone() {
+        if pick.len() == 0 { return; }
+
+        if let Ok(mut sorter) = sorter_q.get_mut(pick[0]) {
+            let mut s = sorter.clone();
+
+            egui::Window::new("GateSorter").show(egui_contexts.ctx_mut(), |ui: &mut egui::Ui| {
+                ui.horizontal(|ui: &mut egui::Ui| {
+                    ui.label("pass");
+                    for name in SortFilter::NAMES {
+                        if ui.selectable_label(s.filter.name() == name, name).clicked() && s.filter.name() != name {
+                            s.filter = SortFilter::from_name(name);
+                        }
+                    }
+                    ui.checkbox(&mut s.invert, "invert");
+                });
+                ui.horizontal(|ui: &mut egui::Ui| {
+                    match &mut s.filter {
+                        SortFilter::BallType(ball_type) => {
+                            let is_unit = ball_type.unit().is_some();
+                            if ui.selectable_label(*ball_type == BallType::Zundamon, "Zundamon").clicked() {
+                                *ball_type = BallType::Zundamon;
+                            }
+                            if ui.selectable_label(*ball_type == BallType::Zombie, "Zombie").clicked() {
+                                *ball_type = BallType::Zombie;
+                            }
+                            if ui.selectable_label(matches!(ball_type, BallType::Species(_)), "Species").clicked() {
+                                *ball_type = BallType::Species(String::new());
+                            }
+                            if ui.selectable_label(is_unit, "Unit").clicked() && ! is_unit {
+                                *ball_type = BallType::Unit(String::from("type1"), Team(0));
+                            }
+                        }
+                        SortFilter::Team(team) => {
+                            ui.label("team");
+                            ui.add(egui::DragValue::new(&mut team.0).clamp_range(0..=cmp_team::MAX_TEAMS - 1));
+                        }
+                        SortFilter::Radius { min, max } => {
+                            ui.label("min");
+                            ui.add(egui::DragValue::new(min).clamp_range(0.0..=500.0));
+                            ui.label("max");
+                            ui.add(egui::DragValue::new(max).clamp_range(0.0..=500.0));
+                        }
+                    }
+                });
+                if let SortFilter::BallType(ball_type) = &mut s.filter {
+                    // legacy unit types are edited as named units
+                    if let Some((name, team)) = ball_type.unit() {
+                        if ! matches!(ball_type, BallType::Unit(..)) {
+                            *ball_type = BallType::Unit(name, team);
+                        }
+                    }
+                    match ball_type {
+                        BallType::Species(name) => {
+                            ui.horizontal(|ui: &mut egui::Ui| {
+                                ui.label("species");
+                                ui.text_edit_singleline(name);
+                            });
+                        }
+                        BallType::Unit(name, team) => {
+                            ui.horizontal(|ui: &mut egui::Ui| {
+                                ui.label("unit");
+                                ui.text_edit_singleline(name);
+                                ui.label("team");
+                                ui.add(egui::DragValue::new(&mut team.0).clamp_range(0..=cmp_team::MAX_TEAMS - 1));
+                            });
+                        }
+                        _ => {}
+                    }
+                }
+                ui.horizontal(|ui: &mut egui::Ui| {
+                    ui.label("redirect");
+                    let teleport = matches!(s.redirect, Redirect::Teleport { .. });
+                    if ui.selectable_label(teleport, "Teleport").clicked() && ! teleport {
+                        s.redirect = Redirect::Teleport { target: pick[0].index() };
+                    }
+                    if ui.selectable_label(! teleport, "Velocity").clicked() && teleport {
+                        s.redirect = Redirect::Velocity { direction: Vec2::Y, speed: DEFAULT_SPEED };
+                    }
+                });
+                ui.horizontal(|ui: &mut egui::Ui| {
+                    match &mut s.redirect {
+                        Redirect::Teleport { target } => {
+                            ui.label("target entity");
+                            ui.add(egui::DragValue::new(target));
+                        }
+                        Redirect::Velocity { direction, speed } => {
+                            ui.label("direction");
+                            ui.add(egui::DragValue::new(&mut direction.x).speed(0.01).clamp_range(-1.0..=1.0));
+                            ui.add(egui::DragValue::new(&mut direction.y).speed(0.01).clamp_range(-1.0..=1.0));
+                            ui.label("speed");
+                            ui.add(egui::DragValue::new(speed).clamp_range(0.0..=5000.0));
+                        }
+                    }
+                });
+            });
+
+            if s != *sorter {
+                *sorter = s;
+            }
+        }
+    }
+}
+
+
+const FILE_NAME: &str = "/gate_sorter.map";
+use crate::ev_save_load_world::LoadWorldEvent;
+pub fn load(
+    mut load_world_er: EventReader<LoadWorldEvent>,
+    mut commands: Commands,
+    ) {
+
+    for e in load_world_er.iter() {
+        let dir = e.0.clone();
+
+        let json_str = std::fs::read_to_string(dir + FILE_NAME);
+        if let Ok(json_str) = json_str {
+            let elem_list: Vec<(u32, Vec3, Quat, Vec3, GateSorter)> = serde_json::from_str(&json_str).unwrap();
+
+            for (i, t, r, s, sorter) in elem_list {
+                let mut entity = commands.get_or_spawn(Entity::from_raw(i));
+                entity.insert(GateSorterBundle::from((t, r, s, sorter)));
+            }
+        }
+    }
+}
+
+
+use crate::ev_save_load_world::SaveWorldEvent;
+pub fn save(mut save_world_er: EventReader<SaveWorldEvent>,
+              q: Query<(Entity, &Transform, &GateSorter)>,
+              ) {
+
+    for e in save_world_er.iter() {
+        let dir = e.0.clone();
+        let mut elem_list: Vec<(u32, Vec3, Quat, Vec3, GateSorter)> = vec![];
+
+        for (e, t, sorter) in q.iter() {
+            elem_list.push((e.index(), t.translation, t.rotation, t.scale, sorter.clone()));
+        }
+
+        std::fs::write(dir + FILE_NAME, serde_json::to_string(&elem_list).unwrap()).unwrap();
+    }
+}
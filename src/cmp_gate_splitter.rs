@@ -11,6 +11,7 @@ use crate::cmp_game_asset::GameAsset;
 use crate::cmp_ball_zundamon;
 use crate::cmp_ball_zombie;
 use crate::cmp_gate_generic;
+use crate::contacts;
 
 use crate::cmp_gate_generic::SpawnBall;
 
@@ -123,8 +124,8 @@ pub fn system(
     mut event: EventWriter<cmp_gate_generic::SpawnBall>,
 ) {
     for (splitter_e, mut splitter_sprite, mut splitter) in splitter_q.iter_mut() {
-        for ball_e in ball_q.iter_mut() {
-            if rapier_context.intersection_pair(splitter_e, ball_e) == Some(true) {
+        for other in contacts::intersecting(&rapier_context, splitter_e) {
+            if let Ok(ball_e) = ball_q.get(other) {
                 commands.entity(ball_e).despawn();
                 let mut signals = splitter.signals.clone();
                 if let Some(remain) = splitter.remaining.as_mut() {
@@ -1,31 +1,104 @@
 use serde::{Serialize, Deserialize};
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
+use bevy_inspector_egui::bevy_egui::{egui, EguiContexts};
+use rand::prelude::*;
+use std::collections::HashSet;
+
 use crate::cmp_ball::Ball;
 use crate::cmp_bbsize::BBSize;
+use crate::edit_context::*;
+
+
+const COOLDOWN: f32 = 0.5;
 
+fn default_cooldown() -> f32 { COOLDOWN }
+
+#[derive(Reflect, FromReflect, Clone, Copy, PartialEq, Serialize, Deserialize, Debug, Default)]
+pub enum Momentum {
+    // older maps: the ball stops at the exit
+    #[default]
+    Zero,
+    Preserve,
+    // turned by the angle between entrance and exit
+    Rotate,
+}
 
-#[derive(Component, Reflect, Clone, Serialize, Deserialize, Debug)]
+#[derive(Reflect, FromReflect, Clone, Copy, PartialEq, Serialize, Deserialize, Debug, Default)]
+pub enum Distribution {
+    #[default]
+    RoundRobin,
+    Random,
+}
+
+#[derive(Component, Reflect, Clone, PartialEq, Serialize, Deserialize, Debug)]
 pub struct GateTeleportEntrance{
     pub id: u32,
     pub size: Vec2,
     pub position: Vec2,
     pub color: Color,
+    #[serde(default)]
+    pub angle: f32,
+    #[serde(default)]
+    pub momentum: Momentum,
+    #[serde(default)]
+    pub distribution: Distribution,
+    // exits with the same id send balls back here
+    #[serde(default)]
+    pub two_way: bool,
+    // seconds before a teleported ball can teleport again
+    #[serde(default = "default_cooldown")]
+    pub cooldown: f32,
+    #[serde(skip)]
+    pub next: usize,
     }
 
-#[derive(Component, Reflect, Clone, Serialize, Deserialize, Debug)]
+#[derive(Component, Reflect, Clone, PartialEq, Serialize, Deserialize, Debug)]
 pub struct GateTeleportExit{
     pub id: u32,
     pub size: Vec2,
     pub position: Vec2,
     pub color: Color,
+    #[serde(default)]
+    pub angle: f32,
     }
 
-pub fn add_entrance(commands: &mut Commands, gate_teleport: GateTeleportEntrance) -> Entity {
-    let size = gate_teleport.size;
-    let pos = gate_teleport.position;
-    let color = gate_teleport.color;
-    let mut entity = commands
+#[derive(Component)]
+pub struct TeleportCooldown {
+    timer: Timer,
+}
+
+impl GateTeleportEntrance {
+    pub fn new(id: u32, size: Vec2, position: Vec2, color: Color) -> Self {
+        Self {
+            id,
+            size,
+            position,
+            color,
+            angle: 0.0,
+            momentum: Momentum::Rotate,
+            distribution: Distribution::RoundRobin,
+            two_way: false,
+            cooldown: COOLDOWN,
+            next: 0,
+        }
+    }
+
+    // index into `count` candidates according to the distribution
+    fn pick(&mut self, count: usize) -> usize {
+        match self.distribution {
+            Distribution::RoundRobin => {
+                let index = self.next % count;
+                self.next = self.next + 1;
+                index
+            }
+            Distribution::Random => rand::thread_rng().gen_range(0..count),
+        }
+    }
+}
+
+fn spawn_gate(commands: &mut Commands, size: Vec2, pos: Vec2, angle: f32, color: Color, id: u32) -> Entity {
+    commands
         .spawn(SpriteBundle {
                 sprite: Sprite {
                     color,
@@ -33,69 +106,181 @@ pub fn add_entrance(commands: &mut Commands, gate_teleport: GateTeleportEntrance
                     ..Default::default()
                 },
                 ..Default::default()
-            });
-        // .insert(Collider::cuboid(size.x / 2.0, size.y / 2.0))
-
-    entity
-        .insert(TransformBundle::from(Transform::from_xyz(pos.x, pos.y, 0.0)))
+            })
+        .insert(TransformBundle::from(Transform {
+                translation: Vec3::new(pos.x, pos.y, 0.0),
+                rotation: Quat::from_rotation_z(angle),
+                ..default()
+            }))
         .insert(BBSize{x: size.x, y: size.y})
-        .insert(gate_teleport);
+        .insert(MapObject::GateTeleport(Some((id, color))))
+        .id()
+}
+
+pub fn add_entrance(commands: &mut Commands, gate_teleport: GateTeleportEntrance) -> Entity {
+    let entity = spawn_gate(commands, gate_teleport.size, gate_teleport.position, gate_teleport.angle,
+                            gate_teleport.color, gate_teleport.id);
+    commands.entity(entity).insert(gate_teleport);
 
-    return entity.id();
+    return entity;
 }
 
 pub fn add_exit(commands: &mut Commands, gate_teleport: GateTeleportExit) -> Entity {
-    let size = gate_teleport.size;
-    let pos = gate_teleport.position;
-    let color = gate_teleport.color;
+    let entity = spawn_gate(commands, gate_teleport.size, gate_teleport.position, gate_teleport.angle,
+                            gate_teleport.color, gate_teleport.id);
+    commands.entity(entity).insert(gate_teleport);
 
-    let mut entity = commands
-        .spawn(SpriteBundle {
-                sprite: Sprite {
-                    color,
-                    custom_size: Some(Vec2::new(size.x, size.y)),
-                    ..Default::default()
-                },
-                ..Default::default()
-            });
-        // .insert(Collider::cuboid(size.x / 2.0, size.y / 2.0))
+    return entity;
+}
 
-    entity
-        .insert(TransformBundle::from(Transform::from_xyz(pos.x, pos.y, 0.0)))
-        .insert(BBSize{x: size.x, y: size.y})
-        .insert(gate_teleport);
 
-    return entity.id();
+// dynamic bodies overlapping the gate's box
+fn overlapping(rapier_context: &RapierContext, transform: &Transform, bbsize: &BBSize) -> Vec<Entity> {
+    let cuboid_size = Vec2::new(bbsize.x, bbsize.y) / 2.0 * transform.scale.truncate();
+    let shape = Collider::cuboid(cuboid_size.x, cuboid_size.y);
+    let shape_pos = transform.translation.truncate();
+    let (shape_rot, _, _) = transform.rotation.to_euler(EulerRot::ZXY);
+    let filter = QueryFilter::only_dynamic()
+                    .groups(CollisionGroups::new(Group::GROUP_1, Group::GROUP_1));
+
+    let mut entities = vec![];
+    rapier_context.intersections_with_shape(
+        shape_pos, shape_rot, &shape, filter, |entity| {
+            entities.push(entity);
+            true
+    });
+    entities
+}
+
+fn angle_of(transform: &Transform) -> f32 {
+    transform.rotation.to_euler(EulerRot::ZXY).0
+}
+
+fn carry(velocity: Vec2, momentum: Momentum, from: &Transform, to: &Transform) -> Vec2 {
+    match momentum {
+        Momentum::Zero => Vec2::ZERO,
+        Momentum::Preserve => velocity,
+        Momentum::Rotate => Vec2::from_angle(angle_of(to) - angle_of(from)).rotate(velocity),
+    }
 }
 
 
 pub fn system(
+    mut commands: Commands,
+    time: Res<Time>,
     rapier_context: Res<RapierContext>,
-    mut ball_q: Query<(&mut Transform, &mut Velocity), With<Ball>>,
-    mut entrance_q: Query<(&Transform, &BBSize, &GateTeleportEntrance), Without<Ball>>,
-    exit_q: Query<(&Transform, &GateTeleportExit), Without<Ball>>,
+    mut ball_q: Query<(Entity, &mut Transform, &mut Velocity, Option<&mut TeleportCooldown>), With<Ball>>,
+    mut entrance_q: Query<(&Transform, &BBSize, &mut GateTeleportEntrance), Without<Ball>>,
+    exit_q: Query<(Entity, &Transform, &BBSize, &GateTeleportExit), Without<Ball>>,
 ) {
-    for (transform, bbsize, gate_teleport) in entrance_q.iter_mut() {
-        let cuboid_size = Vec2::new(bbsize.x, bbsize.y) / 2.0 * transform.scale.truncate();
-        let shape = Collider::cuboid(cuboid_size.x, cuboid_size.y);
-        let shape_pos = transform.translation.truncate();
-        let (shape_rot, _, _) = transform.rotation.to_euler(EulerRot::ZXY);
-        let filter = QueryFilter::only_dynamic()
-                        .groups(CollisionGroups::new(Group::GROUP_1, Group::GROUP_1));
-
-        rapier_context.intersections_with_shape(
-            shape_pos, shape_rot, &shape, filter, |entity| {
-                if let Ok((mut ball_transform, mut ball_velocity)) = ball_q.get_mut(entity) {
-                    for (exit_transform, exit_gate_teleport) in exit_q.iter() {
-                        if exit_gate_teleport.id == gate_teleport.id {
-                            ball_transform.translation = exit_transform.translation;
-                            ball_velocity.linvel = Vec2::ZERO;
-                            break;
-                        }
-                    }
+    // balls on any gate. A teleported ball lands on a gate, so it is re-armed
+    // only once it has left, otherwise it bounces between two way gates
+    let mut inside = HashSet::new();
+    for (transform, bbsize, _) in entrance_q.iter() {
+        inside.extend(overlapping(&rapier_context, transform, bbsize));
+    }
+    for (_, transform, bbsize, _) in exit_q.iter() {
+        inside.extend(overlapping(&rapier_context, transform, bbsize));
+    }
+
+    // balls allowed to teleport, each at most once a frame
+    let mut armed = HashSet::new();
+    for (ball, _, _, cooldown) in ball_q.iter_mut() {
+        match cooldown {
+            Some(mut cooldown) => {
+                cooldown.timer.tick(time.delta());
+                if cooldown.timer.finished() && ! inside.contains(&ball) {
+                    commands.entity(ball).remove::<TeleportCooldown>();
                 }
-            true // Return `false` instead if we want to stop searching for other colliders that contain this point.
-        });
+            }
+            None => { armed.insert(ball); }
+        }
+    }
+
+    // exits in a stable order, so round robin goes round the same way every run
+    let mut exits: Vec<(Entity, Transform, &BBSize, &GateTeleportExit)> = exit_q.iter()
+        .map(|(e, t, b, g)| (e, *t, b, g))
+        .collect();
+    exits.sort_by_key(|(e, _, _, _)| e.index());
+
+    let mut teleport = |commands: &mut Commands, ball: Entity, from: &Transform, to: &Transform, entrance: &GateTeleportEntrance| {
+        let Ok((_, mut ball_t, mut ball_v, _)) = ball_q.get_mut(ball) else { return; };
+
+        ball_t.translation = Vec3::from((to.translation.truncate(), ball_t.translation.z));
+        ball_v.linvel = carry(ball_v.linvel, entrance.momentum, from, to);
+        commands.entity(ball).insert(TeleportCooldown { timer: Timer::from_seconds(entrance.cooldown, TimerMode::Once) });
+    };
+
+    for (transform, bbsize, mut entrance) in entrance_q.iter_mut() {
+        let targets: Vec<Transform> = exits.iter()
+            .filter(|(_, _, _, g)| g.id == entrance.id)
+            .map(|(_, t, _, _)| *t)
+            .collect();
+        if targets.len() == 0 { continue; }
+
+        for ball in overlapping(&rapier_context, transform, bbsize) {
+            if ! armed.remove(&ball) { continue; }
+            // picked only for a ball that really goes, so round robin doesn't skip exits
+            let target = targets[entrance.pick(targets.len())];
+            teleport(&mut commands, ball, transform, &target, &entrance);
+        }
+
+        if ! entrance.two_way { continue; }
+
+        // the way back always leads to this entrance
+        for (_, exit_t, exit_bbsize, exit) in exits.iter() {
+            if exit.id != entrance.id { continue; }
+
+            for ball in overlapping(&rapier_context, exit_t, exit_bbsize) {
+                if ! armed.remove(&ball) { continue; }
+                teleport(&mut commands, ball, exit_t, transform, &entrance);
+            }
+        }
+    }
+}
+
+
+pub fn edit_window(
+    mut egui_contexts: EguiContexts,
+    mut edit_context: ResMut<EditContext>,
+    mut entrance_q: Query<&mut GateTeleportEntrance>,
+    ) {
+    if let EditContext::Edit(MapObject::GateTeleport(_), pick, _) = edit_context.clone() {
+        if pick.len() == 0 { return; }
+
+        if let Ok(mut entrance) = entrance_q.get_mut(pick[0]) {
+            let mut g = entrance.clone();
+            let mut add_exit = false;
+
+            egui::Window::new("GateTeleport").show(egui_contexts.ctx_mut(), |ui: &mut egui::Ui| {
+                ui.horizontal(|ui: &mut egui::Ui| {
+                    ui.label("momentum");
+                    ui.selectable_value(&mut g.momentum, Momentum::Zero, "Zero");
+                    ui.selectable_value(&mut g.momentum, Momentum::Preserve, "Preserve");
+                    ui.selectable_value(&mut g.momentum, Momentum::Rotate, "Rotate");
+                });
+                ui.horizontal(|ui: &mut egui::Ui| {
+                    ui.label("exits");
+                    ui.selectable_value(&mut g.distribution, Distribution::RoundRobin, "RoundRobin");
+                    ui.selectable_value(&mut g.distribution, Distribution::Random, "Random");
+                    if ui.button("add exit").clicked() {
+                        add_exit = true;
+                    }
+                });
+                ui.horizontal(|ui: &mut egui::Ui| {
+                    ui.checkbox(&mut g.two_way, "two way");
+                    ui.label("cooldown");
+                    ui.add(egui::DragValue::new(&mut g.cooldown).speed(0.1).clamp_range(0.0..=10.0));
+                });
+            });
+
+            if g != *entrance {
+                *entrance = g.clone();
+            }
+            if add_exit {
+                *edit_context = EditContext::Spawn(MapObject::GateTeleport(Some((g.id, g.color))));
+            }
+        }
     }
 }
 
@@ -151,6 +336,7 @@ pub fn save(mut save_world_er: EventReader<SaveWorldEvent>,
                 let mut e = e.clone();
                 e.size = e.size * t.scale.truncate();
                 e.position = t.translation.truncate();
+                e.angle = angle_of(t);
                 elem_list.push(e.clone());
             }
 
@@ -165,6 +351,7 @@ pub fn save(mut save_world_er: EventReader<SaveWorldEvent>,
                 let mut e = e.clone();
                 e.size = e.size * t.scale.truncate();
                 e.position = t.translation.truncate();
+                e.angle = angle_of(t);
                 elem_list.push(e.clone());
             }
 
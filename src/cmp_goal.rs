@@ -0,0 +1,338 @@
+use serde::{Serialize, Deserialize};
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use bevy_inspector_egui::bevy_egui::{egui, EguiContexts};
+use std::collections::HashSet;
+
+use crate::cmp_ball::Ball;
+use crate::cmp_ball_species::BallSpeciesRegistry;
+use crate::cmp_ball_species::Species;
+use crate::cmp_bbsize::BBSize;
+use crate::cmp_cleanup::BallRemoved;
+use crate::cmp_cleanup::RemoveReason;
+use crate::cmp_game_asset::GameAsset;
+use crate::cmp_match::MatchState;
+use crate::cmp_particle;
+use crate::cmp_particle::Preset;
+use crate::child_label;
+use crate::contacts;
+use crate::edit_context::*;
+use crate::ev_save_load_world;
+
+pub const DEFAULT_SIZE_X: f32 = 120.0;
+pub const DEFAULT_SIZE_Y: f32 = 40.0;
+const POPUP_TIME: f32 = 1.0;
+const POPUP_RISE: f32 = 60.0;
+
+
+// Collects the balls falling in and scores them, the ball's species
+// points times the multiplier.
+#[derive(Component, Reflect, Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct GoalBucket {
+    pub multiplier: u32,
+}
+
+#[derive(Component)]
+pub struct GoalLabel;
+
+#[derive(Resource, Default, Debug)]
+pub struct Score {
+    pub points: u32,
+    pub balls: u32,
+    // balls the cleanup rules removed before they reached a bucket
+    pub lost: u32,
+    // best of the current map, from its best_score.map
+    pub best: u32,
+}
+
+pub struct GoalReached {
+    pub bucket: Entity,
+    pub points: u32,
+}
+
+#[derive(Component)]
+pub struct ScoreText;
+
+// the "+20" rising from a bucket, seconds left
+#[derive(Component)]
+pub struct ScorePopup(f32);
+
+
+// `index` reuses the entity saved in a map, so a rematch reload replaces it
+pub fn add(commands: &mut Commands, index: Option<u32>, translation: Vec3, rotation: Quat, scale: Vec3, bucket: GoalBucket) -> Entity {
+    let mut entity = ev_save_load_world::get_or_spawn(commands, index);
+    entity
+        .insert(SpriteBundle {
+            sprite: Sprite {
+                color: Color::GOLD.with_a(0.4),
+                custom_size: Some(Vec2::new(DEFAULT_SIZE_X, DEFAULT_SIZE_Y)),
+                ..default()
+            },
+            transform: Transform { translation, rotation, scale },
+            ..default()
+        })
+        .insert(Collider::cuboid(DEFAULT_SIZE_X / 2.0, DEFAULT_SIZE_Y / 2.0))
+        .insert(Sensor)
+        .insert(BBSize{x: DEFAULT_SIZE_X, y: DEFAULT_SIZE_Y})
+        .insert(MapObject::GoalBucket)
+        .insert(bucket)
+        .id()
+}
+
+
+pub fn system(
+    mut commands: Commands,
+    rapier_context: Res<RapierContext>,
+    registry: Res<BallSpeciesRegistry>,
+    mut score: ResMut<Score>,
+    mut goal_ew: EventWriter<GoalReached>,
+    bucket_q: Query<(Entity, &GoalBucket)>,
+    ball_q: Query<(&Transform, Option<&Species>), With<Ball>>,
+) {
+    // a ball in two overlapping buckets scores once
+    let mut scored = HashSet::new();
+    for (bucket_e, bucket) in bucket_q.iter() {
+        for ball_e in contacts::intersecting(&rapier_context, bucket_e) {
+            let Ok((t, species)) = ball_q.get(ball_e) else { continue; };
+            if ! scored.insert(ball_e) { continue; }
+
+            // plain balls without a species are worth one point
+            let points = species.and_then(|s| registry.get(&s.0)).map_or(1, |s| s.points) * bucket.multiplier;
+            score.points += points;
+            score.balls += 1;
+            goal_ew.send(GoalReached { bucket: bucket_e, points });
+            cmp_particle::burst(&mut commands, Preset::Confetti, t.translation.truncate(), 8);
+
+            commands.entity(ball_e).despawn();
+        }
+    }
+}
+
+
+// flashes the bucket a ball just went into
+pub fn system_flash(
+    time: Res<Time>,
+    mut goal_er: EventReader<GoalReached>,
+    mut bucket_q: Query<&mut Sprite, With<GoalBucket>>,
+) {
+    for e in goal_er.iter() {
+        if let Ok(mut sprite) = bucket_q.get_mut(e.bucket) {
+            sprite.color.set_a(0.9);
+        }
+    }
+    for mut sprite in bucket_q.iter_mut() {
+        let a = sprite.color.a();
+        if a > 0.4 {
+            sprite.color.set_a((a - time.delta_seconds()).max(0.4));
+        }
+    }
+}
+
+
+pub fn system_popup(
+    mut commands: Commands,
+    time: Res<Time>,
+    game_assets: Res<GameAsset>,
+    mut goal_er: EventReader<GoalReached>,
+    bucket_q: Query<&Transform, With<GoalBucket>>,
+    mut popup_q: Query<(Entity, &mut ScorePopup, &mut Transform, &mut Text), Without<GoalBucket>>,
+) {
+    let font = game_assets.font_handles.get("font1_handle").unwrap();
+    for e in goal_er.iter() {
+        let Ok(t) = bucket_q.get(e.bucket) else { continue; };
+        commands.spawn(Text2dBundle {
+            text: Text::from_section(format!("+{}", e.points),
+                                     TextStyle { font: font.clone(), font_size: 28.0, color: Color::GOLD }),
+            transform: Transform::from_translation(t.translation + Vec3::new(0.0, DEFAULT_SIZE_Y, 1.0)),
+            ..default()
+        })
+        .insert(ScorePopup(POPUP_TIME));
+    }
+
+    let dt = time.delta_seconds();
+    for (entity, mut popup, mut transform, mut text) in popup_q.iter_mut() {
+        popup.0 -= dt;
+        if popup.0 <= 0.0 {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        transform.translation.y += POPUP_RISE * dt;
+        text.sections[0].style.color.set_a(popup.0 / POPUP_TIME);
+    }
+}
+
+
+// the "x2" on a bucket
+pub fn system_label(
+    mut commands: Commands,
+    game_assets: Res<GameAsset>,
+    bucket_q: Query<(Entity, &GoalBucket, Option<&Children>)>,
+    mut label_q: Query<&mut Text, With<GoalLabel>>,
+) {
+    let font = game_assets.font_handles.get("font1_handle").unwrap();
+    for (entity, bucket, children) in bucket_q.iter() {
+        let style = TextStyle { font: font.clone(), font_size: 24.0, color: Color::rgb(0.9, 0.9, 0.9) };
+        child_label::update(&mut commands, &mut label_q, entity, children, format!("x{}", bucket.multiplier),
+                            style, Vec3::new(0.0, 0.0, 0.1), GoalLabel);
+    }
+}
+
+
+pub fn add_text(
+    mut command: Commands,
+    game_assets: Res<GameAsset>,
+    text_q: Query<Entity, With<ScoreText>>,
+){
+    if ! text_q.is_empty() { return; }
+
+    let font = game_assets.font_handles.get("font1_handle").unwrap();
+    let message = "";
+
+    command.spawn(TextBundle::from_section(
+        message,
+        TextStyle {
+            font_size: 40.0,
+            color: Color::GOLD,
+            font: font.clone(),
+            ..default()
+        })
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Percent(5.0),
+                right: Val::Percent(5.0),
+                ..default()
+            },
+            ..default()
+        }),
+    ).insert(ScoreText);
+}
+
+
+pub fn system_setup(
+    mut score: ResMut<Score>,
+) {
+    score.points = 0;
+    score.balls = 0;
+    score.lost = 0;
+}
+
+
+pub fn system_lost(
+    mut commands: Commands,
+    mut score: ResMut<Score>,
+    mut removed_er: EventReader<BallRemoved>,
+) {
+    for e in removed_er.iter() {
+        score.lost += 1;
+        // out of bounds is off screen anyway
+        if e.reason != RemoveReason::OutOfBounds {
+            cmp_particle::burst(&mut commands, Preset::Smoke, e.position, 6);
+        }
+    }
+}
+
+
+// the scoreboard only shows on maps with buckets
+pub fn system_text(
+    score: Res<Score>,
+    bucket_q: Query<(), With<GoalBucket>>,
+    mut text_q: Query<&mut Text, With<ScoreText>>,
+) {
+    let Ok(mut text) = text_q.get_single_mut() else { return; };
+
+    let mut message = String::new();
+    if ! bucket_q.is_empty() {
+        message = format!("Score {} ({} balls, {} lost)\nBest {}", score.points, score.balls, score.lost, score.best.max(score.points));
+    }
+
+    if text.sections[0].value != message {
+        text.sections[0].value = message;
+    }
+}
+
+
+pub fn edit_window(
+    mut egui_contexts: EguiContexts,
+    edit_context: Res<EditContext>,
+    mut bucket_q: Query<&mut GoalBucket>,
+    ) {
+    if let EditContext::Edit(MapObject::GoalBucket, pick, _) = edit_context.clone() {
+        if pick.len() == 0 { return; }
+
+        if let Ok(mut bucket) = bucket_q.get_mut(pick[0]) {
+            let mut b = bucket.clone();
+
+            egui::Window::new("GoalBucket").show(egui_contexts.ctx_mut(), |ui: &mut egui::Ui| {
+                ui.horizontal(|ui: &mut egui::Ui| {
+                    ui.label("multiplier");
+                    ui.add(egui::DragValue::new(&mut b.multiplier).clamp_range(0..=100));
+                });
+            });
+
+            if b != *bucket {
+                *bucket = b;
+            }
+        }
+    }
+}
+
+
+const FILE_NAME: &str = "/goal_bucket.map";
+const BEST_FILE_NAME: &str = "/best_score.map";
+use crate::ev_save_load_world::LoadWorldEvent;
+pub fn load(
+    mut load_world_er: EventReader<LoadWorldEvent>,
+    mut commands: Commands,
+    mut score: ResMut<Score>,
+    ) {
+
+    for e in load_world_er.iter() {
+        let dir = e.0.clone();
+
+        let json_str = std::fs::read_to_string(dir.clone() + FILE_NAME);
+        if let Ok(json_str) = json_str {
+            let elem_list: Vec<(u32, Vec3, Quat, Vec3, GoalBucket)> = serde_json::from_str(&json_str).unwrap();
+
+            for (i, t, r, s, bucket) in elem_list {
+                add(&mut commands, Some(i), t, r, s, bucket);
+            }
+        }
+
+        let json_str = std::fs::read_to_string(dir + BEST_FILE_NAME);
+        score.best = json_str.ok().map_or(0, |json_str| serde_json::from_str(&json_str).unwrap());
+    }
+}
+
+
+// a new best is written next to the map when the game ends, by a
+// rematch or by going back to Edit
+pub fn save_best(
+    state: Res<MatchState>,
+    mut score: ResMut<Score>,
+) {
+    if score.points <= score.best { return; }
+    score.best = score.points;
+
+    if let Some(dir) = state.last_map.clone() {
+        std::fs::write(dir + BEST_FILE_NAME, serde_json::to_string(&score.best).unwrap()).unwrap();
+    }
+}
+
+
+use crate::ev_save_load_world::SaveWorldEvent;
+pub fn save(mut save_world_er: EventReader<SaveWorldEvent>,
+              q: Query<(Entity, &Transform, &GoalBucket)>,
+              ) {
+
+    for e in save_world_er.iter() {
+        let dir = e.0.clone();
+        let mut elem_list: Vec<(u32, Vec3, Quat, Vec3, GoalBucket)> = vec![];
+
+        for (e, t, bucket) in q.iter() {
+            elem_list.push((e.index(), t.translation, t.rotation, t.scale, bucket.clone()));
+        }
+
+        std::fs::write(dir + FILE_NAME, serde_json::to_string(&elem_list).unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,140 @@
+use bevy::prelude::*;
+use bevy::app::AppExit;
+use bevy::app::ScheduleRunnerPlugin;
+use bevy::app::ScheduleRunnerSettings;
+use bevy::render::RenderPlugin;
+use bevy::render::settings::WgpuSettings;
+use bevy::time::TimeSystem;
+use bevy::time::TimeUpdateStrategy;
+use bevy::window::ExitCondition;
+use bevy::winit::WinitPlugin;
+use std::collections::HashMap;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::cmp_economy::Controller;
+use crate::cmp_economy::EconomyConfig;
+use crate::cmp_match::MatchPhase;
+use crate::cmp_match::MatchRules;
+use crate::cmp_match::MatchState;
+use crate::cmp_match::RequestRematch;
+use crate::cmp_team::Team;
+
+// every frame advances the game by this much, however long it took to compute
+const STEP: f32 = 1.0 / 60.0;
+// maps without victory rules end here so a run always finishes
+const DEFAULT_TIMEOUT: f32 = 300.0;
+
+// AI-vs-AI balance testing without a window:
+//   zunda_shower --headless <map dir> [matches]
+// --bench runs the benchmark scene the same way, see cmp_bench
+#[derive(Resource, Debug)]
+pub struct Headless {
+    pub map: String,
+    pub matches: usize,
+    pub results: HashMap<Option<Team>, usize>,
+}
+
+impl Headless {
+    pub fn from_args() -> Option<Self> {
+        let args: Vec<String> = std::env::args().collect();
+        if args.iter().any(|a| a == "--bench") {
+            return Some(Self { map: String::from(crate::cmp_bench::MAP), matches: 1, results: HashMap::new() });
+        }
+        let index = args.iter().position(|a| a == "--headless")?;
+
+        let map = args.get(index + 1).cloned().unwrap_or(String::from("assets/map"));
+        let matches = args.get(index + 2).and_then(|n| n.parse().ok()).unwrap_or(1);
+
+        Some(Self { map, matches, results: HashMap::new() })
+    }
+}
+
+
+pub fn add_plugins(app: &mut App, headless: Headless) {
+    app.add_plugins(DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: None,
+                exit_condition: ExitCondition::DontExit,
+                ..default()
+            })
+            .set(RenderPlugin {
+                wgpu_settings: WgpuSettings { backends: None, ..default() },
+            })
+            .disable::<WinitPlugin>())
+        .insert_resource(ScheduleRunnerSettings::run_loop(Duration::ZERO))
+        .add_plugin(ScheduleRunnerPlugin)
+        // ManualDuration adds to the wall clock, so step a manual instant instead
+        .insert_resource(TimeUpdateStrategy::ManualInstant(Instant::now()))
+        .add_system(system_step.in_base_set(CoreSet::First).before(TimeSystem))
+        // load the map through the rematch path, then straight into the game
+        .insert_resource(MatchState::loading(headless.map.clone()))
+        .insert_resource(NextState(Some(crate::AppState::Rematch)))
+        .insert_resource(headless);
+}
+
+
+pub fn system_step(mut strategy: ResMut<TimeUpdateStrategy>) {
+    if let TimeUpdateStrategy::ManualInstant(instant) = strategy.as_mut() {
+        *instant += Duration::from_secs_f32(STEP);
+    }
+}
+
+
+// run condition for systems that need a window
+pub fn windowed(headless: Option<Res<Headless>>) -> bool {
+    headless.is_none()
+}
+
+pub fn headless(headless: Option<Res<Headless>>) -> bool {
+    headless.is_some()
+}
+
+
+// nobody is at the keyboard, so every player is played by the AI
+pub fn system_setup(
+    mut economy: ResMut<EconomyConfig>,
+    mut rules: ResMut<MatchRules>,
+) {
+    for player in economy.players.iter_mut() {
+        if player.controller == Controller::Keyboard {
+            player.controller = Controller::Ai;
+        }
+    }
+
+    if ! rules.enabled() {
+        rules.timeout = Some(DEFAULT_TIMEOUT);
+    }
+}
+
+
+pub fn system(
+    state: Res<MatchState>,
+    mut headless: ResMut<Headless>,
+    mut rematch_ew: EventWriter<RequestRematch>,
+    mut exit_ew: EventWriter<AppExit>,
+) {
+    let MatchPhase::Result { winner } = state.phase else { return; };
+
+    let played = headless.results.values().sum::<usize>() + 1;
+    *headless.results.entry(winner).or_insert(0) += 1;
+    match winner {
+        Some(team) => println!("match {}: team {} wins", played, team.0 + 1),
+        None => println!("match {}: draw", played),
+    }
+
+    if played < headless.matches {
+        rematch_ew.send(RequestRematch);
+        return;
+    }
+
+    let mut results: Vec<(&Option<Team>, &usize)> = headless.results.iter().collect();
+    results.sort_by_key(|(winner, _)| winner.map(|t| t.0));
+    for (winner, count) in results {
+        match winner {
+            Some(team) => println!("team {}: {}/{}", team.0 + 1, count, played),
+            None => println!("draw: {}/{}", count, played),
+        }
+    }
+    exit_ew.send(AppExit);
+}
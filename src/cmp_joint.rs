@@ -0,0 +1,91 @@
+use serde::{Serialize, Deserialize};
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use bevy_rapier2d::rapier::dynamics::JointAxis;
+
+use bevy_inspector_egui::bevy_egui::egui;
+
+#[derive(Reflect, FromReflect, Clone, Copy, PartialEq, Serialize, Deserialize, Debug, Default)]
+pub enum JointMotor {
+    #[default]
+    None,
+    Velocity { target_vel: Real, factor: Real },
+    Position { target_pos: Real, stiffness: Real, damping: Real },
+}
+
+pub fn apply_motor(joint: &mut GenericJoint, axis: JointAxis, motor: JointMotor) {
+    match motor {
+        JointMotor::None => {}
+        JointMotor::Velocity { target_vel, factor } => {
+            joint.set_motor_velocity(axis, target_vel, factor);
+        }
+        JointMotor::Position { target_pos, stiffness, damping } => {
+            joint.set_motor_position(axis, target_pos, stiffness, damping);
+        }
+    }
+}
+
+pub fn motor_ui(ui: &mut egui::Ui, motor: &mut JointMotor) {
+    ui.horizontal(|ui: &mut egui::Ui| {
+        ui.label("Motor");
+        if ui.radio(*motor == JointMotor::None, "none").clicked() {
+            *motor = JointMotor::None;
+        }
+        if ui.radio(matches!(motor, JointMotor::Velocity { .. }), "velocity").clicked() {
+            *motor = JointMotor::Velocity { target_vel: 1.0, factor: 1.0 };
+        }
+        if ui.radio(matches!(motor, JointMotor::Position { .. }), "position").clicked() {
+            *motor = JointMotor::Position { target_pos: 0.0, stiffness: 1.0, damping: 0.1 };
+        }
+    });
+
+    match motor {
+        JointMotor::None => {}
+        JointMotor::Velocity { target_vel, factor } => {
+            ui.horizontal(|ui: &mut egui::Ui| {
+                ui.label("target vel");
+                ui.add(egui::DragValue::new(target_vel).speed(0.05));
+                ui.label("factor");
+                ui.add(egui::DragValue::new(factor).speed(0.05));
+            });
+        }
+        JointMotor::Position { target_pos, stiffness, damping } => {
+            ui.horizontal(|ui: &mut egui::Ui| {
+                ui.label("target pos");
+                ui.add(egui::DragValue::new(target_pos).speed(0.05));
+                ui.label("stiffness");
+                ui.add(egui::DragValue::new(stiffness).speed(0.05));
+                ui.label("damping");
+                ui.add(egui::DragValue::new(damping).speed(0.05));
+            });
+        }
+    }
+}
+
+pub fn limits_ui(ui: &mut egui::Ui, enabled: &mut bool, limits: &mut [Real; 2], speed: f64) {
+    ui.horizontal(|ui: &mut egui::Ui| {
+        ui.checkbox(enabled, "Limits");
+        ui.add_enabled(*enabled, egui::DragValue::new(&mut limits[0]).speed(speed));
+        ui.add_enabled(*enabled, egui::DragValue::new(&mut limits[1]).speed(speed));
+    });
+
+    if limits[0] > limits[1] {
+        limits.swap(0, 1);
+    }
+}
+
+
+// Joints refer to entities spawned by other loaders, so they are loaded
+// one frame after the LoadWorldEvent once those entities exist.
+use crate::ev_save_load_world::LoadWorldEvent;
+pub struct DelayLoadJoint(pub String);
+pub fn delay_load(
+    mut load_world_er: EventReader<LoadWorldEvent>,
+    mut load_world_ew: EventWriter<DelayLoadJoint>,
+    )
+{
+    for e in load_world_er.iter() {
+        let dir = e.0.clone();
+        load_world_ew.send(DelayLoadJoint(dir));
+    }
+}
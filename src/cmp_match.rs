@@ -0,0 +1,374 @@
+use serde::{Serialize, Deserialize};
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+use crate::cmp_ball::Ball;
+use crate::cmp_breakable_sync::BreakableSync;
+use crate::cmp_combat_unit::Unit;
+use crate::cmp_debris::Debris;
+use crate::cmp_explosion::Explosion;
+use crate::cmp_game_asset::GameAsset;
+use crate::cmp_team::Team;
+use crate::cmp_team::TeamConfig;
+
+#[derive(Reflect, FromReflect, Default, Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
+pub struct SurviveRule {
+    pub team: Team,
+    pub seconds: f32,
+}
+
+// Victory rules of a map. With every rule off the game runs forever, as before.
+#[derive(Resource, Reflect, Clone, Serialize, Deserialize, Debug)]
+pub struct MatchRules {
+    pub countdown: f32,
+    // a team that owned a BreakableSync core loses when all of them are gone
+    pub destroy_core: bool,
+    // a team that fielded units loses when all of them are dead
+    pub eliminate: bool,
+    // the defending team wins once it has held out this long
+    pub survive: Option<SurviveRule>,
+    // the team with the most units wins when time runs out
+    pub timeout: Option<f32>,
+}
+
+impl Default for MatchRules {
+    fn default() -> Self {
+        Self {
+            countdown: 3.0,
+            destroy_core: false,
+            eliminate: false,
+            survive: None,
+            timeout: None,
+        }
+    }
+}
+
+impl MatchRules {
+    pub fn enabled(&self) -> bool {
+        self.destroy_core || self.eliminate || self.survive.is_some() || self.timeout.is_some()
+    }
+
+    fn time_limit(&self) -> Option<f32> {
+        match (self.survive.map(|s| s.seconds), self.timeout) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MatchPhase {
+    Countdown { remaining: f32 },
+    Playing { elapsed: f32 },
+    // None is a draw
+    Result { winner: Option<Team> },
+    // reloading the map, see system_rematch
+    Rematch { step: u8 },
+}
+
+#[derive(Resource, Debug)]
+pub struct MatchState {
+    pub phase: MatchPhase,
+    pub last_map: Option<String>,
+    core_teams: HashSet<Team>,
+    fielded_teams: HashSet<Team>,
+}
+
+impl Default for MatchState {
+    fn default() -> Self {
+        Self {
+            phase: MatchPhase::Playing { elapsed: 0.0 },
+            last_map: None,
+            core_teams: HashSet::new(),
+            fielded_teams: HashSet::new(),
+        }
+    }
+}
+
+impl MatchState {
+    // loads `map` and starts the game, as a rematch would
+    pub fn loading(map: String) -> Self {
+        Self {
+            phase: MatchPhase::Rematch { step: 0 },
+            last_map: Some(map),
+            ..default()
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct MatchText;
+
+// same as pressing R on the results screen
+pub struct RequestRematch;
+
+
+// run condition for systems that must stand still outside of play
+pub fn playing(state: Res<MatchState>) -> bool {
+    matches!(state.phase, MatchPhase::Playing { .. })
+}
+
+
+pub fn add(
+    mut command: Commands,
+    game_assets: Res<GameAsset>,
+    text_q: Query<Entity, With<MatchText>>,
+){
+    // a rematch enters Game again with the overlay still up
+    if ! text_q.is_empty() { return; }
+
+    let font = game_assets.font_handles.get("font1_handle").unwrap();
+    let message = "";
+
+    command.spawn(TextBundle::from_section(
+        message,
+        TextStyle {
+            font_size: 80.0,
+            color: Color::rgb(0.9, 0.9, 0.9),
+            font: font.clone(),
+            ..default()
+        })
+        .with_text_alignment(TextAlignment::Center)
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Percent(35.0),
+                left: Val::Percent(40.0),
+                ..default()
+            },
+            ..default()
+        }),
+    ).insert(MatchText);
+}
+
+
+pub fn system_setup(
+    rules: Res<MatchRules>,
+    mut state: ResMut<MatchState>,
+) {
+    state.phase = if rules.enabled() {
+        MatchPhase::Countdown { remaining: rules.countdown }
+    } else {
+        MatchPhase::Playing { elapsed: 0.0 }
+    };
+    state.core_teams.clear();
+    state.fielded_teams.clear();
+}
+
+
+fn decide(rules: &MatchRules,
+          team_config: &TeamConfig,
+          state: &MatchState,
+          cores: &HashMap<Team, usize>,
+          units: &HashMap<Team, usize>,
+          elapsed: f32) -> Option<Option<Team>> {
+    let mut participants = HashSet::new();
+    let mut out = HashSet::new();
+
+    if rules.destroy_core {
+        for team in state.core_teams.iter() {
+            participants.insert(*team);
+            if ! cores.contains_key(team) { out.insert(*team); }
+        }
+    }
+
+    if rules.eliminate {
+        for team in state.fielded_teams.iter() {
+            participants.insert(*team);
+            if ! units.contains_key(team) { out.insert(*team); }
+        }
+    }
+
+    let mut remaining: Vec<Team> = participants.difference(&out).cloned().collect();
+    if participants.len() >= 2 && remaining.len() <= 1 {
+        return Some(remaining.pop());
+    }
+
+    if let Some(survive) = rules.survive {
+        if elapsed >= survive.seconds {
+            return Some(Some(survive.team));
+        }
+    }
+
+    if let Some(seconds) = rules.timeout {
+        if elapsed >= seconds {
+            let count = |team: &Team| *units.get(team).unwrap_or(&0);
+            let teams = team_config.teams();
+            let most = teams.iter().map(count).max().unwrap_or(0);
+            let leaders: Vec<Team> = teams.into_iter().filter(|t| count(t) == most).collect();
+
+            return Some(if leaders.len() == 1 { Some(leaders[0]) } else { None });
+        }
+    }
+
+    None
+}
+
+
+pub fn system(
+    mut commands: Commands,
+    time: Res<Time>,
+    keys: Res<Input<KeyCode>>,
+    rules: Res<MatchRules>,
+    team_config: Res<TeamConfig>,
+    mut state: ResMut<MatchState>,
+    mut next_app_state: ResMut<NextState<crate::AppState>>,
+    mut rematch_er: EventReader<RequestRematch>,
+    core_q: Query<&Team, With<BreakableSync>>,
+    unit_q: Query<&Team, (With<Unit>, With<Ball>)>,
+    dynamic_q: Query<Entity, Or<(With<Ball>, With<Explosion>, With<Debris>)>>,
+) {
+    let rematch = rematch_er.iter().count() > 0 || keys.just_pressed(KeyCode::R);
+
+    match state.phase {
+        MatchPhase::Countdown { remaining } => {
+            let remaining = remaining - time.delta_seconds();
+            state.phase = if remaining > 0.0 {
+                MatchPhase::Countdown { remaining }
+            } else {
+                MatchPhase::Playing { elapsed: 0.0 }
+            };
+        }
+
+        MatchPhase::Playing { elapsed } => {
+            let elapsed = elapsed + time.delta_seconds();
+            state.phase = MatchPhase::Playing { elapsed };
+            if ! rules.enabled() { return; }
+
+            let mut cores = HashMap::new();
+            for team in core_q.iter() {
+                *cores.entry(*team).or_insert(0) += 1;
+            }
+            let mut units = HashMap::new();
+            for team in unit_q.iter() {
+                *units.entry(*team).or_insert(0) += 1;
+            }
+            state.core_teams.extend(cores.keys());
+            state.fielded_teams.extend(units.keys());
+
+            if let Some(winner) = decide(&rules, &team_config, &state, &cores, &units, elapsed) {
+                state.phase = MatchPhase::Result { winner };
+            }
+        }
+
+        MatchPhase::Result { .. } => {
+            if rematch {
+                for entity in dynamic_q.iter() {
+                    commands.entity(entity).despawn();
+                }
+                // leave Game so its OnEnter setups run again on the reloaded map
+                next_app_state.set(crate::AppState::Rematch);
+                state.phase = MatchPhase::Rematch { step: 0 };
+            }
+        }
+
+        MatchPhase::Rematch { .. } => {}
+    }
+}
+
+
+use crate::ev_save_load_world::LoadWorldEvent;
+// Reload the last map once the old balls are gone, give the loaders
+// a frame to apply their commands and then start the game again.
+pub fn system_rematch(
+    mut state: ResMut<MatchState>,
+    mut load_world_ew: EventWriter<LoadWorldEvent>,
+    mut next_app_state: ResMut<NextState<crate::AppState>>,
+) {
+    if let MatchPhase::Rematch { step } = state.phase {
+        match step {
+            1 => {
+                if let Some(dir) = state.last_map.clone() {
+                    load_world_ew.send(LoadWorldEvent(dir));
+                }
+            }
+            3 => {
+                next_app_state.set(crate::AppState::Game);
+            }
+            _ => {}
+        }
+        state.phase = MatchPhase::Rematch { step: step + 1 };
+    }
+}
+
+
+pub fn system_pause(
+    state: Res<MatchState>,
+    mut rapier_configuration: ResMut<RapierConfiguration>,
+) {
+    let active = matches!(state.phase, MatchPhase::Playing { .. });
+    if rapier_configuration.physics_pipeline_active != active {
+        rapier_configuration.physics_pipeline_active = active;
+    }
+}
+
+
+pub fn system_text(
+    rules: Res<MatchRules>,
+    state: Res<MatchState>,
+    mut text_q: Query<&mut Text, With<MatchText>>,
+) {
+    let Ok(mut text) = text_q.get_single_mut() else { return; };
+
+    let (message, color) = match state.phase {
+        MatchPhase::Countdown { remaining } => {
+            (format!("{}", remaining.ceil() as i32), Color::rgb(0.9, 0.9, 0.9))
+        }
+        MatchPhase::Playing { elapsed } => {
+            if let Some(limit) = rules.time_limit() {
+                (format!("{}", (limit - elapsed).max(0.0).ceil() as i32), Color::rgb(0.9, 0.9, 0.9).with_a(0.5))
+            } else {
+                (String::new(), Color::WHITE)
+            }
+        }
+        MatchPhase::Result { winner: Some(team) } => {
+            (format!("Team {} wins\nR: rematch", team.0 + 1), team.color())
+        }
+        MatchPhase::Result { winner: None } => {
+            (String::from("Draw\nR: rematch"), Color::rgb(0.9, 0.9, 0.9))
+        }
+        MatchPhase::Rematch { .. } => {
+            (String::new(), Color::WHITE)
+        }
+    };
+
+    if text.sections[0].value != message {
+        text.sections[0].value = message;
+        text.sections[0].style.color = color;
+    }
+}
+
+
+const FILE_NAME: &str = "/match_rules.map";
+pub fn load(
+    mut load_world_er: EventReader<LoadWorldEvent>,
+    mut rules: ResMut<MatchRules>,
+    mut state: ResMut<MatchState>,
+    ) {
+
+    for e in load_world_er.iter() {
+        let dir = e.0.clone();
+        state.last_map = Some(dir.clone());
+
+        let json_str = std::fs::read_to_string(dir + FILE_NAME);
+        if let Ok(json_str) = json_str {
+            *rules = serde_json::from_str(&json_str).unwrap();
+        } else {
+            *rules = MatchRules::default();
+        }
+    }
+}
+
+
+use crate::ev_save_load_world::SaveWorldEvent;
+pub fn save(mut save_world_er: EventReader<SaveWorldEvent>,
+              rules: Res<MatchRules>,
+              ) {
+
+    for e in save_world_er.iter() {
+        let dir = e.0.clone();
+
+        std::fs::write(dir + FILE_NAME, serde_json::to_string(&*rules).unwrap()).unwrap();
+    }
+}
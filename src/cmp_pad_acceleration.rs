@@ -5,6 +5,7 @@ use bevy_rapier2d::prelude::*;
 use crate::cmp_bbsize::BBSize;
 use crate::cmp_game_asset::GameAsset;
 use crate::cmp_ball::Ball;
+use crate::contacts;
 
 #[derive(Component, Reflect, Clone, Serialize, Deserialize, Debug)]
 pub struct PadAcceleration {
@@ -57,8 +58,8 @@ pub fn system(
     pa_q: Query<(Entity, &PadAcceleration)>,
 ) {
     for (pa_e, pa) in pa_q.iter() {
-        for (ball_e, mut ball_v) in ball_q.iter_mut() {
-            if rapier_context.intersection_pair(pa_e, ball_e) == Some(true) {
+        for other in contacts::intersecting(&rapier_context, pa_e) {
+            if let Ok((_, mut ball_v)) = ball_q.get_mut(other) {
                 ball_v.linvel += pa.speed_delta * pa.direction;
             }
         }
@@ -0,0 +1,347 @@
+use bevy::prelude::*;
+use bevy::sprite::Mesh2dHandle;
+use bevy_rapier2d::prelude::*;
+use rand::prelude::*;
+
+use crate::cmp_explosion::Explosion;
+use crate::cmp_explosion::Lingering;
+use crate::cmp_polygonal_shape::PolygonalShape;
+use crate::quad_mesh::QuadMesh;
+
+// live particles at most, bursts beyond it are dropped
+const MAX_PARTICLES: usize = 8192;
+const TAU: f32 = std::f32::consts::TAU;
+
+
+// Cosmetic particles without any Rapier body: they never push balls
+// around and cost a few floats each. All of them live in the Particles
+// pool and are drawn as one mesh.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Preset {
+    Blood,
+    Smoke,
+    Spark,
+    Confetti,
+}
+
+pub struct Style {
+    // seconds, picked between the two
+    pub life: (f32, f32),
+    pub speed: (f32, f32),
+    // cone around straight up, TAU for every direction
+    pub spread: f32,
+    // times the world gravity, negative rises
+    pub gravity: f32,
+    pub drag: f32,
+    // keyframes over the life time, 0.0 to 1.0
+    pub color: &'static [(f32, [f32; 4])],
+    pub size: &'static [(f32, [f32; 1])],
+    // one is picked per particle and multiplies `color`
+    pub tints: &'static [[f32; 3]],
+    // bounce off the map polylines keeping this much speed, 0.0 sticks.
+    // None flies through
+    pub ground: Option<f32>,
+}
+
+const BLOOD: Style = Style {
+    life: (2.5, 3.0),
+    speed: (0.0, 1000.0),
+    spread: TAU,
+    gravity: 1.0,
+    drag: 0.0,
+    color: &[(0.0, [1.0, 0.0, 0.0, 1.0]), (0.8, [0.7, 0.0, 0.0, 1.0]), (1.0, [0.7, 0.0, 0.0, 0.0])],
+    size: &[(0.0, [4.0])],
+    tints: &[[1.0, 1.0, 1.0]],
+    ground: Some(0.0),
+};
+
+const SMOKE: Style = Style {
+    life: (1.0, 2.0),
+    speed: (20.0, 120.0),
+    spread: TAU,
+    gravity: -0.05,
+    drag: 1.5,
+    color: &[(0.0, [0.5, 0.5, 0.5, 0.0]), (0.1, [0.5, 0.5, 0.5, 0.5]), (1.0, [0.3, 0.3, 0.3, 0.0])],
+    size: &[(0.0, [10.0]), (1.0, [40.0])],
+    tints: &[[1.0, 1.0, 1.0]],
+    ground: None,
+};
+
+const SPARK: Style = Style {
+    life: (0.2, 0.5),
+    speed: (200.0, 600.0),
+    spread: TAU,
+    gravity: 1.0,
+    drag: 0.5,
+    color: &[(0.0, [1.0, 1.0, 0.6, 1.0]), (0.5, [1.0, 0.6, 0.1, 1.0]), (1.0, [1.0, 0.3, 0.0, 0.0])],
+    size: &[(0.0, [3.0]), (1.0, [1.0])],
+    tints: &[[1.0, 1.0, 1.0]],
+    ground: Some(0.5),
+};
+
+const CONFETTI: Style = Style {
+    life: (1.5, 2.5),
+    speed: (200.0, 500.0),
+    spread: 1.0,
+    gravity: 0.3,
+    drag: 1.0,
+    color: &[(0.0, [1.0, 1.0, 1.0, 1.0]), (0.8, [1.0, 1.0, 1.0, 1.0]), (1.0, [1.0, 1.0, 1.0, 0.0])],
+    size: &[(0.0, [5.0])],
+    tints: &[[1.0, 0.2, 0.2], [0.2, 0.8, 0.2], [0.2, 0.4, 1.0], [1.0, 0.85, 0.1], [0.9, 0.3, 0.9]],
+    ground: Some(0.3),
+};
+
+impl Preset {
+    pub fn style(&self) -> &'static Style {
+        match self {
+            Preset::Blood => &BLOOD,
+            Preset::Smoke => &SMOKE,
+            Preset::Spark => &SPARK,
+            Preset::Confetti => &CONFETTI,
+        }
+    }
+}
+
+// linear between the keyframes, held flat past the ends
+fn sample<const N: usize>(curve: &[(f32, [f32; N])], t: f32) -> [f32; N] {
+    let next = curve.iter().position(|(key, _)| *key > t).unwrap_or(curve.len());
+    if next == 0 { return curve[0].1; }
+    if next == curve.len() { return curve[next - 1].1; }
+
+    let (t0, v0) = curve[next - 1];
+    let (t1, v1) = curve[next];
+    let k = (t - t0) / (t1 - t0);
+    let mut v = v0;
+    for i in 0..N {
+        v[i] = v0[i] + (v1[i] - v0[i]) * k;
+    }
+    v
+}
+
+
+#[derive(Clone, Copy)]
+struct Particle {
+    alive: bool,
+    preset: Preset,
+    position: Vec2,
+    velocity: Vec2,
+    age: f32,
+    life: f32,
+    tint: [f32; 3],
+    // stuck on the ground
+    resting: bool,
+}
+
+// Slots are reused through `free` so a steady stream of bursts doesn't
+// allocate once the pool has grown.
+#[derive(Resource, Default)]
+pub struct Particles {
+    pool: Vec<Particle>,
+    free: Vec<usize>,
+}
+
+impl Particles {
+    pub fn burst(&mut self, preset: Preset, position: Vec2, count: usize) {
+        let style = preset.style();
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..count {
+            let angle = rng.gen_range(-0.5..=0.5) * style.spread;
+            let speed = rng.gen_range(style.speed.0..=style.speed.1);
+            let particle = Particle {
+                alive: true,
+                preset,
+                position,
+                velocity: Vec2::from_angle(angle).rotate(Vec2::Y) * speed,
+                age: 0.0,
+                life: rng.gen_range(style.life.0..=style.life.1),
+                tint: style.tints[rng.gen_range(0..style.tints.len())],
+                resting: false,
+            };
+
+            if let Some(i) = self.free.pop() {
+                self.pool[i] = particle;
+            } else if self.pool.len() < MAX_PARTICLES {
+                self.pool.push(particle);
+            } else {
+                return;
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.pool.clear();
+        self.free.clear();
+    }
+}
+
+// bursts from anywhere that has Commands, e.g. cmp_ball::kill
+pub fn burst(commands: &mut Commands, preset: Preset, position: Vec2, count: usize) {
+    commands.add(move |world: &mut World| {
+        world.resource_mut::<Particles>().burst(preset, position, count);
+    });
+}
+
+
+// keeps emitting for as long as its entity lives
+#[derive(Component)]
+pub struct Emitter {
+    pub preset: Preset,
+    // particles a second
+    pub rate: f32,
+    pub carry: f32,
+}
+
+impl Emitter {
+    pub fn new(preset: Preset, rate: f32) -> Self {
+        Self { preset, rate, carry: 0.0 }
+    }
+}
+
+pub fn system_emit(
+    time: Res<Time>,
+    mut particles: ResMut<Particles>,
+    mut emitter_q: Query<(&GlobalTransform, &mut Emitter)>,
+) {
+    for (t, mut emitter) in emitter_q.iter_mut() {
+        emitter.carry += emitter.rate * time.delta_seconds();
+        let count = emitter.carry as usize;
+        emitter.carry -= count as f32;
+        particles.burst(emitter.preset, t.translation().truncate(), count);
+    }
+}
+
+
+// smoke and sparks for new explosions, lingering ones keep smoking
+pub fn system_explosion(
+    mut commands: Commands,
+    mut particles: ResMut<Particles>,
+    explosion_q: Query<(Entity, &Transform, &Explosion, Option<&Lingering>), Added<Explosion>>,
+) {
+    for (entity, t, explosion, lingering) in explosion_q.iter() {
+        let position = t.translation.truncate();
+        let count = (explosion.radius / 10.0) as usize;
+
+        if lingering.is_some() {
+            commands.entity(entity).insert(Emitter::new(Preset::Smoke, count as f32 * 2.0));
+        } else {
+            particles.burst(Preset::Smoke, position, count);
+            particles.burst(Preset::Spark, position, count * 2);
+        }
+    }
+}
+
+
+// where p -> q first crosses a..b, and the normal on p's side
+fn crossing(p: Vec2, q: Vec2, a: Vec2, b: Vec2) -> Option<(Vec2, Vec2)> {
+    let d = q - p;
+    let e = b - a;
+    let denominator = d.perp_dot(e);
+    if denominator.abs() < 1e-6 { return None; }
+
+    let t = (a - p).perp_dot(e) / denominator;
+    let u = (a - p).perp_dot(d) / denominator;
+    if !(0.0..=1.0).contains(&t) || !(0.0..=1.0).contains(&u) { return None; }
+
+    let mut normal = e.perp().normalize();
+    if normal.dot(d) > 0.0 { normal = -normal; }
+    Some((p + d * t, normal))
+}
+
+pub fn system(
+    time: Res<Time>,
+    rapier_configuration: Res<RapierConfiguration>,
+    mut particles: ResMut<Particles>,
+    shape_q: Query<(&Transform, &PolygonalShape)>,
+) {
+    let dt = time.delta_seconds();
+    if dt == 0.0 { return; }
+
+    // ground segments in world space, with their bounds for a quick reject
+    let mut segments = vec![];
+    for (t, shape) in shape_q.iter() {
+        let points: Vec<Vec2> = shape.polygon.iter().map(|p| t.transform_point(p.extend(0.0)).truncate()).collect();
+        for pair in points.windows(2) {
+            segments.push((pair[0], pair[1], pair[0].min(pair[1]), pair[0].max(pair[1])));
+        }
+    }
+
+    let Particles { pool, free } = &mut *particles;
+    for (i, particle) in pool.iter_mut().enumerate() {
+        if ! particle.alive { continue; }
+
+        particle.age += dt;
+        if particle.age >= particle.life {
+            particle.alive = false;
+            free.push(i);
+            continue;
+        }
+        if particle.resting { continue; }
+
+        let style = particle.preset.style();
+        particle.velocity += rapier_configuration.gravity * style.gravity * dt;
+        particle.velocity *= 1.0 / (1.0 + style.drag * dt);
+
+        let from = particle.position;
+        let to = from + particle.velocity * dt;
+        particle.position = to;
+
+        let Some(bounce) = style.ground else { continue; };
+        let (min, max) = (from.min(to), from.max(to));
+        for (a, b, seg_min, seg_max) in segments.iter() {
+            if max.cmplt(*seg_min).any() || min.cmpgt(*seg_max).any() { continue; }
+            let Some((hit, normal)) = crossing(from, to, *a, *b) else { continue; };
+
+            particle.position = hit + normal * 0.5;
+            if bounce == 0.0 {
+                particle.resting = true;
+            } else {
+                let v = particle.velocity;
+                particle.velocity = (v - 2.0 * v.dot(normal) * normal) * bounce;
+            }
+            break;
+        }
+    }
+}
+
+
+// every live particle as a quad of one mesh
+#[derive(Component)]
+pub struct ParticleMesh;
+
+pub fn system_mesh(
+    mut commands: Commands,
+    particles: Res<Particles>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    particle_mesh_q: Query<&Mesh2dHandle, With<ParticleMesh>>,
+) {
+    let mut quads = QuadMesh::default();
+
+    for particle in particles.pool.iter().filter(|p| p.alive) {
+        let style = particle.preset.style();
+        let t = particle.age / particle.life;
+        let [size] = sample(style.size, t).map(|s| s / 2.0);
+        let [r, g, b, a] = sample(style.color, t);
+        let [tr, tg, tb] = particle.tint;
+        let p = particle.position;
+        let color = [r * tr, g * tg, b * tb, a];
+
+        quads.quad([(p + Vec2::new(-size, -size), color), (p + Vec2::new(size, -size), color),
+                    (p + Vec2::new(size, size), color), (p + Vec2::new(-size, size), color)]);
+    }
+
+    quads.apply(&mut commands, &mut meshes, &mut materials, particle_mesh_q.get_single().ok(), 1.5, ParticleMesh);
+}
+
+
+pub fn system_cleanup(
+    mut commands: Commands,
+    mut particles: ResMut<Particles>,
+    particle_mesh_q: Query<Entity, With<ParticleMesh>>,
+) {
+    particles.clear();
+    for entity in particle_mesh_q.iter() {
+        commands.entity(entity).despawn();
+    }
+}
@@ -7,6 +7,9 @@ use crate::edit_context::*;
 
 use crate::BBSize;
 use crate::constants;
+use crate::cmp_combat::CombatConfig;
+use crate::cmp_explosion::Explosion;
+use crate::cmp_explosion::Lingering;
 use crate::ev_save_load_world::Derrived;
 
 #[derive(Component, Reflect, Clone, Serialize, Deserialize, Debug)]
@@ -143,6 +146,125 @@ impl From<(Vec3, Quat, Vec3, PolygonalShape)> for PolygonalShapeBundle {
 }
 
 
+// craters are this much of the blast radius
+const CARVE_RATIO: f32 = 0.5;
+const CARVE_ARC_STEP: f32 = std::f32::consts::PI / 12.0;
+
+// parameters along a->b where the segment crosses the circle
+fn circle_crossings(a: Vec2, b: Vec2, center: Vec2, radius: f32) -> Vec<f32> {
+    let d = b - a;
+    let f = a - center;
+    let qa = d.dot(d);
+    let qb = 2.0 * f.dot(d);
+    let qc = f.dot(f) - radius * radius;
+    let discriminant = qb * qb - 4.0 * qa * qc;
+    if qa == 0.0 || discriminant <= 0.0 { return vec![]; }
+
+    let root = discriminant.sqrt();
+    [(-qb - root) / (2.0 * qa), (-qb + root) / (2.0 * qa)]
+        .into_iter()
+        .filter(|t| *t > 0.0 && *t <= 1.0)
+        .collect()
+}
+
+// twice the signed area, > 0 when the points go counterclockwise
+fn winding(polyline: &Vec<Vec2>) -> f32 {
+    polyline.iter()
+        .zip(polyline.iter().cycle().skip(1))
+        .map(|(a, b)| a.perp_dot(*b))
+        .sum()
+}
+
+// Points strictly between `from` and `to` round the circle, on the `left`
+// side of from->to. The shorter way bumps out of the shape whenever the
+// blast centre is on the solid side of the cut.
+fn crater_arc(from: Vec2, to: Vec2, center: Vec2, radius: f32, left: bool) -> Vec<Vec2> {
+    let start = Vec2::new(1.0, 0.0).angle_between(from - center);
+    let mut sweep = (from - center).angle_between(to - center);
+    let middle = start + sweep / 2.0;
+    let on_left = (to - from).perp_dot(Vec2::new(middle.cos(), middle.sin()) * radius + center - from) > 0.0;
+    if on_left != left {
+        sweep = sweep - sweep.signum() * std::f32::consts::TAU;
+    }
+    let steps = (sweep.abs() / CARVE_ARC_STEP).ceil() as usize;
+
+    (1..steps)
+        .map(|i| start + sweep * i as f32 / steps as f32)
+        .map(|a| center + Vec2::new(a.cos(), a.sin()) * radius)
+        .collect()
+}
+
+// Replace every part of the polyline inside the circle with the crater
+// rim. None if the circle misses the polyline.
+pub fn carve(polyline: &Vec<Vec2>, center: Vec2, radius: f32) -> Option<Vec<Vec2>> {
+    if polyline.len() < 2 { return None; }
+
+    // the crater goes into the solid, which is left of the outline going counterclockwise
+    let left = winding(polyline) > 0.0;
+    let mut carved = vec![];
+    let mut changed = false;
+    let mut inside = polyline[0].distance(center) < radius;
+    let mut entry: Option<Vec2> = None;
+    if inside { changed = true; } else { carved.push(polyline[0]); }
+
+    for segment in polyline.windows(2) {
+        let (a, b) = (segment[0], segment[1]);
+        for t in circle_crossings(a, b, center, radius) {
+            let point = a + (b - a) * t;
+            changed = true;
+            if inside {
+                if let Some(entry) = entry {
+                    carved.extend(crater_arc(entry, point, center, radius, left));
+                }
+                carved.push(point);
+                entry = None;
+            } else {
+                carved.push(point);
+                entry = Some(point);
+            }
+            inside = ! inside;
+        }
+
+        if ! inside { carved.push(b); }
+    }
+
+    if changed { Some(carved) } else { None }
+}
+
+
+pub fn system_carve(
+    mut commands: Commands,
+    config: Res<CombatConfig>,
+    explosion_q: Query<(&Explosion, &Transform), (Added<Explosion>, Without<Lingering>)>,
+    mut shape_q: Query<(Entity, &Transform, &mut PolygonalShape), Without<Explosion>>,
+) {
+    if ! config.carve_terrain { return; }
+
+    for (explosion, explosion_t) in explosion_q.iter() {
+        for (entity, transform, mut ps) in shape_q.iter_mut() {
+            // polygons are stored unscaled around the shape's origin
+            let local = transform.rotation.inverse() * (explosion_t.translation - transform.translation) / transform.scale.x;
+            let radius = explosion.radius * CARVE_RATIO / transform.scale.x;
+
+            let Some(polyline) = carve(&ps.polygon, local.truncate(), radius) else { continue; };
+            if polyline.len() < 2 {
+                commands.entity(entity).despawn();
+                continue;
+            }
+
+            let bbsize = bounding_box(&polyline);
+            let polygon = shapes::Polygon {points: polyline.clone(), closed: false};
+            commands.entity(entity).insert((
+                    Collider::polyline(polyline.clone(), None),
+                    GeometryBuilder::build_as(&polygon),
+                    BBSize { x: bbsize.x * transform.scale.x, y: bbsize.y * transform.scale.y },
+                    ));
+            ps.polygon = polyline;
+        }
+    }
+}
+
+
 const FILE_NAME: &str = "/polygonal_shape.map";
 use crate::ev_save_load_world::LoadWorldEvent;
 pub fn load(
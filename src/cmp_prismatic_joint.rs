@@ -0,0 +1,191 @@
+use serde::{Serialize, Deserialize};
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use bevy_rapier2d::rapier::dynamics::JointAxis;
+
+use bevy_inspector_egui::bevy_egui::{egui, EguiContexts};
+
+use crate::cmp_joint;
+use crate::cmp_joint::JointMotor;
+use crate::edit_context::*;
+use crate::ev_save_load_world;
+
+const DEFAULT_LIMIT: Real = 100.0;
+
+#[derive(Component, Reflect, Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct PrismaticJoint {
+    pub child_entity: Entity,
+    pub translation: Vec3,
+    pub rotation: Quat,
+    // slide direction in the local frame of the child body
+    pub axis: Vec2,
+    pub limits_enabled: bool,
+    pub limits: [Real; 2],
+    pub motor: JointMotor,
+    // entity index of the base body, so a reload reuses it
+    #[serde(default)]
+    pub base: Option<u32>,
+}
+
+#[derive(Component, Reflect, Clone, Serialize, Deserialize, Debug)]
+pub struct PrismaticJointBase;
+
+pub fn handle_user_input(
+    mut commands: Commands,
+    buttons: Res<Input<MouseButton>>,
+    mut edit_context: ResMut<EditContext>,
+    world_position: Res<WorldPosition>,
+    mut transform_q: Query<(Entity, &mut Transform, &mut RigidBody)>,
+    ) {
+    if ! buttons.just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    if let EditContext::Spawn(map_object) = edit_context.clone() {
+        if let MapObject::PrismaticJoint(entities) = map_object {
+            for entity in entities.clone() {
+                let (entity, transform, mut rigid_body) = transform_q.get_mut(entity).unwrap();
+                let dir = world_position.translation - transform.translation.truncate();
+                let dir = if dir.length() > 0.0 { dir.normalize() } else { Vec2::X };
+                let axis = transform.rotation.inverse().mul_vec3(Vec3::from((dir, 0.0))).truncate();
+
+                let entity = add(&mut commands,
+                                 &mut rigid_body,
+                                 PrismaticJoint { child_entity: entity,
+                                                  translation: transform.translation,
+                                                  rotation: transform.rotation,
+                                                  axis,
+                                                  limits_enabled: true,
+                                                  limits: [-DEFAULT_LIMIT, DEFAULT_LIMIT],
+                                                  motor: JointMotor::None,
+                                                  base: None });
+
+                commands.entity(entity).insert(MapObject::PrismaticJoint(vec![]));
+            }
+
+            *edit_context = EditContext::Edit(MapObject::PrismaticJoint(vec![]), entities, EditTool::Select);
+        }
+    }
+}
+
+
+pub fn edit_window(
+    mut egui_contexts: EguiContexts,
+    edit_context: Res<EditContext>,
+    mut pj_q: Query<&mut PrismaticJoint>,
+    ) {
+    if let EditContext::Edit(MapObject::PrismaticJoint(_), pick, _) = edit_context.clone() {
+        if pick.len() == 0 { return; }
+
+        if let Ok(mut prismatic_joint) = pj_q.get_mut(pick[0]) {
+            let mut pj = prismatic_joint.clone();
+
+            egui::Window::new("PrismaticJoint").show(egui_contexts.ctx_mut(), |ui: &mut egui::Ui| {
+                cmp_joint::limits_ui(ui, &mut pj.limits_enabled, &mut pj.limits, 1.0);
+                cmp_joint::motor_ui(ui, &mut pj.motor);
+            });
+
+            if pj != *prismatic_joint {
+                *prismatic_joint = pj;
+            }
+        }
+    }
+}
+
+
+pub fn system(
+    mut pj_q: Query<(&mut ImpulseJoint, &PrismaticJoint), Changed<PrismaticJoint>>,
+) {
+    for (mut ij, pj) in pj_q.iter_mut() {
+        ij.data = joint_data(pj);
+    }
+}
+
+
+fn joint_data(prismatic_joint: &PrismaticJoint) -> GenericJoint {
+    let mut joint: GenericJoint = PrismaticJointBuilder::new(prismatic_joint.axis)
+        .local_anchor1(Vec2::new(0.0, 0.0))
+        .local_anchor2(Vec2::new(0.0, 0.0))
+        .into();
+
+    if prismatic_joint.limits_enabled {
+        joint.set_limits(JointAxis::X, prismatic_joint.limits);
+    }
+    cmp_joint::apply_motor(&mut joint, JointAxis::X, prismatic_joint.motor);
+
+    joint
+}
+
+
+fn add(commands: &mut Commands, rigid_body: &mut RigidBody, prismatic_joint: PrismaticJoint) -> Entity {
+    let joint = joint_data(&prismatic_joint);
+
+    let base_entity = ev_save_load_world::get_or_spawn(commands, prismatic_joint.base)
+        .insert(RigidBody::Dynamic)
+        .insert(TransformBundle {
+            local: Transform {
+                translation: prismatic_joint.translation,
+                rotation: prismatic_joint.rotation,
+                ..Default::default()
+            },
+            ..default()
+        })
+        .insert(PrismaticJointBase)
+    .id();
+
+    let mut entity = commands.get_entity(prismatic_joint.child_entity).unwrap();
+    entity
+        .insert(ImpulseJoint::new(base_entity, joint))
+        .insert(prismatic_joint);
+
+    *rigid_body = RigidBody::Dynamic;
+
+    return entity.id();
+}
+
+
+use crate::cmp_joint::DelayLoadJoint;
+const FILE_NAME: &str = "/prismatic_joint.map";
+pub fn load(
+    mut load_world_er: EventReader<DelayLoadJoint>,
+    mut commands: Commands,
+    mut q: Query<&mut RigidBody>,
+    )
+{
+    for e in load_world_er.iter() {
+        let dir = e.0.clone();
+
+        let filename = dir + FILE_NAME;
+        let json_str = std::fs::read_to_string(filename);
+        if let Ok(json_str) = json_str {
+            let elem_list: Vec<(u32, PrismaticJoint)> = serde_json::from_str(&json_str).unwrap();
+
+            for (id, v) in elem_list {
+                let entity = commands.get_or_spawn(Entity::from_raw(id)).id();
+                let mut rigid_body = q.get_mut(entity).unwrap();
+
+                add(&mut commands,
+                    &mut rigid_body,
+                    PrismaticJoint { child_entity: entity, ..v });
+                commands.entity(entity).insert(MapObject::PrismaticJoint(vec![]));
+            }
+        }
+    }
+}
+
+use crate::ev_save_load_world::SaveWorldEvent;
+pub fn save(mut save_world_er: EventReader<SaveWorldEvent>,
+              q: Query<(Entity, &PrismaticJoint, &ImpulseJoint)>
+              ) {
+    for e in save_world_er.iter() {
+        let dir = e.0.clone();
+        let mut elem_list: Vec<(u32, PrismaticJoint)> = vec![];
+
+        for (e, pj, ij) in q.iter() {
+            elem_list.push((e.index(), PrismaticJoint { base: Some(ij.parent.index()), ..pj.clone() }));
+        }
+
+        let filename = dir + FILE_NAME;
+        std::fs::write(filename, serde_json::to_string(&elem_list).unwrap()).unwrap();
+    }
+}
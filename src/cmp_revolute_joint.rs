@@ -1,14 +1,33 @@
 use serde::{Serialize, Deserialize};
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
+use bevy_rapier2d::rapier::dynamics::JointAxis;
 
+use bevy_inspector_egui::bevy_egui::{egui, EguiContexts};
+
+use crate::cmp_joint;
+use crate::cmp_joint::JointMotor;
 use crate::edit_context::*;
+use crate::ev_save_load_world;
 
-#[derive(Component, Reflect, Clone, Serialize, Deserialize, Debug)]
+const DEFAULT_LIMIT: Real = 0.25 * std::f32::consts::PI;
+
+#[derive(Component, Reflect, Clone, PartialEq, Serialize, Deserialize, Debug)]
 pub struct RevoluteJoint {
     pub child_entity: Entity,
+    // world position of the pivot
     pub translation: Vec3,
     pub limits: [Real; 2],
+    // pivot in the local frame of the child body
+    #[serde(default)]
+    pub anchor: Vec2,
+    #[serde(default)]
+    pub limits_enabled: bool,
+    #[serde(default)]
+    pub motor: JointMotor,
+    // entity index of the base body, so a reload reuses it
+    #[serde(default)]
+    pub base: Option<u32>,
 }
 
 #[derive(Component, Reflect, Clone, Serialize, Deserialize, Debug)]
@@ -18,16 +37,32 @@ pub fn handle_user_input(
     mut commands: Commands,
     buttons: Res<Input<MouseButton>>,
     mut edit_context: ResMut<EditContext>,
+    world_position: Res<WorldPosition>,
     mut transform_q: Query<(Entity, &mut Transform, &mut RigidBody)>,
     ) {
+    if ! buttons.just_pressed(MouseButton::Left) {
+        return;
+    }
+
     if let EditContext::Spawn(map_object) = edit_context.clone() {
         if let MapObject::RevoluteJoint(entities) = map_object {
+            let pivot = world_position.translation;
+
             for entity in entities.clone() {
                 let (entity, transform, mut rigid_body) = transform_q.get_mut(entity).unwrap();
+                let anchor = transform.rotation.inverse()
+                                .mul_vec3(Vec3::from((pivot, 0.0)) - transform.translation)
+                                .truncate();
 
                 let entity = add(&mut commands,
                                  &mut rigid_body,
-                                 RevoluteJoint { child_entity: entity, translation: transform.translation, limits: [0.0, 0.0] });
+                                 RevoluteJoint { child_entity: entity,
+                                                 translation: Vec3::from((pivot, transform.translation.z)),
+                                                 limits: [-DEFAULT_LIMIT, DEFAULT_LIMIT],
+                                                 anchor,
+                                                 limits_enabled: false,
+                                                 motor: JointMotor::None,
+                                                 base: None });
 
                 commands.entity(entity).insert(MapObject::RevoluteJoint(vec![]));
             }
@@ -38,31 +73,59 @@ pub fn handle_user_input(
 }
 
 
+pub fn edit_window(
+    mut egui_contexts: EguiContexts,
+    edit_context: Res<EditContext>,
+    mut rj_q: Query<&mut RevoluteJoint>,
+    ) {
+    if let EditContext::Edit(MapObject::RevoluteJoint(_), pick, _) = edit_context.clone() {
+        if pick.len() == 0 { return; }
+
+        if let Ok(mut revolute_joint) = rj_q.get_mut(pick[0]) {
+            let mut rj = revolute_joint.clone();
+
+            egui::Window::new("RevoluteJoint").show(egui_contexts.ctx_mut(), |ui: &mut egui::Ui| {
+                cmp_joint::limits_ui(ui, &mut rj.limits_enabled, &mut rj.limits, 0.01);
+                cmp_joint::motor_ui(ui, &mut rj.motor);
+            });
+
+            if rj != *revolute_joint {
+                *revolute_joint = rj;
+            }
+        }
+    }
+}
+
+
 pub fn system(
-    mut rj_q: Query<(&mut ImpulseJoint, &mut RevoluteJoint)>,
-    mut rjb_q: Query<&mut Transform, (With<RevoluteJointBase>, Without<RevoluteJoint>)>,
+    mut rj_q: Query<(&mut ImpulseJoint, &RevoluteJoint), Changed<RevoluteJoint>>,
 ) {
     for (mut ij, rj) in rj_q.iter_mut() {
+        ij.data = joint_data(rj);
     }
+}
+
+
+fn joint_data(revolute_joint: &RevoluteJoint) -> GenericJoint {
+    let mut joint: GenericJoint = RevoluteJointBuilder::new()
+        .local_anchor1(Vec2::new(0.0, 0.0))
+        .local_anchor2(revolute_joint.anchor)
+        .into();
 
-    //for (t, mut ij, rj) in rj_q.iter_mut() {
-    //    let mut parent_t = rjb_q.get_mut(ij.parent).unwrap();
+    if revolute_joint.limits_enabled {
+        joint.set_limits(JointAxis::AngX, revolute_joint.limits);
+    }
+    cmp_joint::apply_motor(&mut joint, JointAxis::AngX, revolute_joint.motor);
 
-    //    println!("{:?}", parent_t);
-    //    println!("{:?}", t);
-    //    if *parent_t != *t {
-    //        *parent_t = *t;
-    //    }
-    //}
+    joint
 }
 
 
 fn add(commands: &mut Commands, rigid_body: &mut RigidBody, revolute_joint: RevoluteJoint) -> Entity {
-    let joint = RevoluteJointBuilder::new()
-        .local_anchor1(Vec2::new(0.0, 0.0))
-        .local_anchor2(Vec2::new(0.0, 0.0));
+    let joint = joint_data(&revolute_joint);
 
-    let base_entity = commands.spawn(RigidBody::Dynamic)
+    let base_entity = ev_save_load_world::get_or_spawn(commands, revolute_joint.base)
+        .insert(RigidBody::Dynamic)
         .insert(TransformBundle {
             local: Transform {
                 translation: revolute_joint.translation,
@@ -84,22 +147,10 @@ fn add(commands: &mut Commands, rigid_body: &mut RigidBody, revolute_joint: Revo
 }
 
 
-use crate::ev_save_load_world::LoadWorldEvent;
+use crate::cmp_joint::DelayLoadJoint;
 const FILE_NAME: &str = "/revolute_joint.map";
-pub struct DelayLoadRevoluteJoint(pub String);
-pub fn delay_load(
-    mut load_world_er: EventReader<LoadWorldEvent>,
-    mut load_world_ew: EventWriter<DelayLoadRevoluteJoint>,
-    )
-{
-    for e in load_world_er.iter() {
-        let dir = e.0.clone();
-        load_world_ew.send(DelayLoadRevoluteJoint(dir));
-    }
-}
-
 pub fn load(
-    mut load_world_er: EventReader<DelayLoadRevoluteJoint>,
+    mut load_world_er: EventReader<DelayLoadJoint>,
     mut commands: Commands,
     mut q: Query<&mut RigidBody>,
     )
@@ -119,9 +170,8 @@ pub fn load(
 
                 add(&mut commands,
                     &mut rigid_body,
-                    RevoluteJoint { child_entity: entity,
-                                    translation: v.translation,
-                                    limits: v.limits });
+                    RevoluteJoint { child_entity: entity, ..v });
+                commands.entity(entity).insert(MapObject::RevoluteJoint(vec![]));
             }
         }
     }
@@ -129,18 +179,17 @@ pub fn load(
 
 use crate::ev_save_load_world::SaveWorldEvent;
 pub fn save(mut save_world_er: EventReader<SaveWorldEvent>,
-              q: Query<(Entity, &RevoluteJoint)>
+              q: Query<(Entity, &RevoluteJoint, &ImpulseJoint)>
               ) {
     for e in save_world_er.iter() {
         let dir = e.0.clone();
         let mut elem_list: Vec<(u32, RevoluteJoint)> = vec![];
 
-        for (e, rt) in q.iter() {
-            elem_list.push((e.index(), rt.clone()));
+        for (e, rt, ij) in q.iter() {
+            elem_list.push((e.index(), RevoluteJoint { base: Some(ij.parent.index()), ..rt.clone() }));
         }
 
         let filename = dir + FILE_NAME;
         std::fs::write(filename, serde_json::to_string(&elem_list).unwrap()).unwrap();
     }
 }
-
@@ -0,0 +1,176 @@
+use serde::{Serialize, Deserialize};
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use bevy_rapier2d::rapier::dynamics::{JointAxesMask, JointAxis};
+
+use bevy_inspector_egui::bevy_egui::{egui, EguiContexts};
+
+use crate::edit_context::*;
+use crate::ev_save_load_world;
+
+#[derive(Component, Reflect, Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct RopeJoint {
+    pub child_entity: Entity,
+    // world position the rope is tied to
+    pub translation: Vec3,
+    pub length: Real,
+    // entity index of the base body, so a reload reuses it
+    #[serde(default)]
+    pub base: Option<u32>,
+}
+
+#[derive(Component, Reflect, Clone, Serialize, Deserialize, Debug)]
+pub struct RopeJointBase;
+
+pub fn handle_user_input(
+    mut commands: Commands,
+    buttons: Res<Input<MouseButton>>,
+    mut edit_context: ResMut<EditContext>,
+    world_position: Res<WorldPosition>,
+    mut transform_q: Query<(Entity, &mut Transform, &mut RigidBody)>,
+    ) {
+    if ! buttons.just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    if let EditContext::Spawn(map_object) = edit_context.clone() {
+        if let MapObject::RopeJoint(entities) = map_object {
+            let base = world_position.translation;
+
+            for entity in entities.clone() {
+                let (entity, transform, mut rigid_body) = transform_q.get_mut(entity).unwrap();
+
+                let entity = add(&mut commands,
+                                 &mut rigid_body,
+                                 RopeJoint { child_entity: entity,
+                                             translation: Vec3::from((base, transform.translation.z)),
+                                             length: base.distance(transform.translation.truncate()),
+                                             base: None });
+
+                commands.entity(entity).insert(MapObject::RopeJoint(vec![]));
+            }
+
+            *edit_context = EditContext::Edit(MapObject::RopeJoint(vec![]), entities, EditTool::Select);
+        }
+    }
+}
+
+
+pub fn edit_window(
+    mut egui_contexts: EguiContexts,
+    edit_context: Res<EditContext>,
+    mut rj_q: Query<&mut RopeJoint>,
+    ) {
+    if let EditContext::Edit(MapObject::RopeJoint(_), pick, _) = edit_context.clone() {
+        if pick.len() == 0 { return; }
+
+        if let Ok(mut rope_joint) = rj_q.get_mut(pick[0]) {
+            let mut rj = rope_joint.clone();
+
+            egui::Window::new("RopeJoint").show(egui_contexts.ctx_mut(), |ui: &mut egui::Ui| {
+                ui.horizontal(|ui: &mut egui::Ui| {
+                    ui.label("Length");
+                    ui.add(egui::DragValue::new(&mut rj.length).speed(1.0).clamp_range(0.0..=f32::MAX));
+                });
+            });
+
+            if rj != *rope_joint {
+                *rope_joint = rj;
+            }
+        }
+    }
+}
+
+
+pub fn system(
+    mut rj_q: Query<(&mut ImpulseJoint, &RopeJoint), Changed<RopeJoint>>,
+) {
+    for (mut ij, rj) in rj_q.iter_mut() {
+        ij.data = joint_data(rj);
+    }
+}
+
+
+fn joint_data(rope_joint: &RopeJoint) -> GenericJoint {
+    let mut joint = GenericJointBuilder::new(JointAxesMask::empty())
+        .local_anchor1(Vec2::new(0.0, 0.0))
+        .local_anchor2(Vec2::new(0.0, 0.0))
+        .limits(JointAxis::X, [0.0, rope_joint.length])
+        .build();
+    // bevy_rapier's builder doesn't expose coupled_axes, the limit is on the distance
+    joint.raw.coupled_axes = JointAxesMask::LIN_AXES;
+    joint
+}
+
+
+fn add(commands: &mut Commands, rigid_body: &mut RigidBody, rope_joint: RopeJoint) -> Entity {
+    let joint = joint_data(&rope_joint);
+
+    let base_entity = ev_save_load_world::get_or_spawn(commands, rope_joint.base)
+        .insert(RigidBody::Dynamic)
+        .insert(TransformBundle {
+            local: Transform {
+                translation: rope_joint.translation,
+                ..Default::default()
+            },
+            ..default()
+        })
+        .insert(RopeJointBase)
+    .id();
+
+    let mut entity = commands.get_entity(rope_joint.child_entity).unwrap();
+    entity
+        .insert(ImpulseJoint::new(base_entity, joint))
+        .insert(rope_joint);
+
+    *rigid_body = RigidBody::Dynamic;
+
+    return entity.id();
+}
+
+
+use crate::cmp_joint::DelayLoadJoint;
+const FILE_NAME: &str = "/rope_joint.map";
+pub fn load(
+    mut load_world_er: EventReader<DelayLoadJoint>,
+    mut commands: Commands,
+    mut q: Query<&mut RigidBody>,
+    )
+{
+    for e in load_world_er.iter() {
+        let dir = e.0.clone();
+
+        let filename = dir + FILE_NAME;
+        let json_str = std::fs::read_to_string(filename);
+        if let Ok(json_str) = json_str {
+            let elem_list: Vec<(u32, RopeJoint)> = serde_json::from_str(&json_str).unwrap();
+
+            for (id, v) in elem_list {
+                let entity = commands.get_or_spawn(Entity::from_raw(id)).id();
+                let mut rigid_body = q.get_mut(entity).unwrap();
+
+                add(&mut commands,
+                    &mut rigid_body,
+                    RopeJoint { child_entity: entity, ..v });
+                commands.entity(entity).insert(MapObject::RopeJoint(vec![]));
+            }
+        }
+    }
+}
+
+use crate::ev_save_load_world::SaveWorldEvent;
+pub fn save(mut save_world_er: EventReader<SaveWorldEvent>,
+              q: Query<(Entity, &RopeJoint, &ImpulseJoint)>
+              ) {
+    for e in save_world_er.iter() {
+        let dir = e.0.clone();
+        let mut elem_list: Vec<(u32, RopeJoint)> = vec![];
+
+        for (e, rj, ij) in q.iter() {
+            elem_list.push((e.index(), RopeJoint { base: Some(ij.parent.index()), ..rj.clone() }));
+        }
+
+        let filename = dir + FILE_NAME;
+        std::fs::write(filename, serde_json::to_string(&elem_list).unwrap()).unwrap();
+    }
+}
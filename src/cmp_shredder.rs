@@ -129,7 +129,7 @@ pub fn system_kill(
     game_assets: Res<GameAsset>,
     rapier_context: Res<RapierContext>,
     shredder_q: Query<(&Transform, &BBSize), With<Shredder>>,
-    ball_q: Query<&Transform, With<Ball>>,
+    ball_q: Query<(&Transform, &Ball)>,
 ) {
     let game_assets = game_assets.into_inner();
 
@@ -143,8 +143,8 @@ pub fn system_kill(
 
         rapier_context.intersections_with_shape(
             shape_pos, shape_rot, &shape, filter, |entity| {
-                let transform = ball_q.get(entity).unwrap();
-                cmp_ball::kill(&mut commands, &audio, game_assets, entity, &transform);
+                let (transform, ball) = ball_q.get(entity).unwrap();
+                cmp_ball::kill(&mut commands, &audio, game_assets, entity, &transform, ball);
                 true // Return `false` instead if we want to stop searching for other colliders that contain this point.
         });
 
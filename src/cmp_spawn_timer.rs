@@ -10,24 +10,111 @@ use crate::cmp_game_asset::GameAsset;
 use crate::cmp_fuse_time::FuseTime;
 use crate::cmp_ball_zundamon;
 use crate::cmp_ball_zombie;
-use crate::cmp_ball_type1;
 
 use crate::cmp_gate_generic;
 use crate::cmp_gate_generic::SpawnBall;
+use crate::cmp_gate_generic::BallType;
+use crate::cmp_gate_generic::GateGeneric;
+use crate::cmp_conveyor::Conveyor;
+use crate::cmp_ball_species::BallSpeciesRegistry;
+use crate::cmp_combat_unit::UnitRegistry;
+use crate::cmp_team::TeamConfig;
 
 use crate::cmp_rotator::Rotator;
 use crate::edit_context::*;
+use bevy_inspector_egui::bevy_egui::{egui, EguiContexts};
 
 pub const DEFAULT_SIZE_X: f32 = 10.0;
 pub const DEFAULT_SIZE_Y: f32 = 10.0;
+// firings shown per timer in the timeline
+const TIMELINE_MAX_FIRINGS: u32 = 100;
+const TIMELINE_SECONDS: f32 = 120.0;
+// shortest time between firings, a ramp or jitter never goes below it
+const MIN_INTERVAL: f32 = 0.1;
 
 
-#[derive(Component, Reflect, Clone, Serialize, Deserialize, Debug)]
+fn default_repeat() -> u32 { 1 }
+fn default_ramp() -> f32 { 1.0 }
+
+// Fires its signals `seconds` after the game starts and then, for
+// `repeat` > 1, again every `interval` seconds. The defaults fire once.
+#[derive(Component, Reflect, Clone, PartialEq, Serialize, Deserialize, Debug)]
 pub struct SpawnTimer {
     pub signals: Vec<SpawnBall>,
     pub seconds: f32,
+    #[serde(default)]
+    pub interval: f32,
+    // number of firings, 0 repeats forever
+    #[serde(default = "default_repeat")]
+    pub repeat: u32,
+    // per-wave signals, used in turn instead of `signals` when not empty
+    #[serde(default)]
+    pub waves: Vec<Vec<SpawnBall>>,
+    // the interval is multiplied by this after every firing, < 1.0 speeds up
+    #[serde(default = "default_ramp")]
+    pub ramp: f32,
+    // up to this many seconds are randomly added to or taken from each interval
+    #[serde(default)]
+    pub jitter: f32,
+    // the same seed gives the same jitter in every game
+    #[serde(default)]
+    pub seed: u64,
+    #[serde(skip)]
+    pub fired: u32,
+}
+
+impl SpawnTimer {
+    pub fn new(signals: Vec<SpawnBall>) -> Self {
+        Self {
+            signals,
+            seconds: 0.0,
+            interval: 0.0,
+            repeat: 1,
+            waves: vec![],
+            ramp: 1.0,
+            jitter: 0.0,
+            seed: 0,
+            fired: 0,
+        }
+    }
+
+    fn done(&self) -> bool {
+        self.repeat != 0 && self.fired >= self.repeat
+    }
+
+    fn wave(&self, n: u32) -> &Vec<SpawnBall> {
+        if self.waves.is_empty() {
+            &self.signals
+        } else {
+            &self.waves[n as usize % self.waves.len()]
+        }
+    }
+
+    // seconds from firing `n` to the next one
+    fn delay(&self, n: u32, rng: &mut StdRng) -> f32 {
+        let jitter = if self.jitter > 0.0 { rng.gen_range(-self.jitter..=self.jitter) } else { 0.0 };
+        (self.interval * self.ramp.powi(n as i32) + jitter).max(MIN_INTERVAL)
+    }
+
+    // when each firing happens, for the timeline
+    fn firing_times(&self) -> Vec<f32> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut times = vec![];
+        let mut t = self.seconds;
+        let mut n = 0;
+        while (self.repeat == 0 || n < self.repeat) && n < TIMELINE_MAX_FIRINGS && t <= TIMELINE_SECONDS {
+            times.push(t);
+            t += self.delay(n, &mut rng);
+            n += 1;
+        }
+        times
+    }
 }
 
+// the jitter generator of a running timer
+#[derive(Component)]
+pub struct SpawnTimerRng(StdRng);
+
 
 #[derive(Bundle)]
 pub struct SpawnTimerBundle {
@@ -47,10 +134,7 @@ impl Default for SpawnTimerBundle {
             fuse_time: FuseTime {
                 timer: Timer::from_seconds(0.0, TimerMode::Once)
             },
-            spawn_timer: SpawnTimer {
-                signals: vec![],
-                seconds: 0.0,
-            },
+            spawn_timer: SpawnTimer::new(vec![]),
             map_object: MapObject::SpawnTimer(vec![]),
             sprite_bundle: SpriteBundle {
                 sprite: Sprite {
@@ -100,7 +184,7 @@ pub fn handle_user_input(
                 SpawnTimerBundle::from((Vec3::from((world_position.translation, 0.0)),
                                         Quat::from_rotation_z(0.0),
                                         Vec3::ONE,
-                                        SpawnTimer { signals, seconds: 0.0, },
+                                        SpawnTimer::new(signals),
                                         ))
                 );
 
@@ -110,8 +194,9 @@ pub fn handle_user_input(
 }
 
 pub fn system_setup(
+    mut commands: Commands,
     mut query: Query<&mut Sprite, With<SpawnTimer>>,
-    mut fuse_time_q: Query<(&mut FuseTime, &SpawnTimer)>,
+    mut fuse_time_q: Query<(Entity, &mut FuseTime, &mut SpawnTimer)>,
     ) {
 
     for mut sprite in query.iter_mut() {
@@ -119,29 +204,206 @@ pub fn system_setup(
         sprite.color = color;
     }
 
-    for (mut fuse_time, spawn_timer) in fuse_time_q.iter_mut() {
+    for (entity, mut fuse_time, mut spawn_timer) in fuse_time_q.iter_mut() {
         *fuse_time = FuseTime { timer: Timer::from_seconds(spawn_timer.seconds, TimerMode::Once) };
+        spawn_timer.fired = 0;
+        commands.entity(entity).insert(SpawnTimerRng(StdRng::seed_from_u64(spawn_timer.seed)));
+    }
+}
+
+// timers stay in the map, so show them again after a game
+pub fn system_edit_setup(
+    mut query: Query<&mut Sprite, With<SpawnTimer>>,
+    ) {
+    for mut sprite in query.iter_mut() {
+        sprite.color = Color::GRAY;
     }
 }
 
 pub fn system(
-    mut commands: Commands,
     time: Res<Time>,
     mut event: EventWriter<SpawnBall>,
-    mut trajectory_q: Query<(Entity, &mut FuseTime, &mut SpawnTimer)>,
+    mut trajectory_q: Query<(&mut FuseTime, &mut SpawnTimer, &mut SpawnTimerRng)>,
 ) {
-    for (entity, mut fuse_time, mut spawn_timer) in trajectory_q.iter_mut() {
+    for (mut fuse_time, mut spawn_timer, mut rng) in trajectory_q.iter_mut() {
+        if spawn_timer.done() { continue; }
+
         fuse_time.timer.tick(time.delta());
-        if fuse_time.timer.finished() { 
-            for s in spawn_timer.signals.iter() {
+        if fuse_time.timer.finished() {
+            let n = spawn_timer.fired;
+            for s in spawn_timer.wave(n).iter() {
                 event.send(s.clone());
-                commands.entity(entity).despawn();
+            }
+            spawn_timer.fired += 1;
+
+            let delay = spawn_timer.delay(n, &mut rng.0);
+            *fuse_time = FuseTime { timer: Timer::from_seconds(delay, TimerMode::Once) };
+        }
+    }
+}
+
+
+fn ball_label(ball_type: &BallType) -> String {
+    match ball_type {
+        BallType::Species(name) => name.clone(),
+        BallType::Unit(name, team) => format!("{} team {}", name, team.0),
+        b => format!("{:?}", b),
+    }
+}
+
+// target and ball type of one signal
+fn signal_ui(ui: &mut egui::Ui, id: (usize, usize), signal: &mut SpawnBall, targets: &Vec<u32>, ball_types: &Vec<BallType>) {
+    egui::ComboBox::from_id_source(("signal_target", id))
+        .selected_text(format!("{}", signal.0))
+        .show_ui(ui, |ui: &mut egui::Ui| {
+            for target in targets.iter() {
+                ui.selectable_value(&mut signal.0, *target, format!("{}", target));
+            }
+        });
+    egui::ComboBox::from_id_source(("signal_ball", id))
+        .selected_text(ball_label(&signal.1))
+        .show_ui(ui, |ui: &mut egui::Ui| {
+            for ball_type in ball_types.iter() {
+                ui.selectable_value(&mut signal.1, ball_type.clone(), ball_label(ball_type));
+            }
+        });
+}
+
+pub fn edit_window(
+    mut egui_contexts: EguiContexts,
+    edit_context: Res<EditContext>,
+    species_registry: Res<BallSpeciesRegistry>,
+    unit_registry: Res<UnitRegistry>,
+    team_config: Res<TeamConfig>,
+    mut timer_q: Query<&mut SpawnTimer>,
+    target_q: Query<Entity, Or<(With<GateGeneric>, With<Conveyor>)>>,
+    ) {
+    if let EditContext::Edit(MapObject::SpawnTimer(_), pick, _) = edit_context.clone() {
+        if pick.len() == 0 { return; }
+
+        if let Ok(mut spawn_timer) = timer_q.get_mut(pick[0]) {
+            let mut t = spawn_timer.clone();
+
+            // what a signal can be sent to and what it can spawn
+            let mut targets: Vec<u32> = target_q.iter().map(|e| e.index()).collect();
+            targets.sort();
+            let mut ball_types = vec![BallType::Zundamon, BallType::Zombie];
+            for name in species_registry.names() {
+                ball_types.push(BallType::Species(name));
+            }
+            for name in unit_registry.names() {
+                for team in team_config.teams() {
+                    ball_types.push(BallType::Unit(name.clone(), team));
+                }
+            }
+
+            egui::Window::new("SpawnTimer").show(egui_contexts.ctx_mut(), |ui: &mut egui::Ui| {
+                ui.horizontal(|ui: &mut egui::Ui| {
+                    ui.label("start");
+                    ui.add(egui::DragValue::new(&mut t.seconds).speed(0.1).clamp_range(0.0..=3600.0));
+                    ui.label("repeat");
+                    ui.add(egui::DragValue::new(&mut t.repeat).clamp_range(0..=10000));
+                    // a repeating timer with no interval would fire every frame
+                    let min = if t.repeat == 1 { 0.0 } else { MIN_INTERVAL };
+                    ui.label("interval");
+                    ui.add(egui::DragValue::new(&mut t.interval).speed(0.1).clamp_range(min..=3600.0));
+                });
+                ui.horizontal(|ui: &mut egui::Ui| {
+                    ui.label("ramp");
+                    ui.add(egui::DragValue::new(&mut t.ramp).speed(0.01).clamp_range(0.1..=10.0));
+                    ui.label("jitter");
+                    ui.add(egui::DragValue::new(&mut t.jitter).speed(0.01).clamp_range(0.0..=60.0));
+                    ui.label("seed");
+                    ui.add(egui::DragValue::new(&mut t.seed));
+                });
+                ui.horizontal(|ui: &mut egui::Ui| {
+                    ui.label(format!("signals {}", t.signals.len()));
+                    // a new wave starts as a copy of the last one, or of the signals
+                    if ui.button("add wave").clicked() {
+                        let last = t.waves.last().unwrap_or(&t.signals).clone();
+                        t.waves.push(last);
+                    }
+                });
+                let mut remove = None;
+                for (i, wave) in t.waves.iter_mut().enumerate() {
+                    ui.horizontal(|ui: &mut egui::Ui| {
+                        ui.label(format!("wave {}: {} signals", i + 1, wave.len()));
+                        // a new signal starts as a copy of the last one, then gets its own target and ball
+                        if ui.button("+").clicked() {
+                            let signal = match wave.last() {
+                                Some(s) => s.clone(),
+                                None => SpawnBall(targets.first().cloned().unwrap_or(0), BallType::Zundamon),
+                            };
+                            wave.push(signal);
+                        }
+                        if ui.button("-").clicked() {
+                            wave.pop();
+                        }
+                        if ui.button("remove").clicked() {
+                            remove = Some(i);
+                        }
+                    });
+                    for (j, signal) in wave.iter_mut().enumerate() {
+                        ui.horizontal(|ui: &mut egui::Ui| {
+                            ui.label("  ");
+                            signal_ui(ui, (i, j), signal, &targets, &ball_types);
+                        });
+                    }
+                }
+                if let Some(i) = remove {
+                    t.waves.remove(i);
+                }
+            });
+            if t.repeat != 1 {
+                t.interval = t.interval.max(MIN_INTERVAL);
+            }
+
+            if t != *spawn_timer {
+                *spawn_timer = t;
             }
         }
     }
 }
 
 
+// every scheduled firing of the map on one time axis, collapsed until needed
+pub fn timeline_window(
+    mut egui_contexts: EguiContexts,
+    timer_q: Query<(Entity, &SpawnTimer)>,
+    ) {
+    if timer_q.is_empty() { return; }
+
+    egui::Window::new("Spawn Timeline").default_open(false).show(egui_contexts.ctx_mut(), |ui: &mut egui::Ui| {
+        let mut timers: Vec<(Entity, &SpawnTimer, Vec<f32>)> = timer_q.iter()
+            .map(|(e, t)| (e, t, t.firing_times()))
+            .collect();
+        timers.sort_by_key(|(e, _, _)| e.index());
+
+        let end = timers.iter()
+            .filter_map(|(_, _, times)| times.last().cloned())
+            .fold(10.0_f32, f32::max)
+            .ceil();
+        ui.label(format!("0 - {} s", end));
+
+        for (entity, spawn_timer, times) in timers.iter() {
+            ui.horizontal(|ui: &mut egui::Ui| {
+                let balls: usize = (0..times.len() as u32).map(|n| spawn_timer.wave(n).len()).sum();
+                ui.label(format!("{:>4} {:>4} balls", entity.index(), balls));
+
+                let (rect, _) = ui.allocate_exact_size(egui::vec2(400.0, 16.0), egui::Sense::hover());
+                let painter = ui.painter();
+                painter.rect_filled(rect, 2.0, egui::Color32::from_gray(40));
+                for t in times {
+                    let x = rect.left() + rect.width() * t / end;
+                    painter.line_segment([egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())],
+                                         egui::Stroke::new(1.0, egui::Color32::LIGHT_GREEN));
+                }
+            });
+        }
+    });
+}
+
+
 const FILE_NAME: &str = "/spawn_timer.map";
 use crate::ev_save_load_world::LoadWorldEvent;
 pub fn load(
@@ -0,0 +1,258 @@
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+use crate::cmp_combat::Status;
+use crate::cmp_game_asset::GameAsset;
+
+const HIT_COLOR: Color = Color::rgb(1.0, 0.5, 0.5);
+
+// atlas name and its frames, in order
+const ATLASES: [(&str, &[&str]); 3] = [
+    ("explosion", &["explosion00_handle", "explosion01_handle", "explosion02_handle",
+                    "explosion03_handle", "explosion04_handle", "explosion05_handle",
+                    "explosion06_handle", "explosion07_handle", "explosion08_handle"]),
+    ("zundamon", &["zun1_handle", "zun2_handle", "zun3_handle"]),
+    ("zombie", &["zombie1_handle"]),
+];
+
+// clip name, atlas, first and last frame, seconds a frame, mode.
+// A Character named "x" plays "x_idle", "x_hit" and "x_dying"
+const CLIPS: [(&str, &str, usize, usize, f32, Mode); 8] = [
+    ("explosion", "explosion", 0, 8, 1.0 / 9.0, Mode::Once),
+    ("explosion_loop", "explosion", 0, 8, 0.1, Mode::Loop),
+    ("zundamon_idle", "zundamon", 0, 1, 0.4, Mode::Loop),
+    ("zundamon_hit", "zundamon", 2, 2, 0.3, Mode::Once),
+    ("zundamon_dying", "zundamon", 2, 2, 0.5, Mode::Once),
+    ("zombie_idle", "zombie", 0, 0, 1.0, Mode::Loop),
+    ("zombie_hit", "zombie", 0, 0, 0.3, Mode::Once),
+    ("zombie_dying", "zombie", 0, 0, 0.5, Mode::Once),
+];
+
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Mode {
+    Loop,
+    // stops on the last frame and sets `finished`
+    Once,
+}
+
+#[derive(Clone, Debug)]
+pub struct Clip {
+    pub atlas: Handle<TextureAtlas>,
+    // atlas indices in play order
+    pub frames: Vec<usize>,
+    pub frame_time: f32,
+    pub mode: Mode,
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct Clips {
+    pub clips: HashMap<String, Clip>,
+}
+
+
+// Packs the frames loaded by setup_graphics into atlases. The builder
+// places frames wherever they fit, so each clip keeps the atlas index of
+// its frames in order.
+pub fn setup(
+    mut game_assets: ResMut<GameAsset>,
+    mut image_assets: ResMut<Assets<Image>>,
+    mut atlas_assets: ResMut<Assets<TextureAtlas>>,
+    mut clips: ResMut<Clips>,
+) {
+    let mut orders = HashMap::new();
+    for (name, frames) in ATLASES {
+        let mut builder = TextureAtlasBuilder::default().max_size(Vec2::splat(4096.0));
+        let handles: Vec<Handle<Image>> = frames.iter().map(|f| game_assets.image_handles.get(*f).unwrap().clone()).collect();
+        for handle in handles.iter() {
+            builder.add_texture(handle.clone(), image_assets.get(handle).unwrap());
+        }
+
+        let atlas = builder.finish(&mut image_assets).unwrap();
+        let order: Vec<usize> = handles.iter().map(|h| atlas.get_texture_index(h).unwrap()).collect();
+        orders.insert(name, order);
+
+        game_assets.atlas_handles.insert(name.to_string(), atlas_assets.add(atlas));
+    }
+
+    for (name, atlas, first, last, frame_time, mode) in CLIPS {
+        let frames = orders[atlas][first..=last].to_vec();
+        let atlas = game_assets.atlas_handles.get(atlas).unwrap().clone();
+        clips.clips.insert(name.to_string(), Clip { atlas, frames, frame_time, mode });
+    }
+}
+
+
+#[derive(Component, Debug)]
+pub struct SpriteAnimation {
+    pub clip: String,
+    pub finished: bool,
+    frame: usize,
+    timer: Timer,
+}
+
+impl SpriteAnimation {
+    pub fn new(clip: &str) -> Self {
+        Self {
+            clip: clip.to_string(),
+            finished: false,
+            frame: 0,
+            timer: Timer::from_seconds(0.0, TimerMode::Repeating),
+        }
+    }
+
+    // restarts only when the clip changes
+    pub fn play(&mut self, clip: &str) {
+        if self.clip != clip {
+            *self = Self::new(clip);
+        }
+    }
+}
+
+pub fn system(
+    time: Res<Time>,
+    clips: Res<Clips>,
+    mut animation_q: Query<(&mut SpriteAnimation, &mut TextureAtlasSprite, &mut Handle<TextureAtlas>)>,
+) {
+    for (mut animation, mut sprite, mut atlas) in animation_q.iter_mut() {
+        let Some(clip) = clips.clips.get(&animation.clip) else { continue; };
+
+        if *atlas != clip.atlas {
+            *atlas = clip.atlas.clone();
+        }
+        if animation.timer.duration().as_secs_f32() != clip.frame_time {
+            animation.timer = Timer::from_seconds(clip.frame_time, TimerMode::Repeating);
+        }
+
+        let frames = clip.frames.len();
+        animation.timer.tick(time.delta());
+        let frame = animation.frame + animation.timer.times_finished_this_tick() as usize;
+        animation.frame = match clip.mode {
+            Mode::Loop => frame % frames,
+            Mode::Once => frame.min(frames - 1),
+        };
+        if clip.mode == Mode::Once && frame >= frames {
+            animation.finished = true;
+        }
+
+        let index = clip.frames[animation.frame];
+        if sprite.index != index {
+            sprite.index = index;
+        }
+    }
+}
+
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AnimState {
+    Idle,
+    Hit,
+    Dying,
+}
+
+// state driven animation for units, the clips are "<name>_idle" and so on
+#[derive(Component, Debug)]
+pub struct Character {
+    pub name: String,
+    pub state: AnimState,
+}
+
+impl Character {
+    pub fn new(name: &str) -> Self {
+        Self { name: name.to_string(), state: AnimState::Idle }
+    }
+
+    pub fn clip(&self) -> String {
+        let state = match self.state {
+            AnimState::Idle => "idle",
+            AnimState::Hit => "hit",
+            AnimState::Dying => "dying",
+        };
+        format!("{}_{}", self.name, state)
+    }
+}
+
+pub fn system_character(
+    mut character_q: Query<(&mut Character, &mut Status, &mut SpriteAnimation, &mut TextureAtlasSprite)>,
+) {
+    for (mut character, mut status, mut animation, mut sprite) in character_q.iter_mut() {
+        // only discrete damage, a burn ticking every frame would never end the hit
+        let hit = status.hit;
+        if hit {
+            status.hit = false;
+        }
+
+        let state = match character.state {
+            AnimState::Dying => AnimState::Dying,
+            _ if hit => AnimState::Hit,
+            AnimState::Hit if animation.finished => AnimState::Idle,
+            state => state,
+        };
+        if hit && character.state == AnimState::Hit {
+            // hit again before the last one played out
+            *animation = SpriteAnimation::new(&character.clip());
+        }
+        character.state = state;
+        animation.play(&character.clip());
+
+        // one frame sheets still show the hit. cmp_combat sets the effect
+        // color every frame before this, so multiply to keep e.g. the burn
+        if state == AnimState::Hit {
+            let c = sprite.color;
+            sprite.color = Color::rgba(c.r() * HIT_COLOR.r(), c.g() * HIT_COLOR.g(), c.b() * HIT_COLOR.b(), c.a());
+        }
+    }
+}
+
+
+// left behind by a dying unit to play its dying clip, the unit itself is
+// gone from the physics already
+#[derive(Component)]
+pub struct Corpse;
+
+pub fn add_corpse(commands: &mut Commands, clips: &Clips, name: &str, transform: &Transform, size: Vec2) {
+    let mut character = Character::new(name);
+    character.state = AnimState::Dying;
+    let Some(clip) = clips.clips.get(&character.clip()) else { return; };
+
+    commands.spawn((
+        SpriteSheetBundle {
+            sprite: TextureAtlasSprite {
+                index: clip.frames[0],
+                custom_size: Some(size),
+                ..default()
+            },
+            texture_atlas: clip.atlas.clone(),
+            transform: *transform,
+            ..default()
+        },
+        SpriteAnimation::new(&character.clip()),
+        Corpse,
+    ));
+}
+
+pub fn system_corpse(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut corpse_q: Query<(Entity, &SpriteAnimation, &mut TextureAtlasSprite, &mut Transform), With<Corpse>>,
+) {
+    for (entity, animation, mut sprite, mut transform) in corpse_q.iter_mut() {
+        if animation.finished {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let alpha = sprite.color.a();
+        sprite.color.set_a((alpha - time.delta_seconds() * 2.0).max(0.0));
+        transform.scale *= 1.0 - time.delta_seconds();
+    }
+}
+
+
+pub fn system_cleanup(
+    mut commands: Commands,
+    corpse_q: Query<Entity, With<Corpse>>,
+) {
+    for entity in corpse_q.iter() {
+        commands.entity(entity).despawn();
+    }
+}
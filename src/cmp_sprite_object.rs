@@ -10,10 +10,6 @@ use crate::cmp_game_asset::GameAsset;
 use crate::cmp_ball_zundamon;
 use crate::cmp_ball_zombie;
 
-use crate::cmp_ball_type1;
-use crate::cmp_ball_type2;
-use crate::cmp_ball_type3;
-use crate::cmp_ball_type4;
 
 use crate::cmp_rotator::Rotator;
 use crate::edit_context::*;
@@ -0,0 +1,129 @@
+use serde::{Serialize, Deserialize};
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+pub const MAX_TEAMS: u8 = 4;
+
+const TEAM_GROUPS: [Group; MAX_TEAMS as usize] = [Group::GROUP_10, Group::GROUP_11, Group::GROUP_12, Group::GROUP_14];
+const TEAM_COLORS: [Color; MAX_TEAMS as usize] = [Color::GREEN, Color::PURPLE, Color::ORANGE, Color::CYAN];
+
+#[derive(Component, Reflect, FromReflect, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Debug)]
+pub struct Team(pub u8);
+
+impl Team {
+    pub fn group(&self) -> Group {
+        TEAM_GROUPS[(self.0 % MAX_TEAMS) as usize]
+    }
+
+    pub fn color(&self) -> Color {
+        TEAM_COLORS[(self.0 % MAX_TEAMS) as usize]
+    }
+}
+
+// How the collision groups of a team-owned entity are derived.
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+pub enum TeamCollision {
+    // moving units: collide with the world and with hostile units
+    Unit,
+    // walls and cores: part of the world, but only hostile units touch them
+    Structure,
+}
+
+#[derive(Resource, Reflect, Clone, Serialize, Deserialize, Debug)]
+pub struct TeamConfig {
+    pub num_teams: u8,
+    // explosions and shells also hurt their own team, units of a team
+    // still don't fight each other
+    pub friendly_fire: bool,
+    // every unit fights every other, even of its own team; the team only
+    // records ownership and who scores
+    pub free_for_all: bool,
+}
+
+impl Default for TeamConfig {
+    fn default() -> Self {
+        Self {
+            num_teams: 2,
+            friendly_fire: false,
+            free_for_all: false,
+        }
+    }
+}
+
+impl TeamConfig {
+    // units of `a` and `b` collide, fight and target each other
+    pub fn hostile(&self, a: Team, b: Team) -> bool {
+        a != b || self.free_for_all
+    }
+
+    // weapons of `a`, explosions and shells, hurt `b`
+    pub fn harms(&self, a: Team, b: Team) -> bool {
+        self.hostile(a, b) || self.friendly_fire
+    }
+
+    pub fn teams(&self) -> Vec<Team> {
+        (0..self.num_teams.clamp(1, MAX_TEAMS)).map(Team).collect()
+    }
+
+    fn hostile_groups(&self, team: Team) -> Group {
+        let mut groups = Group::NONE;
+        for other in self.teams() {
+            if self.hostile(team, other) {
+                groups = groups | other.group();
+            }
+        }
+        groups
+    }
+
+    pub fn collision_groups(&self, team: Team, collision: TeamCollision) -> CollisionGroups {
+        match collision {
+            TeamCollision::Unit => CollisionGroups::new(team.group(), Group::GROUP_1 | self.hostile_groups(team)),
+            TeamCollision::Structure => CollisionGroups::new(Group::GROUP_1, self.hostile_groups(team)),
+        }
+    }
+}
+
+
+pub fn system_collision_groups(
+    config: Res<TeamConfig>,
+    mut query: Query<(Ref<Team>, Ref<TeamCollision>, &mut CollisionGroups)>,
+) {
+    for (team, collision, mut groups) in query.iter_mut() {
+        if config.is_changed() || team.is_changed() || collision.is_changed() {
+            *groups = config.collision_groups(*team, *collision);
+        }
+    }
+}
+
+
+const FILE_NAME: &str = "/team_config.map";
+use crate::ev_save_load_world::LoadWorldEvent;
+pub fn load(
+    mut load_world_er: EventReader<LoadWorldEvent>,
+    mut config: ResMut<TeamConfig>,
+    ) {
+
+    for e in load_world_er.iter() {
+        let dir = e.0.clone();
+
+        let json_str = std::fs::read_to_string(dir + FILE_NAME);
+        if let Ok(json_str) = json_str {
+            *config = serde_json::from_str(&json_str).unwrap();
+        } else {
+            *config = TeamConfig::default();
+        }
+    }
+}
+
+
+use crate::ev_save_load_world::SaveWorldEvent;
+pub fn save(mut save_world_er: EventReader<SaveWorldEvent>,
+              config: Res<TeamConfig>,
+              ) {
+
+    for e in save_world_er.iter() {
+        let dir = e.0.clone();
+
+        std::fs::write(dir + FILE_NAME, serde_json::to_string(&*config).unwrap()).unwrap();
+    }
+}
@@ -1,60 +1,230 @@
 use serde::{Serialize, Deserialize};
 use bevy::prelude::*;
-use bevy_prototype_lyon::prelude::*;
+use bevy::sprite::Mesh2dHandle;
+use std::collections::{HashMap, VecDeque};
 
-use crate::cmp_fuse_time::FuseTime;
+use crate::cmp_ball::Ball;
+use crate::cmp_ball_species::Species;
+use crate::cmp_ball_zombie::Zombie;
+use crate::cmp_ball_zundamon::Zundamon;
+use crate::cmp_combat_unit::Unit;
+use crate::cmp_team::Team;
+use crate::quad_mesh::QuadMesh;
 
-#[derive(Component, Reflect, Clone, Serialize, Deserialize, Debug)]
-pub struct Trajectory {
-    pub line: (Vec2, Vec2),
-    pub life_time: f32,
+const MAX_ALPHA: f32 = 0.8;
+const LENGTH: usize = 36;
+const WIDTH: f32 = 3.0;
+const COLOR: [f32; 3] = [0.2, 0.8, 0.2];
+
+
+fn default_colors() -> HashMap<String, [f32; 3]> {
+    HashMap::from([(String::from("zombie"), [0.6, 0.2, 0.2])])
 }
 
-const MAX_ALPHA: f32 = 0.8;
+#[derive(Resource, Clone, Serialize, Deserialize, Debug)]
+pub struct TrailConfig {
+    pub enabled: bool,
+    // positions kept per ball, one a frame
+    pub length: usize,
+    pub width: f32,
+    // by ball type: "zundamon", "zombie", a species or a unit name.
+    // Units missing here take their team color, the rest `color`
+    #[serde(default = "default_colors")]
+    pub colors: HashMap<String, [f32; 3]>,
+    pub color: [f32; 3],
+}
 
-pub fn add(commands: &mut Commands,
-            trajectory: Trajectory) -> Entity {
-    let mut path_builder = PathBuilder::new();
-    path_builder.move_to(trajectory.line.0);
-    path_builder.line_to(trajectory.line.1);
-    let path = path_builder.build();
+impl Default for TrailConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            length: LENGTH,
+            width: WIDTH,
+            colors: default_colors(),
+            color: COLOR,
+        }
+    }
+}
 
-    let color = Color::Rgba {
-                        red: 50.0,
-                        green: 205.0,
-                        blue: 50.0,
-                        alpha: 1.0 * MAX_ALPHA,
-                     };
+// the last positions of a ball, oldest first
+#[derive(Component, Default)]
+pub struct Trail {
+    points: VecDeque<Vec2>,
+}
+
+// every trail is drawn into this one mesh
+#[derive(Component)]
+pub struct TrailMesh;
 
-    let mut entity = commands.spawn((
-        ShapeBundle {
-            path,
-            ..default()
-        },
-        Stroke::new(color, 3.0),
-    ));
 
-    entity.insert(FuseTime{timer: Timer::from_seconds(trajectory.life_time, TimerMode::Once)} );
-    entity.insert(trajectory);
+pub fn system_record(
+    mut commands: Commands,
+    config: Res<TrailConfig>,
+    mut ball_q: Query<(Entity, &Transform, Option<&mut Trail>), With<Ball>>,
+) {
+    for (entity, t, trail) in ball_q.iter_mut() {
+        let Some(mut trail) = trail else {
+            commands.entity(entity).insert(Trail::default());
+            continue;
+        };
 
-    return entity.id();
+        if ! config.enabled {
+            trail.points.clear();
+            continue;
+        }
+
+        trail.points.push_back(t.translation.truncate());
+        while trail.points.len() > config.length {
+            trail.points.pop_front();
+        }
+    }
 }
 
 
+fn color(config: &TrailConfig,
+         species: Option<&Species>,
+         unit: Option<(&Unit, &Team)>,
+         zundamon: bool,
+         zombie: bool,
+         ) -> [f32; 3] {
+    let name = match (species, unit) {
+        (Some(species), _) => Some(species.0.as_str()),
+        (_, Some((unit, _))) => Some(unit.name.as_str()),
+        _ if zundamon => Some("zundamon"),
+        _ if zombie => Some("zombie"),
+        _ => None,
+    };
+    if let Some(color) = name.and_then(|name| config.colors.get(name)) {
+        return *color;
+    }
+    if let Some((_, team)) = unit {
+        let c = team.color();
+        return [c.r(), c.g(), c.b()];
+    }
+    config.color
+}
 
+// rebuilds the trail mesh, one quad per segment fading towards the tail
 pub fn system(
     mut commands: Commands,
-    time: Res<Time>,
-    mut trajectory_q: Query<(Entity, &mut FuseTime, &mut Stroke)>,
+    config: Res<TrailConfig>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    trail_mesh_q: Query<&Mesh2dHandle, With<TrailMesh>>,
+    ball_q: Query<(&Trail, Option<&Species>, Option<(&Unit, &Team)>, Option<&Zundamon>, Option<&Zombie>)>,
+) {
+    let mut quads = QuadMesh::default();
+
+    for (trail, species, unit, zundamon, zombie) in ball_q.iter() {
+        let [r, g, b] = color(&config, species, unit, zundamon.is_some(), zombie.is_some());
+        let n = trail.points.len();
+
+        for (i, (p0, p1)) in trail.points.iter().zip(trail.points.iter().skip(1)).enumerate() {
+            let Some(dir) = (*p1 - *p0).try_normalize() else { continue; };
+            let side = dir.perp() * config.width / 2.0;
+            let a0 = (i + 1) as f32 / n as f32 * MAX_ALPHA;
+            let a1 = (i + 2) as f32 / n as f32 * MAX_ALPHA;
+
+            quads.quad([(*p0 - side, [r, g, b, a0]), (*p0 + side, [r, g, b, a0]),
+                        (*p1 + side, [r, g, b, a1]), (*p1 - side, [r, g, b, a1])]);
+        }
+    }
+
+    quads.apply(&mut commands, &mut meshes, &mut materials, trail_mesh_q.get_single().ok(), 0.0, TrailMesh);
+}
+
+
+// trails don't outlive the game, a fresh mesh is made on the next one
+pub fn system_cleanup(
+    mut commands: Commands,
+    trail_mesh_q: Query<Entity, With<TrailMesh>>,
 ) {
-    for (entity, mut fuse_time, mut stroke) in trajectory_q.iter_mut() {
-        fuse_time.timer.tick(time.delta());
-        if fuse_time.timer.finished() { 
-            commands.entity(entity).despawn();
+    for entity in trail_mesh_q.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+
+
+// points of a ballistic flight starting at `origin`, sampled every `step` seconds
+pub fn arc(origin: Vec2, velocity: Vec2, gravity: Vec2, duration: f32, step: f32) -> Vec<Vec2> {
+    let mut points = vec![];
+    let mut t = 0.0;
+    while t <= duration {
+        points.push(origin + velocity * t + 0.5 * gravity * t * t);
+        t = t + step;
+    }
+    points
+}
+
+
+// Launch angle that hits `d` (relative to the muzzle) at `speed` under `gravity`,
+// taking the low arc. Returns the angle and the flight time, or None when out of reach.
+pub fn launch_angle(d: Vec2, speed: f32, gravity: Vec2) -> Option<(f32, f32)> {
+    let g = -gravity.y;
+    let x = d.x.abs();
+
+    if g.abs() < 0.001 || x < 0.001 {
+        let time = d.length() / speed;
+        let d = d - 0.5 * gravity * time * time;
+        return Some((d.y.atan2(d.x), time));
+    }
+
+    let v2 = speed * speed;
+    let discriminant = v2 * v2 - g * (g * x * x + 2.0 * d.y * v2);
+    if discriminant < 0.0 { return None; }
+
+    let theta = ((v2 - discriminant.sqrt()) / (g * x)).atan();
+    let time = x / (speed * theta.cos());
+    let angle = if d.x >= 0.0 { theta } else { std::f32::consts::PI - theta };
+
+    Some((angle, time))
+}
+
+// launch_angle against a target moving at constant velocity
+pub fn lead(origin: Vec2, target: Vec2, target_velocity: Vec2, speed: f32, gravity: Vec2) -> Option<(f32, f32, Vec2)> {
+    let mut time = 0.0;
+    let mut solution = None;
+
+    // each pass re-aims at where the target will be after the last flight time
+    for _ in 0..5 {
+        let predicted = target + target_velocity * time;
+        let (angle, t) = launch_angle(predicted - origin, speed, gravity)?;
+        solution = Some((angle, t, predicted));
+        time = t;
+    }
+    solution
+}
+
+
+const FILE_NAME: &str = "/trail.map";
+use crate::ev_save_load_world::LoadWorldEvent;
+pub fn load(
+    mut load_world_er: EventReader<LoadWorldEvent>,
+    mut config: ResMut<TrailConfig>,
+    ) {
+
+    for e in load_world_er.iter() {
+        let dir = e.0.clone();
+
+        let json_str = std::fs::read_to_string(dir + FILE_NAME);
+        if let Ok(json_str) = json_str {
+            *config = serde_json::from_str(&json_str).unwrap();
         } else {
-            let alpha = fuse_time.timer.percent_left();
-            stroke.color = stroke.color.with_a(alpha * MAX_ALPHA);
+            *config = TrailConfig::default();
         }
     }
 }
 
+
+use crate::ev_save_load_world::SaveWorldEvent;
+pub fn save(mut save_world_er: EventReader<SaveWorldEvent>,
+              config: Res<TrailConfig>,
+              ) {
+
+    for e in save_world_er.iter() {
+        let dir = e.0.clone();
+
+        std::fs::write(dir + FILE_NAME, serde_json::to_string(&*config).unwrap()).unwrap();
+    }
+}
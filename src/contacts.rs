@@ -0,0 +1,23 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+// Lookups over the pairs Rapier already found in its narrow phase. They
+// visit only what actually touches `entity`, so a sensor costs the same
+// with ten balls on the map as with thousands.
+
+// entities overlapping the sensor `entity` this frame
+pub fn intersecting(rapier_context: &RapierContext, entity: Entity) -> impl Iterator<Item = Entity> + '_ {
+    rapier_context.intersections_with(entity)
+        .filter(|(_, _, intersecting)| *intersecting)
+        .map(move |(e1, e2, _)| if e1 == entity { e2 } else { e1 })
+}
+
+// entities in active contact with the solid `entity` this frame
+pub fn touching(rapier_context: &RapierContext, entity: Entity) -> impl Iterator<Item = Entity> + '_ {
+    rapier_context.contacts_with(entity)
+        .filter(|contact_pair| contact_pair.has_any_active_contacts())
+        .map(move |contact_pair| {
+            let (e1, e2) = (contact_pair.collider1(), contact_pair.collider2());
+            if e1 == entity { e2 } else { e1 }
+        })
+}
@@ -3,6 +3,9 @@ use bevy_inspector_egui::prelude::*;
 
 use crate::cmp_primitive_shape;
 use crate::cmp_gate_generic;
+use crate::cmp_team::Team;
+use crate::cmp_artillery_manual;
+use crate::cmp_force_zone;
 
 #[derive(Resource, Reflect, FromReflect, Clone, Copy, PartialEq, Debug, Default, InspectorOptions)]
 #[reflect(Resource, InspectorOptions)]
@@ -19,18 +22,24 @@ pub enum EditTool { #[default] Select,
 pub enum MapObject {
     #[default]None,
     Artillery,
-    ArtilleryAutoP1,
-    ArtilleryAutoP2,
+    ArtilleryAuto(Team),
+    ArtilleryManual(Team, cmp_artillery_manual::Control),
     BlockZombie,
     ConverterBody,
+    Conveyor(Vec<Entity>, Vec<Vec2>),
+    ForceZone(cmp_force_zone::ForceKind),
     GearSimple,
     GearSorting,
     GearSwirl,
     GateGeneric,
+    GateMath(bool),
     GateSplitter(Vec<cmp_gate_generic::SpawnBall>),
+    GateSorter(Option<u32>),
     GateTeleport(Option<(u32, Color)>),
     GateZombie,
     GateZundamon,
+    GoalBucket,
+    KillZone,
     PadVelocity(Option<Vec2>),
     PadAcceleration(Option<Vec2>),
     PolygonalShape,
@@ -39,13 +48,13 @@ pub enum MapObject {
     SpawnTimer(Vec<cmp_gate_generic::SpawnBall>),
     SpriteObject(String),
     Wall,
-    BreakableP1(Vec<Entity>),
-    BreakableP2(Vec<Entity>),
-    BreakableSyncP1(Vec<Entity>),
-    BreakableSyncP2(Vec<Entity>),
+    Breakable(Vec<Entity>, Team),
+    BreakableSync(Vec<Entity>, Team),
     VibratingShape(Vec<Entity>),
     RotatingShape(Vec<Entity>),
     RevoluteJoint(Vec<Entity>),
+    PrismaticJoint(Vec<Entity>),
+    RopeJoint(Vec<Entity>),
     Zundamon,
 }
 
@@ -1,4 +1,5 @@
 use bevy::prelude::*;
+use bevy::ecs::system::EntityCommands;
 
 #[derive(Component)]
 pub struct Derrived;
@@ -6,3 +7,11 @@ pub struct Derrived;
 pub struct SaveWorldEvent(pub String);
 pub struct LoadWorldEvent(pub String);
 
+// The entity saved in a map as `index`, so a reload replaces it instead of
+// adding a second one, or a new entity for objects placed in the editor.
+pub fn get_or_spawn<'w, 's, 'a>(commands: &'a mut Commands<'w, 's>, index: Option<u32>) -> EntityCommands<'w, 's, 'a> {
+    match index {
+        Some(i) => commands.get_or_spawn(Entity::from_raw(i)),
+        None => commands.spawn_empty(),
+    }
+}
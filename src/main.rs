@@ -21,27 +21,33 @@ use crate::cmp_artillery::Artillery;
 mod cmp_artillery_auto;
 use crate::cmp_artillery_auto::ArtilleryAuto;
 
+mod cmp_artillery_manual;
+use crate::cmp_artillery_manual::ArtilleryManual;
+
 mod cmp_bbsize;
 use crate::cmp_bbsize::BBSize;
 
 mod cmp_ball;
 mod cmp_ball_bomb;
-mod cmp_ball_type1;
-mod cmp_ball_type2;
-mod cmp_ball_type3;
-mod cmp_ball_type4;
 mod cmp_explosion;
 mod cmp_ball_zundamon;
 mod cmp_ball_zombie;
-mod cmp_blood;
+mod cmp_ball_species;
+use crate::cmp_ball_species::BallSpeciesRegistry;
+mod cmp_ball_behavior;
+mod cmp_particle;
+mod cmp_sprite_animation;
 
 mod cmp_block_zombie;
 use crate::cmp_block_zombie::BlockZombie;
 
 mod cmp_combat;
+mod cmp_combat_unit;
+mod cmp_team;
+use crate::cmp_team::Team;
+use crate::cmp_team::TeamConfig;
+use crate::cmp_combat_unit::UnitRegistry;
 use crate::cmp_combat::Status;
-use crate::cmp_combat::Player1;
-use crate::cmp_combat::Player2;
 
 mod cmp_converter_body;
 use crate::cmp_converter_body::ConverterBody;
@@ -59,7 +65,11 @@ mod cmp_gate_splitter;
 use crate::cmp_gate_splitter::GateSplitter;
 use crate::cmp_gate_splitter::GateSplitterBundle;
 
+mod cmp_gate_math;
+mod cmp_gate_sorter;
+
 mod cmp_gate_teleport;
+mod cmp_goal;
 use crate::cmp_gate_teleport::GateTeleportExit;
 use crate::cmp_gate_teleport::GateTeleportEntrance;
 
@@ -81,6 +91,9 @@ mod cmp_pad_velocity;
 use crate::cmp_pad_velocity::PadVelocity;
 
 mod cmp_pad_acceleration;
+mod cmp_force_zone;
+mod cmp_cleanup;
+mod cmp_conveyor;
 use crate::cmp_pad_acceleration::PadAcceleration;
 
 mod cmp_polygonal_shape;
@@ -91,9 +104,17 @@ mod cmp_primitive_shape;
 use crate::cmp_primitive_shape::PrimitiveShape;
 use crate::cmp_primitive_shape::PrimitiveShapeBundle;
 
+mod cmp_joint;
+use crate::cmp_joint::DelayLoadJoint;
+
 mod cmp_revolute_joint;
-use crate::cmp_revolute_joint::{RevoluteJoint, delay_load};
-use crate::cmp_revolute_joint::DelayLoadRevoluteJoint;
+use crate::cmp_revolute_joint::RevoluteJoint;
+
+mod cmp_prismatic_joint;
+use crate::cmp_prismatic_joint::PrismaticJoint;
+
+mod cmp_rope_joint;
+use crate::cmp_rope_joint::RopeJoint;
 
 mod cmp_rotator;
 use crate::cmp_rotator::Rotator;
@@ -118,21 +139,29 @@ use crate::cmp_wall::WallBundle;
 
 mod cmp_breakable;
 use crate::cmp_breakable::Breakable;
-use crate::cmp_breakable::BreakableP1Bundle;
-use crate::cmp_breakable::BreakableP2Bundle;
+use crate::cmp_breakable::BreakableBundle;
 
 mod cmp_breakable_sync;
+mod cmp_debris;
 use crate::cmp_breakable_sync::BreakableSync;
-use crate::cmp_breakable_sync::BreakableSyncP1Bundle;
-use crate::cmp_breakable_sync::BreakableSyncP2Bundle;
+use crate::cmp_breakable_sync::BreakableSyncBundle;
 
 mod cmp_trajectory;
-use crate::cmp_trajectory::Trajectory;
 
 mod cmp_zunda_counter;
 
 mod cmp_zundamon_fullbody;
 
+mod cmp_ai;
+mod cmp_economy;
+use crate::cmp_economy::EconomyConfig;
+
+mod cmp_headless;
+mod cmp_bench;
+mod cmp_match;
+use crate::cmp_match::MatchRules;
+use crate::cmp_match::MatchState;
+
 mod cmp_main_camera;
 use crate::cmp_main_camera::MainCamera;
 //use crate::cmp_gate_zundamon;
@@ -145,6 +174,9 @@ use crate::ev_save_load_world::LoadWorldEvent;
 mod ev_despawn;
 use ev_despawn::Despawn;
 
+mod child_label;
+mod contacts;
+mod quad_mesh;
 mod edit_context;
 use crate::edit_context::*;
 
@@ -152,7 +184,7 @@ use crate::edit_context::*;
 pub struct Map;
 
 #[derive(Clone, Copy, Eq, PartialEq, Hash, Debug, Default, States)]
-enum AppState { #[default] Edit, Game}
+enum AppState { #[default] Edit, Game, Rematch}
 
 #[derive(Resource)]
 pub struct EguiWindowClicked(bool);
@@ -162,8 +194,14 @@ use bevy_inspector_egui::quick::WorldInspectorPlugin;
 //use bevy_inspector_egui::quick::FilterQueryInspectorPlugin;
 use bevy_inspector_egui::quick::ResourceInspectorPlugin;
 
-    App::new()
-        .add_plugins(DefaultPlugins.set(WindowPlugin {
+    let mut app = App::new();
+    if let Some(headless) = cmp_headless::Headless::from_args() {
+        cmp_headless::add_plugins(&mut app, headless);
+        if let Some(bench) = cmp_bench::Bench::from_args() {
+            app.insert_resource(bench);
+        }
+    } else {
+        app.add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
                 title: "Zunda shower".into(),
                 resolution: (constants::C_WINDOW_SIZE_X, constants::C_WINDOW_SIZE_Y).into(),
@@ -171,7 +209,10 @@ use bevy_inspector_egui::quick::ResourceInspectorPlugin;
                 ..default()
             }),
             ..default()
-        }))
+        }));
+    }
+
+    app
         .add_plugin(EguiPlugin)
         .add_plugin(ShapePlugin)
         .add_plugin(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(100.0))
@@ -188,18 +229,61 @@ use bevy_inspector_egui::quick::ResourceInspectorPlugin;
         .add_system(setup_graphics.on_startup())
         .add_system(setup_sounds.on_startup())
         .add_system(setup_fonts.on_startup())
+        .insert_resource(cmp_sprite_animation::Clips::default())
+        .add_system(cmp_sprite_animation::setup.after(setup_graphics).on_startup())
+        .insert_resource(BallSpeciesRegistry::default())
+        .add_system(cmp_ball_species::setup.after(setup_graphics).on_startup())
+        .insert_resource(UnitRegistry::default())
+        .add_system(cmp_combat_unit::setup.after(setup_graphics).on_startup())
 
         .add_system(setup_physics.in_schedule(OnEnter(AppState::Edit)))
         //.add_system(game_mode_select.in_set(OnUpdate(AppState::Edit)))
-        .add_system(game_mode_select)
+        .add_system(game_mode_select.run_if(cmp_headless::windowed))
         .add_system(spawn_map_object.in_set(OnUpdate(AppState::Edit))
                                             .before(handle_user_input))
         .insert_resource(WorldPosition { translation: Vec2::ZERO })
-        .add_system(edit_context::update_world_position)
+        .add_system(edit_context::update_world_position.run_if(cmp_headless::windowed))
         .add_system(handle_user_input.in_set(OnUpdate(AppState::Edit)))
 
         .add_system(setup_ui.in_schedule(OnEnter(AppState::Game)))
 
+        .insert_resource(MatchRules::default())
+        // headless runs already put one in that loads their map
+        .init_resource::<MatchState>()
+        .add_system(cmp_match::load)
+        .add_system(cmp_match::save)
+        .add_system(cmp_match::add.in_schedule(OnEnter(AppState::Game)))
+        .add_system(cmp_match::system_setup.in_schedule(OnEnter(AppState::Game)))
+        .add_system(cmp_match::system.in_set(OnUpdate(AppState::Game)))
+        .add_system(cmp_match::system_pause.in_set(OnUpdate(AppState::Game)))
+        .add_system(cmp_match::system_text.in_set(OnUpdate(AppState::Game)))
+        .add_system(cmp_match::system_rematch)
+
+        .insert_resource(EconomyConfig::default())
+        .insert_resource(cmp_economy::Funds::default())
+        .add_system(cmp_economy::load)
+        .add_system(cmp_economy::save)
+        .add_system(cmp_economy::add.in_schedule(OnEnter(AppState::Game)))
+        .add_system(cmp_economy::system_setup.in_schedule(OnEnter(AppState::Game)))
+        .add_system(cmp_economy::system_income.in_set(OnUpdate(AppState::Game)).run_if(cmp_match::playing))
+        .add_system(cmp_economy::system_keyboard.in_set(OnUpdate(AppState::Game)).run_if(cmp_match::playing))
+        .add_system(cmp_economy::system_script.in_set(OnUpdate(AppState::Game)).run_if(cmp_match::playing))
+        .add_system(cmp_economy::system_text.in_set(OnUpdate(AppState::Game)))
+        .add_system(cmp_ai::system.in_set(OnUpdate(AppState::Game)).run_if(cmp_match::playing))
+
+        .add_event::<cmp_match::RequestRematch>()
+        .add_system(cmp_headless::system_setup.in_schedule(OnEnter(AppState::Game))
+                                               .run_if(cmp_headless::headless)
+                                               .before(cmp_match::system_setup))
+        .add_system(cmp_headless::system.in_set(OnUpdate(AppState::Game))
+                                        .run_if(cmp_headless::headless)
+                                        .after(cmp_match::system))
+        .add_system(cmp_bench::system_setup.in_schedule(OnEnter(AppState::Game))
+                                           .run_if(cmp_bench::bench)
+                                           .after(cmp_match::system_setup))
+        .add_system(cmp_bench::system.in_set(OnUpdate(AppState::Game))
+                                     .run_if(cmp_bench::bench))
+
         .add_event::<Despawn>()
         .add_event::<SaveWorldEvent>()
         .add_event::<LoadWorldEvent>()
@@ -208,40 +292,56 @@ use bevy_inspector_egui::quick::ResourceInspectorPlugin;
         //.add_system(bdl_rotating_shape::save)
 
         //.add_system(cmp_ball::system_remove.in_set(OnUpdate(AppState::Game)))
-        .add_system(cmp_ball::system_trajectory.in_set(OnUpdate(AppState::Game)))
-        .add_system(cmp_ball_bomb::system::<Player1>.in_set(OnUpdate(AppState::Game)))
-        .add_system(cmp_ball_bomb::system::<Player2>.in_set(OnUpdate(AppState::Game)))
-        .add_system(cmp_ball_bomb::system_ignition::<Player1>.in_set(OnUpdate(AppState::Game)))
-        .add_system(cmp_ball_bomb::system_ignition::<Player2>.in_set(OnUpdate(AppState::Game)))
-        .add_system(cmp_ball_type1::system.in_set(OnUpdate(AppState::Game)))
-        .add_system(cmp_ball_type2::system.in_set(OnUpdate(AppState::Game)))
-        .add_system(cmp_ball_type3::system.in_set(OnUpdate(AppState::Game)))
-        .add_system(cmp_ball_type4::system.in_set(OnUpdate(AppState::Game)))
+        .add_system(cmp_ball_bomb::system_ignition.in_set(OnUpdate(AppState::Game)))
+        .add_event::<cmp_combat_unit::UnitDied>()
+        .add_system(cmp_combat_unit::system_death.in_set(OnUpdate(AppState::Game)))
         .add_system(cmp_explosion::system.in_set(OnUpdate(AppState::Game)))
-        .add_system(cmp_explosion::system_damage1.in_set(OnUpdate(AppState::Game)))
-        .add_system(cmp_explosion::system_damage2.in_set(OnUpdate(AppState::Game)))
-        //.add_system(cmp_ball_zombie::system_infection.in_set(OnUpdate(AppState::Game)))
-
-        .add_system(cmp_blood::system.in_set(OnUpdate(AppState::Game)))
+        .add_system(cmp_explosion::system_damage.in_set(OnUpdate(AppState::Game)))
+        .add_system(cmp_explosion::system_blast.in_set(OnUpdate(AppState::Game)))
+        .add_system(cmp_ball_behavior::system_split.in_set(OnUpdate(AppState::Game)))
+        .add_system(cmp_ball_behavior::system_sticky.in_set(OnUpdate(AppState::Game)))
+        .add_system(cmp_ball_behavior::system_infection.in_set(OnUpdate(AppState::Game)))
+
+        .insert_resource(cmp_particle::Particles::default())
+        .add_system(cmp_particle::system_explosion.in_set(OnUpdate(AppState::Game))
+                                                  .run_if(cmp_headless::windowed))
+        .add_system(cmp_particle::system_emit.in_set(OnUpdate(AppState::Game))
+                                             .run_if(cmp_headless::windowed))
+        .add_system(cmp_particle::system.in_set(OnUpdate(AppState::Game))
+                                        .run_if(cmp_headless::windowed)
+                                        .after(cmp_particle::system_emit))
+        .add_system(cmp_particle::system_mesh.in_set(OnUpdate(AppState::Game))
+                                             .run_if(cmp_headless::windowed)
+                                             .after(cmp_particle::system))
+        .add_system(cmp_particle::system_cleanup.in_schedule(OnExit(AppState::Game)))
 
         .register_type::<Artillery>()
-        .add_system(cmp_artillery::handle_user_input)
+        .add_system(cmp_artillery::handle_user_input.in_set(OnUpdate(AppState::Edit)))
         .add_system(cmp_artillery::load)
         .add_system(cmp_artillery::save)
         .add_system(cmp_artillery::system.in_set(OnUpdate(AppState::Game)))
         .add_system(cmp_artillery::system_fire.in_set(OnUpdate(AppState::Game)))
 
         .register_type::<ArtilleryAuto>()
-        .add_system(cmp_artillery_auto::handle_user_input)
-        .add_system(cmp_artillery_auto::load::<Player1>)
-        .add_system(cmp_artillery_auto::save::<Player1>)
-        .add_system(cmp_artillery_auto::load::<Player2>)
-        .add_system(cmp_artillery_auto::save::<Player2>)
+        .add_system(cmp_artillery_auto::handle_user_input.in_set(OnUpdate(AppState::Edit)))
+        .add_system(cmp_artillery_auto::load)
+        .add_system(cmp_artillery_auto::save)
         .add_system(cmp_artillery_auto::despawn)
-        .add_system(cmp_artillery_auto::system::<Player1, Player2>.in_set(OnUpdate(AppState::Game)))
-        .add_system(cmp_artillery_auto::system::<Player2, Player1>.in_set(OnUpdate(AppState::Game)))
+        .add_system(cmp_artillery_auto::edit_window.in_set(OnUpdate(AppState::Edit)))
+        .add_system(cmp_artillery_auto::system.in_set(OnUpdate(AppState::Game)))
         .add_system(cmp_artillery_auto::system_fire.in_set(OnUpdate(AppState::Game)))
 
+        .register_type::<ArtilleryManual>()
+        .add_system(cmp_artillery_manual::handle_user_input.in_set(OnUpdate(AppState::Edit)))
+        .add_system(cmp_artillery_manual::load)
+        .add_system(cmp_artillery_manual::save)
+        .add_system(cmp_artillery_manual::despawn)
+        .add_system(cmp_artillery_manual::system.in_set(OnUpdate(AppState::Game)).run_if(cmp_match::playing))
+        .add_system(cmp_artillery_manual::system_ui.in_set(OnUpdate(AppState::Game)))
+        .add_system(cmp_artillery_manual::system_cleanup.in_schedule(OnExit(AppState::Game)))
+        .add_system(cmp_ball_bomb::system_piercing.in_set(OnUpdate(AppState::Game)))
+        .add_system(cmp_ball_bomb::system_homing.in_set(OnUpdate(AppState::Game)))
+
         .register_type::<BBSize>()
 
         .register_type::<BlockZombie>()
@@ -249,7 +349,23 @@ use bevy_inspector_egui::quick::ResourceInspectorPlugin;
         .add_system(cmp_block_zombie::save)
 
         .register_type::<Status>()
+        .register_type::<Team>()
+        .insert_resource(TeamConfig::default())
+        .add_system(cmp_team::load)
+        .add_system(cmp_team::save)
+        .add_system(cmp_team::system_collision_groups)
+        .insert_resource(cmp_combat::CombatConfig::default())
+        .add_system(cmp_combat::load)
+        .add_system(cmp_combat::save)
         .add_system(cmp_combat::system)
+        .add_system(cmp_combat::system_effects.in_set(OnUpdate(AppState::Game)))
+        .add_system(cmp_combat::system_effect_color.in_set(OnUpdate(AppState::Game)))
+        .add_system(cmp_sprite_animation::system_character.in_set(OnUpdate(AppState::Game))
+                                                          .after(cmp_combat::system_effect_color)
+                                                          .before(cmp_sprite_animation::system))
+        .add_system(cmp_sprite_animation::system.in_set(OnUpdate(AppState::Game)))
+        .add_system(cmp_sprite_animation::system_corpse.in_set(OnUpdate(AppState::Game)))
+        .add_system(cmp_sprite_animation::system_cleanup.in_schedule(OnExit(AppState::Game)))
 
         .register_type::<ConverterBody>()
         .add_system(cmp_converter_body::load)
@@ -272,11 +388,45 @@ use bevy_inspector_egui::quick::ResourceInspectorPlugin;
         .add_system(cmp_gate_splitter::save)
         .add_system(cmp_gate_splitter::system)
 
+        .register_type::<cmp_gate_math::GateMath>()
+        .add_system(cmp_gate_math::handle_user_input)
+        .add_system(cmp_gate_math::load)
+        .add_system(cmp_gate_math::save)
+        .add_system(cmp_gate_math::system_setup.in_schedule(OnEnter(AppState::Game)))
+        .add_system(cmp_gate_math::system.in_set(OnUpdate(AppState::Game)))
+        .add_system(cmp_gate_math::system_label)
+        .add_system(cmp_gate_math::edit_window.in_set(OnUpdate(AppState::Edit)))
+
+        .register_type::<cmp_gate_sorter::GateSorter>()
+        .add_system(cmp_gate_sorter::handle_user_input)
+        .add_system(cmp_gate_sorter::load)
+        .add_system(cmp_gate_sorter::save)
+        .add_system(cmp_gate_sorter::system.in_set(OnUpdate(AppState::Game)))
+        .add_system(cmp_gate_sorter::edit_window.in_set(OnUpdate(AppState::Edit)))
+
+        .register_type::<cmp_goal::GoalBucket>()
+        .insert_resource(cmp_goal::Score::default())
+        .add_event::<cmp_goal::GoalReached>()
+        .add_system(cmp_goal::load)
+        .add_system(cmp_goal::save)
+        .add_system(cmp_goal::add_text.in_schedule(OnEnter(AppState::Game)))
+        .add_system(cmp_goal::system_setup.in_schedule(OnEnter(AppState::Game)))
+        .add_system(cmp_goal::save_best.in_schedule(OnExit(AppState::Game)))
+        .add_system(cmp_goal::system.in_set(OnUpdate(AppState::Game)).run_if(cmp_match::playing))
+        .add_system(cmp_goal::system_flash.in_set(OnUpdate(AppState::Game)))
+        .add_system(cmp_goal::system_lost.in_set(OnUpdate(AppState::Game)))
+        .add_system(cmp_goal::system_label)
+        // not only in game, so popups still fade out after it
+        .add_system(cmp_goal::system_popup)
+        .add_system(cmp_goal::system_text.in_set(OnUpdate(AppState::Game)))
+        .add_system(cmp_goal::edit_window.in_set(OnUpdate(AppState::Edit)))
+
         .register_type::<GateTeleportExit>()
         .register_type::<GateTeleportEntrance>()
         .add_system(cmp_gate_teleport::load)
         .add_system(cmp_gate_teleport::save)
         .add_system(cmp_gate_teleport::system.in_set(OnUpdate(AppState::Game)))
+        .add_system(cmp_gate_teleport::edit_window.in_set(OnUpdate(AppState::Edit)))
 
         .register_type::<GateZombie>()
         .add_system(cmp_gate_zombie::load)
@@ -300,10 +450,30 @@ use bevy_inspector_egui::quick::ResourceInspectorPlugin;
         .add_system(cmp_pad_acceleration::load)
         .add_system(cmp_pad_acceleration::save)
         .add_system(cmp_pad_acceleration::system.in_set(OnUpdate(AppState::Game)))
+        .register_type::<cmp_force_zone::ForceZone>()
+        .add_system(cmp_force_zone::load)
+        .add_system(cmp_force_zone::save)
+        .add_system(cmp_force_zone::system.in_set(OnUpdate(AppState::Game)))
+        .add_system(cmp_force_zone::edit_window.in_set(OnUpdate(AppState::Edit)))
+        .register_type::<cmp_cleanup::KillZone>()
+        .insert_resource(cmp_cleanup::CleanupConfig::default())
+        .add_event::<cmp_cleanup::BallRemoved>()
+        .add_system(cmp_cleanup::load)
+        .add_system(cmp_cleanup::save)
+        .add_system(cmp_cleanup::system.in_set(OnUpdate(AppState::Game)))
+
+        .register_type::<cmp_conveyor::Conveyor>()
+        .add_system(cmp_conveyor::load)
+        .add_system(cmp_conveyor::save)
+        .add_system(cmp_conveyor::system.in_set(OnUpdate(AppState::Game)))
+        .add_system(cmp_conveyor::system_stripes)
+        .add_system(cmp_conveyor::system_signal.in_set(OnUpdate(AppState::Game)))
+        .add_system(cmp_conveyor::edit_window.in_set(OnUpdate(AppState::Edit)))
 
         .register_type::<PolygonalShape>()
         .add_system(cmp_polygonal_shape::load)
         .add_system(cmp_polygonal_shape::save)
+        .add_system(cmp_polygonal_shape::system_carve.in_set(OnUpdate(AppState::Game)))
 
         .register_type::<PrimitiveShape>()
         .add_system(cmp_primitive_shape::load)
@@ -317,33 +487,49 @@ use bevy_inspector_egui::quick::ResourceInspectorPlugin;
 
         .register_type::<Breakable>()
         .add_system(cmp_breakable::handle_user_input)
-        .add_system(cmp_breakable::system_damage::<Player1, Player2>)
-        .add_system(cmp_breakable::system_damage::<Player2, Player1>)
+        .add_system(cmp_breakable::system_damage)
+        .add_system(cmp_breakable::system_break)
         .add_system(cmp_breakable::system_color)
-        .add_system(cmp_breakable::load_p1)
-        .add_system(cmp_breakable::load_p2)
-        .add_system(cmp_breakable::save_p1)
-        .add_system(cmp_breakable::save_p2)
+        .add_system(cmp_breakable::load)
+        .add_system(cmp_breakable::save)
 
         .register_type::<BreakableSync>()
         .add_system(cmp_breakable_sync::handle_user_input)
-        .add_system(cmp_breakable_sync::system_damage::<Player1, Player2>)
-        .add_system(cmp_breakable_sync::system_damage::<Player2, Player1>)
+        .add_system(cmp_breakable_sync::system_damage)
+        .add_system(cmp_breakable_sync::system_break)
         .add_system(cmp_breakable_sync::system_color)
-        .add_system(cmp_breakable_sync::load_p1)
-        .add_system(cmp_breakable_sync::load_p2)
-        .add_system(cmp_breakable_sync::save_p1)
-        .add_system(cmp_breakable_sync::save_p2)
+        .insert_resource(cmp_debris::DebrisConfig::default())
+        .add_system(cmp_debris::load)
+        .add_system(cmp_debris::save)
+        .add_system(cmp_debris::system.in_set(OnUpdate(AppState::Game)))
+        .add_system(cmp_debris::system_cracks.in_set(OnUpdate(AppState::Game)))
+        .add_system(cmp_breakable_sync::load)
+        .add_system(cmp_breakable_sync::save)
+
+        .add_event::<DelayLoadJoint>()
+        .add_system(cmp_joint::delay_load)
 
         .register_type::<RevoluteJoint>()
-        .add_event::<DelayLoadRevoluteJoint>()
         .add_system(cmp_revolute_joint::handle_user_input)
+        .add_system(cmp_revolute_joint::edit_window.in_set(OnUpdate(AppState::Edit)))
         .add_system(cmp_revolute_joint::system)
-        .add_system(cmp_revolute_joint::load.before(
-                        cmp_revolute_joint::delay_load))
-        .add_system(cmp_revolute_joint::delay_load)
+        .add_system(cmp_revolute_joint::load.before(cmp_joint::delay_load))
         .add_system(cmp_revolute_joint::save)
 
+        .register_type::<PrismaticJoint>()
+        .add_system(cmp_prismatic_joint::handle_user_input)
+        .add_system(cmp_prismatic_joint::edit_window.in_set(OnUpdate(AppState::Edit)))
+        .add_system(cmp_prismatic_joint::system)
+        .add_system(cmp_prismatic_joint::load.before(cmp_joint::delay_load))
+        .add_system(cmp_prismatic_joint::save)
+
+        .register_type::<RopeJoint>()
+        .add_system(cmp_rope_joint::handle_user_input)
+        .add_system(cmp_rope_joint::edit_window.in_set(OnUpdate(AppState::Edit)))
+        .add_system(cmp_rope_joint::system)
+        .add_system(cmp_rope_joint::load.before(cmp_joint::delay_load))
+        .add_system(cmp_rope_joint::save)
+
         .register_type::<Rotator>()
         .add_system(cmp_rotator::system.in_set(OnUpdate(AppState::Game)))
         .add_system(cmp_rotator::load)
@@ -360,15 +546,25 @@ use bevy_inspector_egui::quick::ResourceInspectorPlugin;
         .add_system(cmp_spawn_timer::load)
         .add_system(cmp_spawn_timer::save)
         .add_system(cmp_spawn_timer::system_setup.in_schedule(OnEnter(AppState::Game)))
-        .add_system(cmp_spawn_timer::system.in_set(OnUpdate(AppState::Game)))
+        .add_system(cmp_spawn_timer::system.in_set(OnUpdate(AppState::Game)).run_if(cmp_match::playing))
+        .add_system(cmp_spawn_timer::system_edit_setup.in_schedule(OnEnter(AppState::Edit)))
+        .add_system(cmp_spawn_timer::edit_window.in_set(OnUpdate(AppState::Edit)))
+        .add_system(cmp_spawn_timer::timeline_window.in_set(OnUpdate(AppState::Edit)))
 
         .register_type::<SpriteObject>()
         .add_system(cmp_sprite_object::handle_user_input)
         .add_system(cmp_sprite_object::load)
         .add_system(cmp_sprite_object::save)
 
-        .register_type::<Trajectory>()
-        .add_system(cmp_trajectory::system.in_set(OnUpdate(AppState::Game)))
+        .insert_resource(cmp_trajectory::TrailConfig::default())
+        .add_system(cmp_trajectory::load)
+        .add_system(cmp_trajectory::save)
+        .add_system(cmp_trajectory::system_record.in_set(OnUpdate(AppState::Game))
+                                                 .run_if(cmp_headless::windowed))
+        .add_system(cmp_trajectory::system.in_set(OnUpdate(AppState::Game))
+                                          .run_if(cmp_headless::windowed)
+                                          .after(cmp_trajectory::system_record))
+        .add_system(cmp_trajectory::system_cleanup.in_schedule(OnExit(AppState::Game)))
 
         .register_type::<Vibrator>()
         .add_system(cmp_vibrator::system.in_set(OnUpdate(AppState::Game)))
@@ -377,9 +573,9 @@ use bevy_inspector_egui::quick::ResourceInspectorPlugin;
 
         //.add_system(cmp_zunda_counter::system.in_set(OnUpdate(AppState::Game)))
 
-        .add_system(cmp_main_camera::move_camera)
+        .add_system(cmp_main_camera::move_camera.run_if(cmp_headless::windowed))
         //.add_system(cmp_main_camera::auto_camera)
-        .add_system(cmp_main_camera::auto_camera_vertical)
+        .add_system(cmp_main_camera::auto_camera_vertical.run_if(cmp_headless::windowed))
 
         .run();
 }
@@ -444,7 +640,15 @@ fn setup_graphics(mut commands: Commands, mut image_assets: ResMut<Assets<Image>
     let image_mappings = [
         (include_bytes!("../assets/map_element/plus_one.png").as_slice(), "plus_one_handle"),
         (include_bytes!("../assets/map_element/bomb.png").as_slice(), "bomb_handle"),
-        (include_bytes!("../assets/map_element/explosion/explosion00.png").as_slice(), "explosion_handle"),
+        (include_bytes!("../assets/map_element/explosion/explosion00.png").as_slice(), "explosion00_handle"),
+        (include_bytes!("../assets/map_element/explosion/explosion01.png").as_slice(), "explosion01_handle"),
+        (include_bytes!("../assets/map_element/explosion/explosion02.png").as_slice(), "explosion02_handle"),
+        (include_bytes!("../assets/map_element/explosion/explosion03.png").as_slice(), "explosion03_handle"),
+        (include_bytes!("../assets/map_element/explosion/explosion04.png").as_slice(), "explosion04_handle"),
+        (include_bytes!("../assets/map_element/explosion/explosion05.png").as_slice(), "explosion05_handle"),
+        (include_bytes!("../assets/map_element/explosion/explosion06.png").as_slice(), "explosion06_handle"),
+        (include_bytes!("../assets/map_element/explosion/explosion07.png").as_slice(), "explosion07_handle"),
+        (include_bytes!("../assets/map_element/explosion/explosion08.png").as_slice(), "explosion08_handle"),
         (include_bytes!("../assets/map_element/zun1.png").as_slice(), "zun1_handle"),
         (include_bytes!("../assets/map_element/zun2.png").as_slice(), "zun2_handle"),
         (include_bytes!("../assets/map_element/zun3.png").as_slice(), "zun3_handle"),
@@ -796,16 +1000,10 @@ fn handle_user_input(
                                         saturation: rng.gen_range(0.0..1.0),
                                         lightness: 0.5,
                                         alpha: 1.0 };
-                                    let gtent = GateTeleportEntrance {
-                                        id,
-                                        size: Vec2::new(16.0, 16.0),
-                                        position: world_position,
-                                        color,
-                                    };
+                                    let gtent = GateTeleportEntrance::new(id, Vec2::new(16.0, 16.0), world_position, color);
 
                                     println!("GateTeleport entrance added {:?}", gtent);
-                                    let entity = cmp_gate_teleport::add_entrance(&mut commands, gtent);
-                                    commands.entity(entity).insert(MapObject::GateTeleport(ctx));
+                                    cmp_gate_teleport::add_entrance(&mut commands, gtent);
                                     *edit_context = EditContext::Spawn(MapObject::GateTeleport(Some((id, color))));
 
                                 } else {
@@ -815,11 +1013,11 @@ fn handle_user_input(
                                         size: Vec2::new(16.0, 16.0),
                                         position: world_position,
                                         color,
+                                        angle: 0.0,
                                     };
 
                                     println!("GateTeleport exit added {:?}", gtext);
                                     let entity = cmp_gate_teleport::add_exit(&mut commands, gtext);
-                                    commands.entity(entity).insert(MapObject::GateTeleport(None));
                                     *edit_context = EditContext::Edit(MapObject::GateTeleport(None), vec![entity], EditTool::Select);
 
                                 }
@@ -902,6 +1100,40 @@ fn handle_user_input(
                             }
                         }
 
+                        MapObject::KillZone => {
+                            if buttons.just_pressed(MouseButton::Left) {
+                                let kill_zone = cmp_cleanup::KillZone {
+                                    size: Vec2::ONE * cmp_cleanup::KILL_ZONE_SIZE,
+                                    position: world_position,
+                                    angle: 0.0,
+                                };
+                                let entity = cmp_cleanup::add(&mut commands, None, kill_zone);
+                                *edit_context = EditContext::Edit(MapObject::KillZone, vec![entity], EditTool::Select);
+                            }
+                        }
+
+                        MapObject::GoalBucket => {
+                            if buttons.just_pressed(MouseButton::Left) {
+                                let bucket = cmp_goal::GoalBucket { multiplier: 1 };
+                                let entity = cmp_goal::add(&mut commands, None,
+                                                           Vec3::from((world_position, 0.0)), Quat::IDENTITY, Vec3::ONE, bucket);
+                                *edit_context = EditContext::Edit(MapObject::GoalBucket, vec![entity], EditTool::Select);
+                            }
+                        }
+
+                        MapObject::ForceZone(kind) => {
+                            if buttons.just_pressed(MouseButton::Left) {
+                                let fz = cmp_force_zone::ForceZone {
+                                    size: Vec2::ONE * cmp_force_zone::DEFAULT_SIZE,
+                                    position: world_position,
+                                    angle: 0.0,
+                                    kind,
+                                };
+                                let entity = cmp_force_zone::add(&mut commands, None, fz);
+                                *edit_context = EditContext::Edit(MapObject::ForceZone(kind), vec![entity], EditTool::Select);
+                            }
+                        }
+
                         MapObject::PrimitiveShape(shape) => {
                             if buttons.just_pressed(MouseButton::Left) {
                                 let t = Vec3::from((world_position, 0.0));
@@ -962,6 +1194,52 @@ fn handle_user_input(
 
                         }
 
+                        MapObject::Conveyor(entities, polyline) => {
+                            if buttons.just_pressed(MouseButton::Left) {
+                                let mut entities: Vec<Entity> = entities.to_vec();
+                                let mut polyline: Vec<Vec2> = polyline.to_vec();
+
+                                let entity = commands
+                                    .spawn(SpriteBundle {
+                                            sprite: Sprite {
+                                                color: Color::DARK_GRAY,
+                                                custom_size: Some(Vec2::new(8.0, 8.0)),
+                                                ..Default::default()
+                                            },
+                                            ..Default::default()
+                                        })
+                                    .insert(TransformBundle::from(Transform::from_translation(Vec3::from((world_position, 0.0)))))
+                                    .id();
+
+                                polyline.push(world_position);
+                                entities.push(entity);
+
+                                *edit_context = EditContext::Spawn(MapObject::Conveyor(entities, polyline));
+
+                            } else if buttons.just_pressed(MouseButton::Right) {
+                                for e in entities {
+                                    commands.entity(e).despawn();
+                                }
+
+                                if polyline.len() > 1 {
+                                    let center = center(&polyline);
+                                    let conveyor = cmp_conveyor::Conveyor {
+                                        polyline: polyline.iter().map(|p| *p - center).collect(),
+                                        speed: cmp_conveyor::DEFAULT_SPEED,
+                                        width: cmp_conveyor::DEFAULT_WIDTH,
+                                    };
+                                    let entity = cmp_conveyor::add(&mut commands, None,
+                                                                  Vec3::from((center, 0.0)),
+                                                                  Quat::from_rotation_z(0.0),
+                                                                  Vec3::ONE,
+                                                                  conveyor);
+                                    *edit_context = EditContext::Edit(MapObject::Conveyor(vec![], vec![]), vec![entity], EditTool::Select);
+                                } else {
+                                    *edit_context = EditContext::Edit(MapObject::None, vec![], EditTool::Select);
+                                }
+                            }
+                        }
+
                         MapObject::Zundamon => {
                             if buttons.just_pressed(MouseButton::Left) {
                                 let entity = commands.spawn(
@@ -1042,14 +1320,14 @@ fn debug_spawn (
             if ui.button("o").clicked() {
                 if let EditContext::Edit(MapObject::GateGeneric, pick, edit_tool) = edit_mode.clone() {
                     let entity = pick[0];
-                    event.send(cmp_gate_generic::SpawnBall (entity.index(), cmp_gate_generic::BallType::Type1P1));
+                    event.send(cmp_gate_generic::SpawnBall (entity.index(), cmp_gate_generic::BallType::Unit(String::from("type1"), Team(0))));
                     println!("send event");
                 }
             }
             if ui.button("o").clicked() {
                 if let EditContext::Edit(MapObject::GateGeneric, pick, edit_tool) = edit_mode.clone() {
                     let entity = pick[0];
-                    event.send(cmp_gate_generic::SpawnBall (entity.index(), cmp_gate_generic::BallType::Type1P2));
+                    event.send(cmp_gate_generic::SpawnBall (entity.index(), cmp_gate_generic::BallType::Unit(String::from("type1"), Team(1))));
                     println!("send event");
                 }
             }
@@ -1063,6 +1341,18 @@ fn spawn_map_object (
     mut edit_mode: ResMut<EditContext>,
     mut window_clicked: ResMut<EguiWindowClicked>,
     mut handle_name: Local<String>,
+    mut species_name: Local<String>,
+    species_registry: Res<BallSpeciesRegistry>,
+    mut unit_name: Local<String>,
+    unit_registry: Res<UnitRegistry>,
+    mut team_config: ResMut<TeamConfig>,
+    mut match_rules: ResMut<MatchRules>,
+    mut economy: ResMut<EconomyConfig>,
+    mut combat_config: ResMut<cmp_combat::CombatConfig>,
+    mut debris_config: ResMut<cmp_debris::DebrisConfig>,
+    mut cleanup_config: ResMut<cmp_cleanup::CleanupConfig>,
+    mut trail_config: ResMut<cmp_trajectory::TrailConfig>,
+    mut artillery_control: Local<cmp_artillery_manual::Control>,
     ){
     window_clicked.0 = false;
     let mut new_edit_mode = None;
@@ -1078,11 +1368,26 @@ fn spawn_map_object (
 
         ui.horizontal(|ui: &mut egui::Ui| {
             ui.label("Artillery Auto");
-            if ui.button("o").clicked() {
-                new_edit_mode = Some(EditContext::Spawn(MapObject::ArtilleryAutoP1));
+            for team in team_config.teams() {
+                if ui.button("o").clicked() {
+                    new_edit_mode = Some(EditContext::Spawn(MapObject::ArtilleryAuto(team)));
+                }
             }
-            if ui.button("o").clicked() {
-                new_edit_mode = Some(EditContext::Spawn(MapObject::ArtilleryAutoP2));
+        });
+
+        ui.horizontal(|ui: &mut egui::Ui| {
+            ui.label("Artillery Manual");
+            egui::ComboBox::from_id_source("artillery_control")
+                .selected_text(format!("{:?}", *artillery_control))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut *artillery_control, cmp_artillery_manual::Control::Mouse, "Mouse");
+                    ui.selectable_value(&mut *artillery_control, cmp_artillery_manual::Control::KeysLeft, "KeysLeft");
+                    ui.selectable_value(&mut *artillery_control, cmp_artillery_manual::Control::KeysRight, "KeysRight");
+                });
+            for team in team_config.teams() {
+                if ui.button("o").clicked() {
+                    new_edit_mode = Some(EditContext::Spawn(MapObject::ArtilleryManual(team, *artillery_control)));
+                }
             }
         });
 
@@ -1134,6 +1439,38 @@ fn spawn_map_object (
             }
         });
 
+        ui.horizontal(|ui: &mut egui::Ui| {
+            ui.label("Goal Bucket");
+            if ui.button("Spawn").clicked() {
+                new_edit_mode = Some(EditContext::Spawn(MapObject::GoalBucket));
+            }
+        });
+
+        ui.horizontal(|ui: &mut egui::Ui| {
+            ui.label("Math Gate");
+            if ui.button("Spawn").clicked() {
+                new_edit_mode = Some(EditContext::Spawn(MapObject::GateMath(false)));
+            }
+            if ui.button("Pair").clicked() {
+                new_edit_mode = Some(EditContext::Spawn(MapObject::GateMath(true)));
+            }
+        });
+
+        ui.horizontal(|ui: &mut egui::Ui| {
+            ui.label("Sorter Gate");
+            if ui.button("Spawn").clicked() {
+                new_edit_mode = Some(EditContext::Spawn(MapObject::GateSorter(None)));
+            }
+            // teleports the filtered balls onto the selected object
+            if ui.button("to selected").clicked() {
+                if let EditContext::Edit(_, entity_vec, EditTool::Select) = edit_mode.clone() {
+                    if entity_vec.len() == 1 {
+                        new_edit_mode = Some(EditContext::Spawn(MapObject::GateSorter(Some(entity_vec[0].index()))));
+                    }
+                }
+            }
+        });
+
         ui.horizontal(|ui: &mut egui::Ui| {
             ui.label("Splitter Gate Left");
             if ui.button("o").clicked() {
@@ -1142,7 +1479,7 @@ fn spawn_map_object (
                     if entity_vec.len() == 2 {
                         new_edit_mode = Some(EditContext::Spawn(MapObject::GateSplitter(
                                                 vec![SpawnBall(entity_vec[0].index(), BallType::Zundamon),
-                                                    SpawnBall(entity_vec[1].index(), BallType::Type1P1)]
+                                                    SpawnBall(entity_vec[1].index(), BallType::Unit(String::from("type1"), Team(0)))]
                                     )));
                     }
                 }
@@ -1154,7 +1491,7 @@ fn spawn_map_object (
                     if entity_vec.len() == 2 {
                         let mut v = vec![SpawnBall(entity_vec[0].index(), BallType::Zundamon)];
                         for i in 0..10 {
-                            v.push(SpawnBall(entity_vec[1].index(), BallType::Type2P1));
+                            v.push(SpawnBall(entity_vec[1].index(), BallType::Unit(String::from("type2"), Team(0))));
                         }
                         new_edit_mode = Some(EditContext::Spawn(MapObject::GateSplitter(v)));
                     }
@@ -1167,7 +1504,7 @@ fn spawn_map_object (
                     if entity_vec.len() == 2 {
                         new_edit_mode = Some(EditContext::Spawn(MapObject::GateSplitter(
                                                 vec![SpawnBall(entity_vec[0].index(), BallType::Zundamon),
-                                                    SpawnBall(entity_vec[1].index(), BallType::Type3P1)]
+                                                    SpawnBall(entity_vec[1].index(), BallType::Unit(String::from("type3"), Team(0)))]
                                     )));
                     }
                 }
@@ -1179,8 +1516,8 @@ fn spawn_map_object (
                     if entity_vec.len() == 2 {
                         new_edit_mode = Some(EditContext::Spawn(MapObject::GateSplitter(
                                                 vec![SpawnBall(entity_vec[0].index(), BallType::Zundamon),
-                                                    SpawnBall(entity_vec[1].index(), BallType::Type4P1),
-                                                    SpawnBall(entity_vec[1].index(), BallType::Type4P1)]
+                                                    SpawnBall(entity_vec[1].index(), BallType::Unit(String::from("type4"), Team(0))),
+                                                    SpawnBall(entity_vec[1].index(), BallType::Unit(String::from("type4"), Team(0)))]
                                     )));
                     }
                 }
@@ -1207,7 +1544,7 @@ fn spawn_map_object (
                     if entity_vec.len() == 2 {
                         new_edit_mode = Some(EditContext::Spawn(MapObject::GateSplitter(
                                                 vec![SpawnBall(entity_vec[0].index(), BallType::Zombie), 
-                                                    SpawnBall(entity_vec[1].index(), BallType::Type1P2)]
+                                                    SpawnBall(entity_vec[1].index(), BallType::Unit(String::from("type1"), Team(1)))]
                                     )));
                     } else {
                         info!("no entity selected");
@@ -1222,7 +1559,7 @@ fn spawn_map_object (
                     if entity_vec.len() == 2 {
                         let mut v = vec![SpawnBall(entity_vec[0].index(), BallType::Zombie)];
                         for i in 0..10 {
-                            v.push(SpawnBall(entity_vec[1].index(), BallType::Type2P2));
+                            v.push(SpawnBall(entity_vec[1].index(), BallType::Unit(String::from("type2"), Team(1))));
                         }
                         new_edit_mode = Some(EditContext::Spawn(MapObject::GateSplitter(v)));
                     }
@@ -1234,7 +1571,7 @@ fn spawn_map_object (
                     if entity_vec.len() == 2 {
                         new_edit_mode = Some(EditContext::Spawn(MapObject::GateSplitter(
                                                 vec![SpawnBall(entity_vec[0].index(), BallType::Zombie),
-                                                    SpawnBall(entity_vec[1].index(), BallType::Type3P2)]
+                                                    SpawnBall(entity_vec[1].index(), BallType::Unit(String::from("type3"), Team(1)))]
                                     )));
                     }
                 }
@@ -1245,8 +1582,8 @@ fn spawn_map_object (
                     if entity_vec.len() == 2 {
                         new_edit_mode = Some(EditContext::Spawn(MapObject::GateSplitter(
                                                 vec![SpawnBall(entity_vec[0].index(), BallType::Zombie),
-                                                    SpawnBall(entity_vec[1].index(), BallType::Type4P2),
-                                                    SpawnBall(entity_vec[1].index(), BallType::Type4P2)]
+                                                    SpawnBall(entity_vec[1].index(), BallType::Unit(String::from("type4"), Team(1))),
+                                                    SpawnBall(entity_vec[1].index(), BallType::Unit(String::from("type4"), Team(1)))]
                                     )));
                     }
                 }
@@ -1265,6 +1602,31 @@ fn spawn_map_object (
             }
         });
 
+        ui.horizontal(|ui: &mut egui::Ui| {
+            ui.label("Splitter Gate (unit)");
+            egui::ComboBox::from_id_source("unit")
+                .selected_text(unit_name.clone())
+                .show_ui(ui, |ui: &mut egui::Ui| {
+                    for name in unit_registry.names() {
+                        ui.selectable_value(&mut *unit_name, name.clone(), name);
+                    }
+                });
+            for team in team_config.teams() {
+                let ball_type = if team.0 % 2 == 0 { BallType::Zundamon } else { BallType::Zombie };
+                if ui.button("o").clicked() {
+                    info!("Splitter Gate spawn start");
+                    if let EditContext::Edit(_, entity_vec, EditTool::Select) = edit_mode.clone() {
+                        if entity_vec.len() == 2 && unit_registry.get(&unit_name).is_some() {
+                            new_edit_mode = Some(EditContext::Spawn(MapObject::GateSplitter(
+                                                    vec![SpawnBall(entity_vec[0].index(), ball_type),
+                                                        SpawnBall(entity_vec[1].index(), BallType::Unit(unit_name.clone(), team))]
+                                        )));
+                        }
+                    }
+                }
+            }
+        });
+
         ui.horizontal(|ui: &mut egui::Ui| {
             ui.label("Teleport Gate");
             if ui.button("Spawn").clicked() {
@@ -1305,6 +1667,22 @@ fn spawn_map_object (
             }
         });
 
+        ui.horizontal(|ui: &mut egui::Ui| {
+            ui.label("Conveyor");
+            if ui.button("Spawn").clicked() {
+                new_edit_mode = Some(EditContext::Spawn(MapObject::Conveyor(Vec::new(), Vec::new())));
+            }
+        });
+
+        ui.horizontal(|ui: &mut egui::Ui| {
+            ui.label("Force Zone");
+            for kind in cmp_force_zone::ForceKind::LIST {
+                if ui.button(kind.name()).clicked() {
+                    new_edit_mode = Some(EditContext::Spawn(MapObject::ForceZone(kind)));
+                }
+            }
+        });
+
         ui.horizontal(|ui: &mut egui::Ui| {
             ui.label("Box");
             if ui.button("o").clicked() {
@@ -1372,6 +1750,31 @@ fn spawn_map_object (
             }
         });
 
+        ui.horizontal(|ui: &mut egui::Ui| {
+            ui.label("Spawn Timer (species)");
+            egui::ComboBox::from_id_source("species")
+                .selected_text(species_name.clone())
+                .show_ui(ui, |ui: &mut egui::Ui| {
+                    for name in species_registry.names() {
+                        ui.selectable_value(&mut *species_name, name.clone(), name);
+                    }
+                });
+            if ui.button("o").clicked() {
+                info!("Spawn Timer spawn start");
+                if let EditContext::Edit(_, entity_vec, EditTool::Select) = edit_mode.clone() {
+                    if entity_vec.len() == 1 && species_registry.get(&species_name).is_some() {
+                        new_edit_mode = Some(EditContext::Spawn(MapObject::SpawnTimer(
+                                    vec![SpawnBall(entity_vec[0].index(), BallType::Species(species_name.clone()))]
+                                    )));
+                    } else {
+                        info!("invalid number of selection or unknown species");
+                    }
+                } else {
+                    info!("target not selected");
+                }
+            }
+        });
+
         ui.horizontal(|ui: &mut egui::Ui| {
             ui.label("Sprite");
             ui.text_edit_singleline(&mut *handle_name);
@@ -1398,29 +1801,179 @@ fn spawn_map_object (
 
         ui.separator();
         ui.horizontal(|ui: &mut egui::Ui| {
-            ui.label("Breakable");
-            if ui.button("o").clicked() {
-                if let EditContext::Edit(_, entity_vec, edit_tool) = edit_mode.clone() {
-                    new_edit_mode = Some(EditContext::Spawn(MapObject::BreakableP1(entity_vec)));
-                }
+            ui.label("Teams");
+            ui.add(egui::DragValue::new(&mut team_config.num_teams).clamp_range(1..=cmp_team::MAX_TEAMS));
+            ui.checkbox(&mut team_config.friendly_fire, "friendly fire");
+            ui.checkbox(&mut team_config.free_for_all, "free for all");
+        });
+
+        ui.horizontal(|ui: &mut egui::Ui| {
+            ui.label("Explosion");
+            ui.label("knockback");
+            ui.add(egui::DragValue::new(&mut combat_config.explosion_knockback).clamp_range(0.0..=200.0));
+            ui.label("wall damage");
+            ui.add(egui::DragValue::new(&mut combat_config.explosion_structure_damage).speed(0.01).clamp_range(0.0..=1.0));
+            ui.checkbox(&mut combat_config.carve_terrain, "carve terrain");
+        });
+
+        ui.horizontal(|ui: &mut egui::Ui| {
+            ui.checkbox(&mut debris_config.enabled, "Debris");
+            ui.label("pieces");
+            ui.add(egui::DragValue::new(&mut debris_config.pieces).clamp_range(2..=32));
+            ui.label("life time");
+            ui.add(egui::DragValue::new(&mut debris_config.life_time).clamp_range(0.5..=30.0));
+        });
+
+        ui.horizontal(|ui: &mut egui::Ui| {
+            ui.label("Kill Zone");
+            if ui.button("Spawn").clicked() {
+                new_edit_mode = Some(EditContext::Spawn(MapObject::KillZone));
             }
-            if ui.button("o").clicked() {
-                if let EditContext::Edit(_, entity_vec, edit_tool) = edit_mode.clone() {
-                    new_edit_mode = Some(EditContext::Spawn(MapObject::BreakableP2(entity_vec)));
+            let mut bounded = cleanup_config.bounds.is_some();
+            ui.checkbox(&mut bounded, "bounds");
+            if bounded != cleanup_config.bounds.is_some() {
+                cleanup_config.bounds = if bounded { Some((Vec2::splat(-5000.0), Vec2::splat(5000.0))) } else { None };
+            }
+            if let Some((min, max)) = cleanup_config.bounds.as_mut() {
+                ui.add(egui::DragValue::new(&mut min.x).speed(10.0));
+                ui.add(egui::DragValue::new(&mut min.y).speed(10.0));
+                ui.label("-");
+                ui.add(egui::DragValue::new(&mut max.x).speed(10.0));
+                ui.add(egui::DragValue::new(&mut max.y).speed(10.0));
+            }
+        });
+
+        ui.horizontal(|ui: &mut egui::Ui| {
+            let mut limited = cleanup_config.life_time.is_some();
+            ui.checkbox(&mut limited, "ball life time");
+            if limited != cleanup_config.life_time.is_some() {
+                cleanup_config.life_time = if limited { Some(30.0) } else { None };
+            }
+            if let Some(life_time) = cleanup_config.life_time.as_mut() {
+                ui.add(egui::DragValue::new(life_time).clamp_range(1.0..=3600.0));
+            }
+            let mut capped = cleanup_config.max_balls.is_some();
+            ui.checkbox(&mut capped, "max balls");
+            if capped != cleanup_config.max_balls.is_some() {
+                cleanup_config.max_balls = if capped { Some(3000) } else { None };
+            }
+            if let Some(max_balls) = cleanup_config.max_balls.as_mut() {
+                ui.add(egui::DragValue::new(max_balls).clamp_range(1..=100000));
+            }
+            if cleanup_config.max_balls.is_some() {
+                for (overflow, name) in [(cmp_cleanup::Overflow::RemoveOldest, "oldest"),
+                                         (cmp_cleanup::Overflow::RemoveLowest, "lowest"),
+                                         (cmp_cleanup::Overflow::RejectNew, "newest")] {
+                    ui.radio_value(&mut cleanup_config.overflow, overflow, name);
                 }
             }
         });
 
         ui.horizontal(|ui: &mut egui::Ui| {
-            ui.label("Breakable Sync");
-            if ui.button("o").clicked() {
-                if let EditContext::Edit(_, entity_vec, edit_tool) = edit_mode.clone() {
-                    new_edit_mode = Some(EditContext::Spawn(MapObject::BreakableSyncP1(entity_vec)));
+            ui.checkbox(&mut trail_config.enabled, "Trail");
+            ui.label("length");
+            ui.add(egui::DragValue::new(&mut trail_config.length).clamp_range(2..=600));
+            ui.label("width");
+            ui.add(egui::DragValue::new(&mut trail_config.width).clamp_range(0.5..=20.0));
+            ui.color_edit_button_rgb(&mut trail_config.color);
+        });
+
+        ui.horizontal(|ui: &mut egui::Ui| {
+            ui.label("Match");
+            ui.label("countdown");
+            ui.add(egui::DragValue::new(&mut match_rules.countdown).clamp_range(0.0..=10.0));
+            ui.checkbox(&mut match_rules.destroy_core, "destroy core");
+            ui.checkbox(&mut match_rules.eliminate, "eliminate");
+        });
+
+        ui.horizontal(|ui: &mut egui::Ui| {
+            let mut survive = match_rules.survive.is_some();
+            ui.checkbox(&mut survive, "survive");
+            if survive {
+                let mut rule = match_rules.survive.unwrap_or(cmp_match::SurviveRule { team: Team(0), seconds: 60.0 });
+                let mut team = rule.team.0 + 1;
+                ui.label("team");
+                ui.add(egui::DragValue::new(&mut team).clamp_range(1..=team_config.num_teams));
+                ui.label("seconds");
+                ui.add(egui::DragValue::new(&mut rule.seconds).clamp_range(1.0..=3600.0));
+                rule.team = Team(team - 1);
+                match_rules.survive = Some(rule);
+            } else {
+                match_rules.survive = None;
+            }
+
+            let mut timeout = match_rules.timeout.is_some();
+            ui.checkbox(&mut timeout, "most units at");
+            if timeout {
+                let mut seconds = match_rules.timeout.unwrap_or(120.0);
+                ui.add(egui::DragValue::new(&mut seconds).clamp_range(1.0..=3600.0));
+                match_rules.timeout = Some(seconds);
+            } else {
+                match_rules.timeout = None;
+            }
+        });
+
+        ui.horizontal(|ui: &mut egui::Ui| {
+            ui.checkbox(&mut economy.enabled, "Economy");
+            ui.label("start");
+            ui.add(egui::DragValue::new(&mut economy.starting_funds).clamp_range(0.0..=10000.0));
+            ui.label("income/s");
+            ui.add(egui::DragValue::new(&mut economy.income_per_sec).clamp_range(0.0..=1000.0));
+            ui.label("kill reward");
+            ui.add(egui::DragValue::new(&mut economy.kill_reward).speed(0.05).clamp_range(0.0..=10.0));
+        });
+
+        for team in team_config.teams() {
+            ui.horizontal(|ui: &mut egui::Ui| {
+                ui.label(format!("Player {}", team.0 + 1));
+                let player = economy.player_mut(team);
+
+                // assign the selected generic gate
+                if ui.button("gate").clicked() {
+                    if let EditContext::Edit(MapObject::GateGeneric, pick, _) = edit_mode.clone() {
+                        player.gate = pick.first().map(|e| e.index());
+                    }
+                }
+                ui.label(format!("{:?}", player.gate));
+
+                egui::ComboBox::from_id_source(("controller", team.0))
+                    .selected_text(format!("{:?}", player.controller))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut player.controller, cmp_economy::Controller::Keyboard, "Keyboard");
+                        ui.selectable_value(&mut player.controller, cmp_economy::Controller::Script, "Script");
+                        ui.selectable_value(&mut player.controller, cmp_economy::Controller::Ai, "Ai");
+                    });
+
+                if player.controller == cmp_economy::Controller::Ai {
+                    egui::ComboBox::from_id_source(("difficulty", team.0))
+                        .selected_text(format!("{:?}", player.difficulty))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut player.difficulty, cmp_ai::Difficulty::Easy, "Easy");
+                            ui.selectable_value(&mut player.difficulty, cmp_ai::Difficulty::Normal, "Normal");
+                            ui.selectable_value(&mut player.difficulty, cmp_ai::Difficulty::Hard, "Hard");
+                        });
+                }
+            });
+        }
+
+        ui.horizontal(|ui: &mut egui::Ui| {
+            ui.label("Breakable");
+            for team in team_config.teams() {
+                if ui.button("o").clicked() {
+                    if let EditContext::Edit(_, entity_vec, edit_tool) = edit_mode.clone() {
+                        new_edit_mode = Some(EditContext::Spawn(MapObject::Breakable(entity_vec, team)));
+                    }
                 }
             }
-            if ui.button("o").clicked() {
-                if let EditContext::Edit(_, entity_vec, edit_tool) = edit_mode.clone() {
-                    new_edit_mode = Some(EditContext::Spawn(MapObject::BreakableSyncP2(entity_vec)));
+        });
+
+        ui.horizontal(|ui: &mut egui::Ui| {
+            ui.label("Breakable Sync");
+            for team in team_config.teams() {
+                if ui.button("o").clicked() {
+                    if let EditContext::Edit(_, entity_vec, edit_tool) = edit_mode.clone() {
+                        new_edit_mode = Some(EditContext::Spawn(MapObject::BreakableSync(entity_vec, team)));
+                    }
                 }
             }
         });
@@ -1470,6 +2023,36 @@ fn spawn_map_object (
             }
         });
 
+        ui.horizontal(|ui: &mut egui::Ui| {
+            ui.label("PrismaticJoint");
+            if ui.button("o").clicked() {
+                if let EditContext::Edit(_, entity_vec, edit_tool) = edit_mode.clone() {
+                    if entity_vec.len() > 0 {
+                        new_edit_mode = Some(EditContext::Spawn(MapObject::PrismaticJoint(entity_vec)));
+                    } else {
+                        info!("no entity selected");
+                    }
+                } else {
+                    info!("target not selected");
+                }
+            }
+        });
+
+        ui.horizontal(|ui: &mut egui::Ui| {
+            ui.label("RopeJoint");
+            if ui.button("o").clicked() {
+                if let EditContext::Edit(_, entity_vec, edit_tool) = edit_mode.clone() {
+                    if entity_vec.len() > 0 {
+                        new_edit_mode = Some(EditContext::Spawn(MapObject::RopeJoint(entity_vec)));
+                    } else {
+                        info!("no entity selected");
+                    }
+                } else {
+                    info!("target not selected");
+                }
+            }
+        });
+
     });
 
     if new_edit_mode.is_some() {
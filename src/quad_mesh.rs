@@ -0,0 +1,58 @@
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::render::view::NoFrustumCulling;
+use bevy::sprite::{MaterialMesh2dBundle, Mesh2dHandle};
+
+// Vertex colored quads drawn as one mesh that is rebuilt every frame, for
+// trails and particles.
+#[derive(Default)]
+pub struct QuadMesh {
+    positions: Vec<[f32; 3]>,
+    colors: Vec<[f32; 4]>,
+    indices: Vec<u32>,
+}
+
+impl QuadMesh {
+    // corners in winding order, each with its own color
+    pub fn quad(&mut self, corners: [(Vec2, [f32; 4]); 4]) {
+        let base = self.positions.len() as u32;
+        for (p, color) in corners {
+            self.positions.push([p.x, p.y, 0.0]);
+            self.colors.push(color);
+        }
+        self.indices.extend([base, base + 1, base + 2, base, base + 2, base + 3]);
+    }
+
+    // Replaces the mesh of `current`, or spawns the entity carrying
+    // `marker` when there is none yet.
+    pub fn apply(self,
+                 commands: &mut Commands,
+                 meshes: &mut Assets<Mesh>,
+                 materials: &mut Assets<ColorMaterial>,
+                 current: Option<&Mesh2dHandle>,
+                 z: f32,
+                 marker: impl Component) {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 0.0, 1.0]; self.positions.len()]);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; self.positions.len()]);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, self.positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, self.colors);
+        mesh.set_indices(Some(Indices::U32(self.indices)));
+
+        if let Some(handle) = current {
+            *meshes.get_mut(&handle.0).unwrap() = mesh;
+        } else {
+            commands.spawn((
+                marker,
+                MaterialMesh2dBundle {
+                    mesh: meshes.add(mesh).into(),
+                    material: materials.add(ColorMaterial::default()),
+                    transform: Transform::from_xyz(0.0, 0.0, z),
+                    ..default()
+                },
+                // the bounds change every frame
+                NoFrustumCulling,
+            ));
+        }
+    }
+}